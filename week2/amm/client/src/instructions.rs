@@ -0,0 +1,417 @@
+use anchor_amm::{accounts, instruction, Config, PoolType};
+use anchor_lang::{
+    prelude::{AccountMeta, Pubkey},
+    solana_program::instruction::Instruction,
+    system_program, InstructionData, ToAccountMetas,
+};
+use anchor_spl::{
+    associated_token::{self, get_associated_token_address_with_program_id},
+    token::spl_token,
+};
+
+use crate::pda::{
+    config_address, event_authority_address, limit_order_address, lp_lock_address, lp_mint_address,
+    protocol_vault_address, vault_address,
+};
+use crate::ID;
+
+/// Every address a pool's instructions touch
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolKeys {
+    pub seed: u64,
+    pub config: Pubkey,
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub mint_lp: Pubkey,
+    pub lp_lock: Pubkey,
+    pub vault_x: Pubkey,
+    pub vault_y: Pubkey,
+    pub protocol_vault_x: Pubkey,
+    pub protocol_vault_y: Pubkey,
+    pub token_program_x: Pubkey,
+    pub token_program_y: Pubkey,
+    /// Token program of the LP mint
+    pub token_program: Pubkey,
+}
+
+impl PoolKeys {
+    /// Derives the keys of the pool with `seed`. The LP mint is assumed to be
+    /// a classic SPL token, set `token_program` afterwards if it isn't.
+    pub fn new(
+        seed: u64,
+        mint_x: Pubkey,
+        mint_y: Pubkey,
+        token_program_x: Pubkey,
+        token_program_y: Pubkey,
+    ) -> Self {
+        let config = config_address(seed);
+
+        Self {
+            seed,
+            config,
+            mint_x,
+            mint_y,
+            mint_lp: lp_mint_address(&config),
+            lp_lock: lp_lock_address(&config),
+            vault_x: vault_address(&config, &mint_x, &token_program_x),
+            vault_y: vault_address(&config, &mint_y, &token_program_y),
+            protocol_vault_x: protocol_vault_address(&config, &mint_x),
+            protocol_vault_y: protocol_vault_address(&config, &mint_y),
+            token_program_x,
+            token_program_y,
+            token_program: spl_token::ID,
+        }
+    }
+
+    /// Keys of an existing pool. Token programs aren't stored on chain, they
+    /// are the owners of the mints.
+    pub fn from_config(config: &Config, token_program_x: Pubkey, token_program_y: Pubkey) -> Self {
+        Self::new(
+            config.seed,
+            config.mint_x,
+            config.mint_y,
+            token_program_x,
+            token_program_y,
+        )
+    }
+
+    fn user_token_accounts(&self, user: &Pubkey) -> (Pubkey, Pubkey, Pubkey) {
+        (
+            get_associated_token_address_with_program_id(user, &self.mint_x, &self.token_program_x),
+            get_associated_token_address_with_program_id(user, &self.mint_y, &self.token_program_y),
+            get_associated_token_address_with_program_id(user, &self.mint_lp, &self.token_program),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize(
+        &self,
+        admin: Pubkey,
+        fee: u16,
+        protocol_fee: u16,
+        authority: Option<Pubkey>,
+        pool_type: PoolType,
+        amp: u64,
+        weight_x: u16,
+    ) -> Instruction {
+        let accounts = accounts::Initialize {
+            admin,
+            mint_x: self.mint_x,
+            mint_y: self.mint_y,
+            config: self.config,
+            mint_lp: self.mint_lp,
+            lp_lock: self.lp_lock,
+            vault_x: self.vault_x,
+            vault_y: self.vault_y,
+            protocol_vault_x: self.protocol_vault_x,
+            protocol_vault_y: self.protocol_vault_y,
+            token_program_x: self.token_program_x,
+            token_program_y: self.token_program_y,
+            token_program: self.token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: event_authority_address(),
+            program: ID,
+        };
+        let data = instruction::Initialize {
+            seed: self.seed,
+            fee,
+            protocol_fee,
+            authority,
+            pool_type,
+            amp,
+            weight_x,
+        };
+
+        build(accounts.to_account_metas(None), data.data())
+    }
+
+    pub fn deposit(
+        &self,
+        user: Pubkey,
+        amount: u64,
+        max_x: u64,
+        max_y: u64,
+        expiration: i64,
+    ) -> Instruction {
+        let (user_token_account_x, user_token_account_y, user_token_account_lp) =
+            self.user_token_accounts(&user);
+
+        let accounts = accounts::Deposit {
+            user,
+            mint_x: self.mint_x,
+            mint_y: self.mint_y,
+            config: self.config,
+            mint_lp: self.mint_lp,
+            vault_x: self.vault_x,
+            vault_y: self.vault_y,
+            user_token_account_x,
+            user_token_account_y,
+            user_token_account_lp,
+            lp_lock: self.lp_lock,
+            token_program_x: self.token_program_x,
+            token_program_y: self.token_program_y,
+            token_program: self.token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: event_authority_address(),
+            program: ID,
+        };
+        let data = instruction::Deposit {
+            amount,
+            max_x,
+            max_y,
+            expiration,
+        };
+
+        build(accounts.to_account_metas(None), data.data())
+    }
+
+    pub fn withdraw(
+        &self,
+        user: Pubkey,
+        amount: u64,
+        min_x: u64,
+        min_y: u64,
+        expiration: i64,
+    ) -> Instruction {
+        let (user_token_account_x, user_token_account_y, user_token_account_lp) =
+            self.user_token_accounts(&user);
+
+        let accounts = accounts::Withdraw {
+            user,
+            mint_x: self.mint_x,
+            mint_y: self.mint_y,
+            config: self.config,
+            mint_lp: self.mint_lp,
+            vault_x: self.vault_x,
+            vault_y: self.vault_y,
+            user_token_account_x,
+            user_token_account_y,
+            user_token_account_lp,
+            token_program_x: self.token_program_x,
+            token_program_y: self.token_program_y,
+            token_program: self.token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: event_authority_address(),
+            program: ID,
+        };
+        let data = instruction::Withdraw {
+            amount,
+            min_x,
+            min_y,
+            expiration,
+        };
+
+        build(accounts.to_account_metas(None), data.data())
+    }
+
+    pub fn swap(
+        &self,
+        user: Pubkey,
+        is_x: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+        expiration: i64,
+    ) -> Instruction {
+        let data = instruction::Swap {
+            is_x,
+            amount_in,
+            min_amount_out,
+            expiration,
+        };
+
+        build(self.swap_accounts(user), data.data())
+    }
+
+    pub fn swap_exact_out(
+        &self,
+        user: Pubkey,
+        is_x: bool,
+        amount_out: u64,
+        max_amount_in: u64,
+        expiration: i64,
+    ) -> Instruction {
+        let data = instruction::SwapExactOut {
+            is_x,
+            amount_out,
+            max_amount_in,
+            expiration,
+        };
+
+        build(self.swap_accounts(user), data.data())
+    }
+
+    /// Account metas of a `Swap` on this pool, also what `swap_route` expects
+    /// for each hop in its remaining accounts.
+    pub fn swap_accounts(&self, user: Pubkey) -> Vec<AccountMeta> {
+        let (user_token_account_x, user_token_account_y, _) = self.user_token_accounts(&user);
+
+        accounts::Swap {
+            user,
+            mint_x: self.mint_x,
+            mint_y: self.mint_y,
+            config: self.config,
+            mint_lp: self.mint_lp,
+            vault_x: self.vault_x,
+            vault_y: self.vault_y,
+            user_token_account_x,
+            user_token_account_y,
+            protocol_vault_x: self.protocol_vault_x,
+            protocol_vault_y: self.protocol_vault_y,
+            token_program_x: self.token_program_x,
+            token_program_y: self.token_program_y,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: event_authority_address(),
+            program: ID,
+        }
+        .to_account_metas(None)
+    }
+
+    /// Escrows `amount_in` of x (or y) from `owner` until the pool pays at
+    /// least `limit_amount_out` for it.
+    #[allow(clippy::too_many_arguments)]
+    pub fn place_limit_order(
+        &self,
+        owner: Pubkey,
+        seed: u64,
+        is_x: bool,
+        amount_in: u64,
+        limit_amount_out: u64,
+        tip: u64,
+        expiration: i64,
+    ) -> Instruction {
+        let (mint_in, token_program) = self.side(is_x);
+        let order = limit_order_address(&self.config, &owner, seed);
+
+        let accounts = accounts::PlaceLimitOrder {
+            owner,
+            mint_in,
+            config: self.config,
+            order,
+            order_vault: get_associated_token_address_with_program_id(
+                &order,
+                &mint_in,
+                &token_program,
+            ),
+            owner_token_account_in: get_associated_token_address_with_program_id(
+                &owner,
+                &mint_in,
+                &token_program,
+            ),
+            token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        };
+        let data = instruction::PlaceLimitOrder {
+            seed,
+            amount_in,
+            limit_amount_out,
+            tip,
+            expiration,
+        };
+
+        build(accounts.to_account_metas(None), data.data())
+    }
+
+    /// Fills `owner`'s order `seed`, selling x if `is_x`, with `keeper`
+    /// collecting the tip.
+    pub fn fill_limit_order(
+        &self,
+        keeper: Pubkey,
+        owner: Pubkey,
+        seed: u64,
+        is_x: bool,
+    ) -> Instruction {
+        let (mint_in, token_program_in) = self.side(is_x);
+        let (mint_out, token_program_out) = self.side(!is_x);
+        let order = limit_order_address(&self.config, &owner, seed);
+
+        let accounts = accounts::FillLimitOrder {
+            keeper,
+            owner,
+            mint_in,
+            mint_out,
+            config: self.config,
+            mint_lp: self.mint_lp,
+            order,
+            order_vault: get_associated_token_address_with_program_id(
+                &order,
+                &mint_in,
+                &token_program_in,
+            ),
+            vault_in: if is_x { self.vault_x } else { self.vault_y },
+            vault_out: if is_x { self.vault_y } else { self.vault_x },
+            protocol_vault_in: if is_x {
+                self.protocol_vault_x
+            } else {
+                self.protocol_vault_y
+            },
+            owner_token_account_out: get_associated_token_address_with_program_id(
+                &owner,
+                &mint_out,
+                &token_program_out,
+            ),
+            token_program_in,
+            token_program_out,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: event_authority_address(),
+            program: ID,
+        };
+
+        build(
+            accounts.to_account_metas(None),
+            instruction::FillLimitOrder {}.data(),
+        )
+    }
+
+    pub fn cancel_limit_order(&self, owner: Pubkey, seed: u64, is_x: bool) -> Instruction {
+        let (mint_in, token_program) = self.side(is_x);
+        let order = limit_order_address(&self.config, &owner, seed);
+
+        let accounts = accounts::CancelLimitOrder {
+            owner,
+            mint_in,
+            config: self.config,
+            order,
+            order_vault: get_associated_token_address_with_program_id(
+                &order,
+                &mint_in,
+                &token_program,
+            ),
+            owner_token_account_in: get_associated_token_address_with_program_id(
+                &owner,
+                &mint_in,
+                &token_program,
+            ),
+            token_program,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        };
+
+        build(
+            accounts.to_account_metas(None),
+            instruction::CancelLimitOrder {}.data(),
+        )
+    }
+
+    /// Mint and token program of x if `is_x`, of y otherwise
+    fn side(&self, is_x: bool) -> (Pubkey, Pubkey) {
+        if is_x {
+            (self.mint_x, self.token_program_x)
+        } else {
+            (self.mint_y, self.token_program_y)
+        }
+    }
+}
+
+fn build(accounts: Vec<AccountMeta>, data: Vec<u8>) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts,
+        data,
+    }
+}
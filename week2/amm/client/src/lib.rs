@@ -0,0 +1,11 @@
+//! Off-chain companion to the `anchor-amm` program: PDA derivation, typed
+//! instruction builders, account deserializers and a pool simulator that runs
+//! the program's own math.
+pub mod instructions;
+pub mod pda;
+pub mod simulator;
+pub mod state;
+
+pub use anchor_amm::ID;
+pub use instructions::PoolKeys;
+pub use simulator::PoolSimulator;
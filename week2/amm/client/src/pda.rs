@@ -0,0 +1,48 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+
+use crate::ID;
+
+pub fn config_address(seed: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"config", seed.to_le_bytes().as_ref()], &ID).0
+}
+
+pub fn lp_mint_address(config: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"lp", config.as_ref()], &ID).0
+}
+
+/// Token account holding the LP locked by the first deposit
+pub fn lp_lock_address(config: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"lp_lock", config.as_ref()], &ID).0
+}
+
+/// The pool's reserves of `mint`, an associated token account of `config`
+pub fn vault_address(config: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(config, mint, token_program)
+}
+
+pub fn protocol_vault_address(config: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"protocol", config.as_ref(), mint.as_ref()], &ID).0
+}
+
+pub fn oracle_address(config: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"oracle", config.as_ref()], &ID).0
+}
+
+pub fn limit_order_address(config: &Pubkey, owner: &Pubkey, seed: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"order",
+            config.as_ref(),
+            owner.as_ref(),
+            seed.to_le_bytes().as_ref(),
+        ],
+        &ID,
+    )
+    .0
+}
+
+/// Signer of the self-CPIs that carry the program's events
+pub fn event_authority_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"__event_authority"], &ID).0
+}
@@ -0,0 +1,277 @@
+use anchor_amm::{
+    deposit_amounts, error::AmmError, fee_of, fee_on_top, withdraw_amounts, withdraw_quote, Config,
+    DepositQuote, SwapQuote, TransferFee, WithdrawQuote, MINIMUM_LIQUIDITY,
+};
+use anchor_lang::{err, require, AccountDeserialize, Result};
+use anchor_spl::token_interface::{
+    spl_token_2022::{
+        extension::{
+            transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions,
+        },
+        state::Mint as MintState,
+    },
+    Mint, TokenAccount,
+};
+
+use crate::state::deserialize_config;
+
+/// Off-chain copy of a pool, advanced with the same functions the program
+/// calls so it fails and rounds exactly where the program does, and left
+/// untouched when an operation fails. The TWAP oracle is not modelled.
+#[derive(Clone)]
+pub struct PoolSimulator {
+    pub config: Config,
+    pub reserve_x: u64,
+    pub reserve_y: u64,
+    pub lp_supply: u64,
+    /// Unix timestamp the next operation runs at, for amp ramps and dynamic fees
+    pub now: i64,
+    /// Slot the next operation runs in, for the circuit breaker window
+    pub slot: u64,
+    /// Token-2022 transfer fee of each mint for the current epoch, `None`
+    /// for mints without one
+    pub transfer_fee_x: Option<TransferFee>,
+    pub transfer_fee_y: Option<TransferFee>,
+}
+
+impl PoolSimulator {
+    /// Starts in slot 0 with no transfer fees, set `slot` before swapping on a
+    /// pool with a price move limit and call `set_transfer_fees` for Token-2022
+    /// mints that charge one.
+    pub fn new(config: Config, reserve_x: u64, reserve_y: u64, lp_supply: u64, now: i64) -> Self {
+        Self {
+            config,
+            reserve_x,
+            reserve_y,
+            lp_supply,
+            now,
+            slot: 0,
+            transfer_fee_x: None,
+            transfer_fee_y: None,
+        }
+    }
+
+    /// Builds a simulator from the raw data of the pool's `Config`, both
+    /// vaults and the LP mint.
+    pub fn from_accounts(
+        config: &[u8],
+        vault_x: &[u8],
+        vault_y: &[u8],
+        mint_lp: &[u8],
+        now: i64,
+    ) -> Result<Self> {
+        let vault_x = TokenAccount::try_deserialize(&mut &vault_x[..])?;
+        let vault_y = TokenAccount::try_deserialize(&mut &vault_y[..])?;
+        let mint_lp = Mint::try_deserialize(&mut &mint_lp[..])?;
+
+        Ok(Self::new(
+            deserialize_config(config)?,
+            vault_x.amount,
+            vault_y.amount,
+            mint_lp.supply,
+            now,
+        ))
+    }
+
+    /// Reads the transfer fees `epoch` charges from the raw data of both
+    /// mints, which may belong to either token program.
+    pub fn set_transfer_fees(&mut self, mint_x: &[u8], mint_y: &[u8], epoch: u64) -> Result<()> {
+        self.transfer_fee_x = epoch_transfer_fee(mint_x, epoch)?;
+        self.transfer_fee_y = epoch_transfer_fee(mint_y, epoch)?;
+        Ok(())
+    }
+
+    /// `swap` without the slippage check.
+    pub fn swap(&mut self, is_x: bool, amount_in: u64) -> Result<SwapQuote> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        self.config.check_no_flash_loan()?;
+        require!(amount_in > 0, AmmError::InvalidAmount);
+
+        let (fee_in, fee_out) = self.transfer_fees(is_x);
+        let received = amount_in - fee_of(fee_in, amount_in)?;
+        let swap = self.config.swap_amounts_at(
+            self.reserve_x,
+            self.reserve_y,
+            self.lp_supply,
+            is_x,
+            received,
+            self.now,
+        )?;
+        let amount_out = swap.amount_out - fee_of(fee_out, swap.amount_out)?;
+
+        self.apply_swap(
+            is_x,
+            (amount_in, received),
+            (swap.amount_out, amount_out),
+            swap.fee,
+        )
+    }
+
+    /// `swap_exact_out` without the slippage check.
+    pub fn swap_exact_out(&mut self, is_x: bool, amount_out: u64) -> Result<SwapQuote> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        self.config.check_no_flash_loan()?;
+        require!(amount_out > 0, AmmError::InvalidAmount);
+
+        let fee = self.config.swap_fee_at(self.now);
+        let (reserve_in, reserve_out) = if is_x {
+            (self.reserve_x, self.reserve_y)
+        } else {
+            (self.reserve_y, self.reserve_x)
+        };
+        let (fee_in, fee_out) = self.transfer_fees(is_x);
+
+        let vault_out = amount_out + fee_on_top(fee_out, amount_out)?;
+        let vault_in = self.config.curve_at(self.now).swap_amount_in(
+            reserve_in,
+            reserve_out,
+            fee,
+            is_x,
+            vault_out,
+        )?;
+        let amount_in = vault_in + fee_on_top(fee_in, vault_in)?;
+        self.config.check_trade_size(vault_in, reserve_in)?;
+
+        let received = amount_in - fee_of(fee_in, amount_in)?;
+        self.apply_swap(is_x, (amount_in, received), (vault_out, amount_out), fee)
+    }
+
+    pub fn deposit(&mut self, amount: u64, max_x: u64, max_y: u64) -> Result<DepositQuote> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        self.config.check_no_flash_loan()?;
+
+        let is_first_deposit = self.lp_supply == 0;
+        let quote = deposit_amounts(
+            self.reserve_x,
+            self.reserve_y,
+            self.lp_supply,
+            amount,
+            max_x,
+            max_y,
+            self.transfer_fee_x.as_ref(),
+            self.transfer_fee_y.as_ref(),
+        )?;
+
+        let received_x = quote.amount_x - fee_of(self.transfer_fee_x.as_ref(), quote.amount_x)?;
+        let received_y = quote.amount_y - fee_of(self.transfer_fee_y.as_ref(), quote.amount_y)?;
+        let minted = if is_first_deposit {
+            quote.lp_amount.checked_add(MINIMUM_LIQUIDITY)
+        } else {
+            Some(quote.lp_amount)
+        };
+
+        let reserve_x = self.reserve_x.checked_add(received_x);
+        let reserve_y = self.reserve_y.checked_add(received_y);
+        let lp_supply = minted.and_then(|minted| self.lp_supply.checked_add(minted));
+        let (Some(reserve_x), Some(reserve_y), Some(lp_supply)) = (reserve_x, reserve_y, lp_supply)
+        else {
+            return err!(AmmError::Overflow);
+        };
+        (self.reserve_x, self.reserve_y, self.lp_supply) = (reserve_x, reserve_y, lp_supply);
+
+        Ok(quote)
+    }
+
+    pub fn withdraw(&mut self, amount: u64, min_x: u64, min_y: u64) -> Result<WithdrawQuote> {
+        self.config.check_no_flash_loan()?;
+
+        let quote = withdraw_quote(
+            self.reserve_x,
+            self.reserve_y,
+            self.lp_supply,
+            amount,
+            min_x,
+            min_y,
+            self.transfer_fee_x.as_ref(),
+            self.transfer_fee_y.as_ref(),
+        )?;
+        let (x, y) = withdraw_amounts(
+            self.reserve_x,
+            self.reserve_y,
+            self.lp_supply,
+            amount,
+            min_x,
+            min_y,
+        )?;
+
+        let reserve_x = self.reserve_x.checked_sub(x);
+        let reserve_y = self.reserve_y.checked_sub(y);
+        let lp_supply = self.lp_supply.checked_sub(amount);
+        let (Some(reserve_x), Some(reserve_y), Some(lp_supply)) = (reserve_x, reserve_y, lp_supply)
+        else {
+            return err!(AmmError::InvalidAmount);
+        };
+        (self.reserve_x, self.reserve_y, self.lp_supply) = (reserve_x, reserve_y, lp_supply);
+
+        Ok(quote)
+    }
+
+    /// Transfer fees of the input and output mints
+    fn transfer_fees(&self, is_x: bool) -> (Option<&TransferFee>, Option<&TransferFee>) {
+        if is_x {
+            (self.transfer_fee_x.as_ref(), self.transfer_fee_y.as_ref())
+        } else {
+            (self.transfer_fee_y.as_ref(), self.transfer_fee_x.as_ref())
+        }
+    }
+
+    /// Moves the reserves the way a swap's transfers do: all of what reached
+    /// the input vault but the protocol's cut in, the output out. `amount_in`
+    /// and `amount_out` pair what the user sends and gets with what the
+    /// vaults receive and send.
+    fn apply_swap(
+        &mut self,
+        is_x: bool,
+        (amount_in, received): (u64, u64),
+        (vault_out, amount_out): (u64, u64),
+        fee: u16,
+    ) -> Result<SwapQuote> {
+        let protocol_fee = self.config.protocol_fee_amount(received, fee)?;
+        let before = (self.reserve_x, self.reserve_y);
+
+        let (reserve_in, reserve_out) = if is_x {
+            (self.reserve_x, self.reserve_y)
+        } else {
+            (self.reserve_y, self.reserve_x)
+        };
+        let reserve_in = reserve_in
+            .checked_add(received - protocol_fee)
+            .ok_or(AmmError::Overflow)?;
+        let reserve_out = reserve_out
+            .checked_sub(vault_out)
+            .ok_or(AmmError::InvalidAmount)?;
+
+        let after = if is_x {
+            (reserve_in, reserve_out)
+        } else {
+            (reserve_out, reserve_in)
+        };
+
+        let mut config = self.config.clone();
+        config.check_price_move_at(before, after, self.slot, self.now)?;
+        config.record_price_move_at(before, after, self.now);
+
+        self.config = config;
+        (self.reserve_x, self.reserve_y) = after;
+
+        Ok(SwapQuote {
+            amount_in,
+            amount_out,
+            fee: (received as u128 * fee as u128 / 10_000) as u64,
+            protocol_fee,
+            price_impact: self.config.price_move_bps_at(before, after, self.now),
+            reserve_x: self.reserve_x,
+            reserve_y: self.reserve_y,
+        })
+    }
+}
+
+/// The transfer fee a mint charges in `epoch`, `None` for legacy mints and
+/// Token-2022 mints without the extension.
+fn epoch_transfer_fee(mint: &[u8], epoch: u64) -> Result<Option<TransferFee>> {
+    let mint = StateWithExtensions::<MintState>::unpack(mint)?;
+    Ok(mint
+        .get_extension::<TransferFeeConfig>()
+        .ok()
+        .map(|config| *config.get_epoch_fee(epoch)))
+}
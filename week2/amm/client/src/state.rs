@@ -0,0 +1,13 @@
+use anchor_lang::{AccountDeserialize, Result};
+
+pub use anchor_amm::{Config, Oracle, PoolType};
+
+/// Parses a `Config` account's data, discriminator included.
+pub fn deserialize_config(data: &[u8]) -> Result<Config> {
+    Config::try_deserialize(&mut &data[..])
+}
+
+/// Parses an `Oracle` account's data, discriminator included.
+pub fn deserialize_oracle(data: &[u8]) -> Result<Oracle> {
+    Oracle::try_deserialize(&mut &data[..])
+}
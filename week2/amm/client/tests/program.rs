@@ -0,0 +1,331 @@
+//! Runs the builders against the compiled program and checks the simulator
+//! lands on the same state. Build it first with `anchor build`, the tests load
+//! `target/deploy/anchor_amm.so`.
+#![allow(deprecated)]
+
+use anchor_amm::{PoolType, NO_EXPIRATION};
+use anchor_amm_client::{PoolKeys, PoolSimulator, ID};
+use anchor_lang::{
+    prelude::{Clock, Pubkey},
+    solana_program::{instruction::Instruction, program_pack::Pack, system_instruction},
+};
+use anchor_spl::{
+    associated_token::{
+        get_associated_token_address,
+        spl_associated_token_account::instruction::create_associated_token_account,
+    },
+    token::spl_token,
+};
+use litesvm::{types::TransactionResult, LiteSVM};
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+
+const PROGRAM_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../target/deploy/anchor_amm.so"
+);
+
+const BALANCE: u64 = 1_000_000_000_000;
+
+struct Harness {
+    svm: LiteSVM,
+    admin: Keypair,
+    keys: PoolKeys,
+}
+
+impl Harness {
+    fn new(fee: u16, protocol_fee: u16) -> Self {
+        let mut svm = LiteSVM::new();
+        svm.add_program_from_file(ID, PROGRAM_PATH)
+            .expect("run `anchor build` before the Rust tests");
+
+        let admin = Keypair::new();
+        svm.airdrop(&admin.pubkey(), 100_000_000_000).unwrap();
+
+        let mut harness = Harness {
+            svm,
+            admin,
+            keys: PoolKeys::new(
+                7,
+                Pubkey::default(),
+                Pubkey::default(),
+                spl_token::ID,
+                spl_token::ID,
+            ),
+        };
+        let mint_x = harness.create_mint();
+        let mint_y = harness.create_mint();
+        harness.keys = PoolKeys::new(7, mint_x, mint_y, spl_token::ID, spl_token::ID);
+
+        let ix = harness.keys.initialize(
+            harness.admin.pubkey(),
+            fee,
+            protocol_fee,
+            Some(harness.admin.pubkey()),
+            PoolType::ConstantProduct,
+            0,
+            0,
+        );
+        let admin = harness.admin.insecure_clone();
+        harness.send(ix, &admin).unwrap();
+        harness
+    }
+
+    /// A funded wallet holding `BALANCE` of both of the pool's tokens
+    fn user(&mut self) -> Keypair {
+        let user = Keypair::new();
+        self.svm.airdrop(&user.pubkey(), 10_000_000_000).unwrap();
+
+        for mint in [self.keys.mint_x, self.keys.mint_y] {
+            let create = create_associated_token_account(
+                &self.admin.pubkey(),
+                &user.pubkey(),
+                &mint,
+                &spl_token::ID,
+            );
+            let mint_to = spl_token::instruction::mint_to(
+                &spl_token::ID,
+                &mint,
+                &get_associated_token_address(&user.pubkey(), &mint),
+                &self.admin.pubkey(),
+                &[],
+                BALANCE,
+            )
+            .unwrap();
+
+            let admin = self.admin.insecure_clone();
+            self.send_all(&[create, mint_to], &[&admin]).unwrap();
+        }
+
+        user
+    }
+
+    /// The pool as the chain sees it right now
+    fn simulator(&self) -> PoolSimulator {
+        let data = |key: &Pubkey| self.svm.get_account(key).unwrap().data;
+        let clock = self.svm.get_sysvar::<Clock>();
+
+        let mut simulator = PoolSimulator::from_accounts(
+            &data(&self.keys.config),
+            &data(&self.keys.vault_x),
+            &data(&self.keys.vault_y),
+            &data(&self.keys.mint_lp),
+            clock.unix_timestamp,
+        )
+        .unwrap();
+        simulator.slot = clock.slot;
+        simulator
+    }
+
+    fn balance(&self, token_account: &Pubkey) -> u64 {
+        self.svm
+            .get_account(token_account)
+            .map(|account| {
+                spl_token::state::Account::unpack(&account.data)
+                    .unwrap()
+                    .amount
+            })
+            .unwrap_or(0)
+    }
+
+    /// `user`'s balances of x, y and LP
+    fn balances(&self, user: &Keypair) -> (u64, u64, u64) {
+        let ata = |mint: &Pubkey| get_associated_token_address(&user.pubkey(), mint);
+
+        (
+            self.balance(&ata(&self.keys.mint_x)),
+            self.balance(&ata(&self.keys.mint_y)),
+            self.balance(&ata(&self.keys.mint_lp)),
+        )
+    }
+
+    fn create_mint(&mut self) -> Pubkey {
+        let mint = Keypair::new();
+        let rent = self
+            .svm
+            .minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN);
+
+        let create = system_instruction::create_account(
+            &self.admin.pubkey(),
+            &mint.pubkey(),
+            rent,
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::ID,
+        );
+        let initialize = spl_token::instruction::initialize_mint2(
+            &spl_token::ID,
+            &mint.pubkey(),
+            &self.admin.pubkey(),
+            None,
+            6,
+        )
+        .unwrap();
+
+        let admin = self.admin.insecure_clone();
+        self.send_all(&[create, initialize], &[&admin, &mint])
+            .unwrap();
+
+        mint.pubkey()
+    }
+
+    fn send(&mut self, ix: Instruction, signer: &Keypair) -> TransactionResult {
+        self.send_all(&[ix], &[signer])
+    }
+
+    fn send_all(&mut self, ixs: &[Instruction], signers: &[&Keypair]) -> TransactionResult {
+        let mut all = vec![ComputeBudgetInstruction::set_compute_unit_limit(1_400_000)];
+        all.extend_from_slice(ixs);
+
+        let tx = Transaction::new_signed_with_payer(
+            &all,
+            Some(&signers[0].pubkey()),
+            signers,
+            self.svm.latest_blockhash(),
+        );
+        let result = self.svm.send_transaction(tx);
+
+        // Identical transactions are otherwise rejected as already processed
+        self.svm.expire_blockhash();
+        result
+    }
+}
+
+/// Sends `ix` and runs `simulate` on a simulator of the pool as it was, then
+/// checks both left the reserves, LP supply and volatility in the same place.
+/// Returns the simulated result and how much x, y and LP the user gained.
+fn step<T>(
+    harness: &mut Harness,
+    user: &Keypair,
+    ix: Instruction,
+    simulate: impl FnOnce(&mut PoolSimulator) -> anchor_lang::Result<T>,
+) -> (Option<T>, (i128, i128, i128)) {
+    let before = harness.simulator();
+    let mut simulator = before.clone();
+    // A failed transaction rolls everything back
+    let simulated = simulate(&mut simulator).ok();
+    if simulated.is_none() {
+        simulator = before;
+    }
+    let user_before = harness.balances(user);
+
+    let result = harness.send(ix, user);
+    assert_eq!(result.is_ok(), simulated.is_some(), "{:?}", result);
+
+    let chain = harness.simulator();
+    assert_eq!(
+        (chain.reserve_x, chain.reserve_y, chain.lp_supply),
+        (
+            simulator.reserve_x,
+            simulator.reserve_y,
+            simulator.lp_supply
+        )
+    );
+    assert_eq!(chain.config.volatility, simulator.config.volatility);
+
+    let user_after = harness.balances(user);
+    let gained = |before: u64, after: u64| after as i128 - before as i128;
+    (
+        simulated,
+        (
+            gained(user_before.0, user_after.0),
+            gained(user_before.1, user_after.1),
+            gained(user_before.2, user_after.2),
+        ),
+    )
+}
+
+#[test]
+fn simulator_matches_the_program() {
+    let mut harness = Harness::new(30, 1_000);
+    let lp = harness.user();
+    let trader = harness.user();
+
+    let ix = harness
+        .keys
+        .deposit(lp.pubkey(), 1, 1_000_000_000, 2_000_000_000, NO_EXPIRATION);
+    let (quote, gained) = step(&mut harness, &lp, ix, |pool| {
+        pool.deposit(1, 1_000_000_000, 2_000_000_000)
+    });
+    let quote = quote.unwrap();
+    assert_eq!(
+        gained,
+        (
+            -(quote.amount_x as i128),
+            -(quote.amount_y as i128),
+            quote.lp_amount as i128
+        )
+    );
+
+    for (is_x, amount) in [(true, 1_000_000), (false, 77_777_777), (true, 1_234)] {
+        let ix = harness
+            .keys
+            .swap(trader.pubkey(), is_x, amount, 0, NO_EXPIRATION);
+        let (quote, (gained_x, gained_y, _)) =
+            step(&mut harness, &trader, ix, |pool| pool.swap(is_x, amount));
+        let quote = quote.unwrap();
+        let (gained_in, gained_out) = if is_x {
+            (gained_x, gained_y)
+        } else {
+            (gained_y, gained_x)
+        };
+        assert_eq!(gained_in, -(quote.amount_in as i128));
+        assert_eq!(gained_out, quote.amount_out as i128);
+    }
+
+    let ix = harness
+        .keys
+        .swap_exact_out(trader.pubkey(), true, 5_000_000, u64::MAX, NO_EXPIRATION);
+    let (quote, (gained_x, gained_y, _)) = step(&mut harness, &trader, ix, |pool| {
+        pool.swap_exact_out(true, 5_000_000)
+    });
+    let quote = quote.unwrap();
+    assert_eq!(
+        (gained_x, gained_y),
+        (-(quote.amount_in as i128), 5_000_000)
+    );
+
+    let ix = harness.keys.deposit(
+        trader.pubkey(),
+        123_456_789,
+        u64::MAX,
+        u64::MAX,
+        NO_EXPIRATION,
+    );
+    let (quote, gained) = step(&mut harness, &trader, ix, |pool| {
+        pool.deposit(123_456_789, u64::MAX, u64::MAX)
+    });
+    let quote = quote.unwrap();
+    assert_eq!(
+        gained,
+        (
+            -(quote.amount_x as i128),
+            -(quote.amount_y as i128),
+            123_456_789
+        )
+    );
+
+    let ix = harness
+        .keys
+        .withdraw(lp.pubkey(), 400_000_000, 0, 0, NO_EXPIRATION);
+    let (quote, gained) = step(&mut harness, &lp, ix, |pool| {
+        pool.withdraw(400_000_000, 0, 0)
+    });
+    let quote = quote.unwrap();
+    assert_eq!(
+        gained,
+        (quote.amount_x as i128, quote.amount_y as i128, -400_000_000)
+    );
+
+    // Rejections agree too
+    let ix = harness
+        .keys
+        .withdraw(lp.pubkey(), 1_000, u64::MAX, 0, NO_EXPIRATION);
+    let (quote, gained) = step(&mut harness, &lp, ix, |pool| {
+        pool.withdraw(1_000, u64::MAX, 0)
+    });
+    assert!(quote.is_none());
+    assert_eq!(gained, (0, 0, 0));
+}
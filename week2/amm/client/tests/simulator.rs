@@ -0,0 +1,225 @@
+use anchor_amm::{error::AmmError, Config, PoolType, TransferFee, MINIMUM_LIQUIDITY};
+use anchor_amm_client::{pda, PoolKeys, PoolSimulator};
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::token::spl_token;
+
+const FEE: u16 = 30;
+const PROTOCOL_FEE: u16 = 1_000;
+
+fn config() -> Config {
+    Config {
+        seed: 42,
+        authority: None,
+        mint_x: Pubkey::new_unique(),
+        mint_y: Pubkey::new_unique(),
+        fee: FEE,
+        protocol_fee: PROTOCOL_FEE,
+        locked: false,
+        config_bump: 0,
+        lp_bump: 0,
+        lp_lock_bump: 0,
+        protocol_x_bump: 0,
+        protocol_y_bump: 0,
+        price_x_cumulative: 0,
+        price_y_cumulative: 0,
+        last_update_ts: 0,
+        last_reserve_x: 0,
+        last_reserve_y: 0,
+        pool_type: PoolType::ConstantProduct,
+        initial_amp: 0,
+        target_amp: 0,
+        ramp_start_ts: 0,
+        ramp_stop_ts: 0,
+        flash_loan_amount: 0,
+        flash_loan_is_x: false,
+        dynamic_fee: false,
+        min_fee: 0,
+        max_fee: 0,
+        volatility: 0,
+        volatility_ts: 0,
+        max_trade_bps: 0,
+        max_price_move_bps: 0,
+        breaker_window: 0,
+        breaker_slot: 0,
+        breaker_reserve_x: 0,
+        breaker_reserve_y: 0,
+        weight_x: 5_000,
+        weight_y: 5_000,
+    }
+}
+
+/// A pool seeded 1:1 with `liquidity` of each token
+fn seeded(liquidity: u64) -> PoolSimulator {
+    let mut pool = PoolSimulator::new(config(), 0, 0, 0, 0);
+    pool.deposit(1, liquidity, liquidity).unwrap();
+    pool
+}
+
+fn state(pool: &PoolSimulator) -> (u64, u64, u64) {
+    (pool.reserve_x, pool.reserve_y, pool.lp_supply)
+}
+
+#[test]
+fn pool_keys_follow_the_config() {
+    let config = config();
+    let keys = PoolKeys::from_config(&config, spl_token::ID, spl_token::ID);
+
+    assert_eq!(keys.config, pda::config_address(42));
+    assert_eq!(keys.mint_lp, pda::lp_mint_address(&keys.config));
+    assert_eq!(
+        keys,
+        PoolKeys::new(
+            42,
+            config.mint_x,
+            config.mint_y,
+            spl_token::ID,
+            spl_token::ID
+        )
+    );
+}
+
+#[test]
+fn first_deposit_locks_minimum_liquidity() {
+    let mut pool = PoolSimulator::new(config(), 0, 0, 0, 0);
+
+    let quote = pool.deposit(1, 1_000_000, 1_000_000).unwrap();
+
+    assert_eq!(quote.lp_amount, 1_000_000 - MINIMUM_LIQUIDITY);
+    assert_eq!(state(&pool), (1_000_000, 1_000_000, 1_000_000));
+}
+
+#[test]
+fn swap_keeps_the_protocol_fee_out_of_the_reserves() {
+    let mut pool = seeded(1_000_000_000);
+
+    let quote = pool.swap(true, 1_000_000).unwrap();
+
+    assert_eq!(quote.fee, 3_000);
+    assert_eq!(quote.protocol_fee, 300);
+    assert_eq!(pool.reserve_x, 1_000_000_000 + 1_000_000 - 300);
+    assert_eq!(pool.reserve_y, 1_000_000_000 - quote.amount_out);
+    assert!(pool.reserve_x as u128 * pool.reserve_y as u128 >= 1_000_000_000u128.pow(2));
+}
+
+#[test]
+fn exact_out_charges_at_least_the_exact_in_price() {
+    let mut pool = seeded(1_000_000_000);
+    let exact_in = pool.clone().swap(false, 1_000_000).unwrap();
+
+    let exact_out = pool.swap_exact_out(false, exact_in.amount_out).unwrap();
+
+    assert_eq!(exact_out.amount_out, exact_in.amount_out);
+    assert!(exact_out.amount_in >= exact_in.amount_in);
+}
+
+#[test]
+fn withdraw_returns_a_pro_rata_share() {
+    let mut pool = seeded(1_000_000_000);
+
+    let quote = pool.withdraw(500_000_000, 0, 0).unwrap();
+
+    assert_eq!((quote.amount_x, quote.amount_y), (500_000_000, 500_000_000));
+    assert_eq!(state(&pool), (500_000_000, 500_000_000, 500_000_000));
+}
+
+#[test]
+fn transfer_fees_come_out_of_the_user_side() {
+    let mut pool = seeded(1_000_000_000);
+    // 1% on every transfer of x
+    pool.transfer_fee_x = Some(TransferFee {
+        epoch: 0.into(),
+        maximum_fee: u64::MAX.into(),
+        transfer_fee_basis_points: 100.into(),
+    });
+
+    // Deposits are grossed up so the vaults keep the pool ratio
+    let deposit = pool.deposit(1_000_000, u64::MAX, u64::MAX).unwrap();
+    assert!(deposit.amount_x > deposit.amount_y);
+    assert_eq!(pool.reserve_x, pool.reserve_y);
+
+    // Withdrawals report what arrives, after the fee
+    let withdraw = pool.withdraw(1_000_000, 0, 0).unwrap();
+    assert_eq!(withdraw.amount_x, 990_000);
+    assert_eq!(withdraw.amount_y, 1_000_000);
+    assert_eq!(
+        pool.withdraw(1_000_000, 1_000_000, 0).unwrap_err(),
+        AmmError::SlippageExceeded.into()
+    );
+
+    // Swaps price what reached the vault
+    let before = pool.reserve_x;
+    let swap = pool.swap(true, 1_000_000).unwrap();
+    assert_eq!(swap.amount_in, 1_000_000);
+    assert_eq!(pool.reserve_x, before + 990_000 - swap.protocol_fee);
+}
+
+#[test]
+fn failed_operations_leave_the_pool_untouched() {
+    let mut pool = seeded(1_000_000_000);
+    let before = state(&pool);
+
+    assert_eq!(
+        pool.deposit(500_000_000, 1, u64::MAX).unwrap_err(),
+        AmmError::SlippageExceeded.into()
+    );
+    assert_eq!(
+        pool.withdraw(500_000_000, u64::MAX, 0).unwrap_err(),
+        AmmError::SlippageExceeded.into()
+    );
+    assert!(pool.withdraw(u64::MAX, 0, 0).is_err());
+    assert_eq!(
+        pool.swap_exact_out(true, 1_000_000_000).unwrap_err(),
+        AmmError::InvalidAmount.into()
+    );
+    assert_eq!(state(&pool), before);
+
+    pool.config.locked = true;
+    assert_eq!(
+        pool.swap(true, 1_000).unwrap_err(),
+        AmmError::PoolLocked.into()
+    );
+    assert_eq!(state(&pool), before);
+}
+
+#[test]
+fn dynamic_fee_follows_the_simulated_clock() {
+    let mut pool = seeded(1_000_000_000);
+    pool.config.dynamic_fee = true;
+    pool.config.min_fee = FEE;
+    pool.config.max_fee = 1_000;
+
+    // A large swap leaves volatility behind that raises the next fee...
+    pool.swap(true, 100_000_000).unwrap();
+    let raised = pool.clone().swap(true, 1_000_000).unwrap();
+    assert!(raised.fee > 3_000);
+
+    // ...until it decays away
+    pool.now += 3_600;
+    let decayed = pool.swap(true, 1_000_000).unwrap();
+    assert_eq!(decayed.fee, 3_000);
+}
+
+#[test]
+fn circuit_breaker_follows_the_simulated_slot() {
+    let mut pool = seeded(1_000_000_000);
+    pool.config.max_trade_bps = 100;
+    pool.config.max_price_move_bps = 200;
+    pool.config.breaker_window = 10;
+
+    assert_eq!(
+        pool.swap(true, 20_000_000).unwrap_err(),
+        AmmError::TradeTooLarge.into()
+    );
+
+    pool.swap(true, 8_000_000).unwrap();
+    let before = pool.clone();
+    assert_eq!(
+        pool.swap(true, 8_000_000).unwrap_err(),
+        AmmError::PriceMoveTooLarge.into()
+    );
+    assert_eq!(state(&pool), state(&before));
+    assert_eq!(pool.config.breaker_slot, before.config.breaker_slot);
+
+    pool.slot += 10;
+    pool.swap(true, 8_000_000).unwrap();
+}
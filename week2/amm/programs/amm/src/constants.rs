@@ -2,3 +2,47 @@ use anchor_lang::prelude::*;
 
 #[constant]
 pub const SEED: &str = "anchor";
+
+/// Pass as `expiration` to skip the deadline check
+#[constant]
+pub const NO_EXPIRATION: i64 = 0;
+
+/// Minimum number of seconds between two stored oracle observations
+#[constant]
+pub const OBSERVATION_INTERVAL: i64 = 60;
+
+/// LP locked forever on the first deposit so the share price can't be inflated
+#[constant]
+pub const MINIMUM_LIQUIDITY: u64 = 1_000;
+
+/// Upper bound on a StableSwap pool's amplification
+#[constant]
+pub const MAX_AMP: u64 = 1_000_000;
+
+/// Shortest allowed amplification ramp, in seconds
+#[constant]
+pub const MIN_RAMP_DURATION: i64 = 86_400;
+
+/// Largest factor a single ramp may move the amplification up or down by
+#[constant]
+pub const MAX_AMP_CHANGE: u64 = 10;
+
+/// Lowest tick a concentrated position can use, price `1.0001^MIN_TICK`
+#[constant]
+pub const MIN_TICK: i32 = -443_636;
+
+/// Highest tick a concentrated position can use, price `1.0001^MAX_TICK`
+#[constant]
+pub const MAX_TICK: i32 = 443_636;
+
+/// Seconds for the dynamic-fee volatility accumulator to decay by half
+#[constant]
+pub const VOLATILITY_HALF_LIFE: i64 = 60;
+
+/// Basis points of accumulated price movement per basis point of extra fee
+#[constant]
+pub const VOLATILITY_FEE_DIVISOR: u64 = 10;
+
+/// Smallest weight either side of a weighted pool may have, in basis points
+#[constant]
+pub const MIN_WEIGHT: u16 = 100;
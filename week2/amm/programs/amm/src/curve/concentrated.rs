@@ -0,0 +1,273 @@
+//! Tick and sqrt-price math for concentrated liquidity, after Uniswap v3 but
+//! with prices in Q64.64 fixed point so they fit a u128.
+//!
+//! Price is `y / x` and tick `i` is the price `1.0001^i`. Pools track
+//! `sqrt(price)` since token amounts are linear in it for a fixed liquidity.
+
+use anchor_lang::prelude::*;
+
+use super::U256;
+use crate::constants::{MAX_TICK, MIN_TICK};
+use crate::error::AmmError;
+
+/// `2^128 / sqrt(1.0001)^(2^i)` for `i` in `1..20`, `i = 0` is handled apart
+const TICK_RATIOS: [u128; 19] = [
+    0xfff97272373d413259a46990580e213a,
+    0xfff2e50f5f656932ef12357cf3c7fdcc,
+    0xffe5caca7e10e4e61c3624eaa0941cd0,
+    0xffcb9843d60f6159c9db58835c926644,
+    0xff973b41fa98c081472e6896dfb254c0,
+    0xff2ea16466c96a3843ec78b326b52861,
+    0xfe5dee046a99a2a811c461f1969c3053,
+    0xfcbe86c7900a88aedcffc83b479aa3a4,
+    0xf987a7253ac413176f2b074cf7815e54,
+    0xf3392b0822b70005940c7a398e4b70f3,
+    0xe7159475a2c29b7443b29c7fa6e889d9,
+    0xd097f3bdfd2022b8845ad8f792aa5825,
+    0xa9f746462d870fdf8a65dc1f90e061e5,
+    0x70d869a156d2a1b890bb3df62baf32f7,
+    0x31be135f97d08fd981231505542fcfa6,
+    0x9aa508b5b7a84e1c677de54f3e99bc9,
+    0x5d6af8dedb81196699c329225ee604,
+    0x2216e584f5fa1ea926041bedfe98,
+    0x48a170391f7dc42444e8fa2,
+];
+
+/// `sqrt(1.0001^tick)` as Q64.64, rounded up.
+pub fn sqrt_price_at_tick(tick: i32) -> Result<u128> {
+    require!((MIN_TICK..=MAX_TICK).contains(&tick), AmmError::InvalidTick);
+
+    let abs_tick = tick.unsigned_abs();
+    let mut ratio = if abs_tick & 1 != 0 {
+        U256::from(0xfffcb933bd6fad37aa2d162d1a594001u128)
+    } else {
+        U256::one() << 128
+    };
+    for (i, r) in TICK_RATIOS.iter().enumerate() {
+        if abs_tick & (2 << i) != 0 {
+            ratio = (ratio * U256::from(*r)) >> 128;
+        }
+    }
+
+    // The ratios are for 1 / sqrt(1.0001), flip for positive ticks
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+
+    let rounding = if (ratio & U256::from(u64::MAX)).is_zero() {
+        0
+    } else {
+        1
+    };
+    Ok((ratio >> 64).as_u128() + rounding)
+}
+
+/// The greatest tick whose sqrt price is at most `sqrt_price`.
+pub fn tick_at_sqrt_price(sqrt_price: u128) -> Result<i32> {
+    require!(
+        sqrt_price >= sqrt_price_at_tick(MIN_TICK)? && sqrt_price < sqrt_price_at_tick(MAX_TICK)?,
+        AmmError::InvalidSqrtPrice
+    );
+
+    let (mut lo, mut hi) = (MIN_TICK, MAX_TICK - 1);
+    while lo < hi {
+        let mid = lo + (hi - lo + 1) / 2;
+        if sqrt_price_at_tick(mid)? <= sqrt_price {
+            lo = mid;
+        } else {
+            hi = mid - 1;
+        }
+    }
+
+    Ok(lo)
+}
+
+fn div_rounding(numerator: U256, denominator: U256, round_up: bool) -> U256 {
+    let quotient = numerator / denominator;
+    if round_up && !(numerator % denominator).is_zero() {
+        quotient + 1
+    } else {
+        quotient
+    }
+}
+
+fn mul(a: U256, b: U256) -> Result<U256> {
+    a.checked_mul(b).ok_or(AmmError::Overflow.into())
+}
+
+fn to_u128(value: U256) -> Result<u128> {
+    u128::try_from(value).map_err(|_| AmmError::Overflow.into())
+}
+
+/// Amount of x backing `liquidity` between two sqrt prices:
+/// `L · (b - a) / (a · b)`.
+pub fn amount_x_delta(
+    sqrt_price_a: u128,
+    sqrt_price_b: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<u128> {
+    let (a, b) = if sqrt_price_a < sqrt_price_b {
+        (sqrt_price_a, sqrt_price_b)
+    } else {
+        (sqrt_price_b, sqrt_price_a)
+    };
+    require!(a > 0, AmmError::InvalidSqrtPrice);
+
+    let numerator = mul(U256::from(liquidity) << 64, U256::from(b - a))?;
+    let amount = div_rounding(
+        div_rounding(numerator, U256::from(b), round_up),
+        U256::from(a),
+        round_up,
+    );
+
+    to_u128(amount)
+}
+
+/// Amount of y backing `liquidity` between two sqrt prices: `L · (b - a)`.
+pub fn amount_y_delta(
+    sqrt_price_a: u128,
+    sqrt_price_b: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<u128> {
+    let (a, b) = if sqrt_price_a < sqrt_price_b {
+        (sqrt_price_a, sqrt_price_b)
+    } else {
+        (sqrt_price_b, sqrt_price_a)
+    };
+
+    let amount = div_rounding(
+        mul(U256::from(liquidity), U256::from(b - a))?,
+        U256::one() << 64,
+        round_up,
+    );
+
+    to_u128(amount)
+}
+
+/// Sqrt price after adding `amount_in` to the pool. Rounds so the price moves
+/// no further than the input pays for.
+pub fn next_sqrt_price_from_input(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount_in: u128,
+    x_to_y: bool,
+) -> Result<u128> {
+    require!(liquidity > 0, AmmError::InvalidAmount);
+
+    if amount_in == 0 {
+        return Ok(sqrt_price);
+    }
+
+    let liquidity = U256::from(liquidity) << 64;
+    let next = if x_to_y {
+        // L · √P / (L + Δx · √P)
+        let product = mul(U256::from(amount_in), U256::from(sqrt_price))?;
+        let denominator = liquidity.checked_add(product).ok_or(AmmError::Overflow)?;
+        div_rounding(mul(liquidity, U256::from(sqrt_price))?, denominator, true)
+    } else {
+        // √P + Δy / L
+        U256::from(sqrt_price) + ((U256::from(amount_in) << 128) / liquidity)
+    };
+
+    to_u128(next)
+}
+
+/// Token amounts backing `liquidity` in `[sqrt_price_lower, sqrt_price_upper)`
+/// with the pool at `sqrt_price`.
+pub fn amounts_for_liquidity(
+    sqrt_price: u128,
+    sqrt_price_lower: u128,
+    sqrt_price_upper: u128,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<(u128, u128)> {
+    if sqrt_price <= sqrt_price_lower {
+        Ok((
+            amount_x_delta(sqrt_price_lower, sqrt_price_upper, liquidity, round_up)?,
+            0,
+        ))
+    } else if sqrt_price < sqrt_price_upper {
+        Ok((
+            amount_x_delta(sqrt_price, sqrt_price_upper, liquidity, round_up)?,
+            amount_y_delta(sqrt_price_lower, sqrt_price, liquidity, round_up)?,
+        ))
+    } else {
+        Ok((
+            0,
+            amount_y_delta(sqrt_price_lower, sqrt_price_upper, liquidity, round_up)?,
+        ))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapStep {
+    pub sqrt_price_next: u128,
+    pub amount_in: u128,
+    pub amount_out: u128,
+    pub fee_amount: u128,
+}
+
+/// One exact-input swap step inside a single liquidity range, stopping at
+/// `sqrt_price_target` if the input is enough to reach it. `amount_in` plus
+/// `fee_amount` never exceeds `amount_remaining`.
+pub fn compute_swap_step(
+    sqrt_price: u128,
+    sqrt_price_target: u128,
+    liquidity: u128,
+    amount_remaining: u128,
+    fee: u16,
+) -> Result<SwapStep> {
+    require!(fee < 10_000, AmmError::InvalidFee);
+
+    let x_to_y = sqrt_price >= sqrt_price_target;
+
+    let amount_remaining_less_fee = amount_remaining
+        .checked_mul(10_000 - fee as u128)
+        .ok_or(AmmError::Overflow)?
+        / 10_000;
+
+    let amount_in_max = if x_to_y {
+        amount_x_delta(sqrt_price_target, sqrt_price, liquidity, true)?
+    } else {
+        amount_y_delta(sqrt_price, sqrt_price_target, liquidity, true)?
+    };
+    let sqrt_price_next = if amount_remaining_less_fee >= amount_in_max {
+        sqrt_price_target
+    } else {
+        next_sqrt_price_from_input(sqrt_price, liquidity, amount_remaining_less_fee, x_to_y)?
+    };
+    let reached_target = sqrt_price_next == sqrt_price_target;
+
+    let (amount_in, amount_out) = if x_to_y {
+        (
+            amount_x_delta(sqrt_price_next, sqrt_price, liquidity, true)?,
+            amount_y_delta(sqrt_price_next, sqrt_price, liquidity, false)?,
+        )
+    } else {
+        (
+            amount_y_delta(sqrt_price, sqrt_price_next, liquidity, true)?,
+            amount_x_delta(sqrt_price, sqrt_price_next, liquidity, false)?,
+        )
+    };
+
+    // Short of the target the whole remainder is spent, the fee is the rest
+    let fee_amount = if reached_target {
+        amount_in
+            .checked_mul(fee as u128)
+            .ok_or(AmmError::Overflow)?
+            .div_ceil(10_000 - fee as u128)
+    } else {
+        amount_remaining
+            .checked_sub(amount_in)
+            .ok_or(AmmError::CurveCalculationError)?
+    };
+
+    Ok(SwapStep {
+        sqrt_price_next,
+        amount_in,
+        amount_out,
+        fee_amount,
+    })
+}
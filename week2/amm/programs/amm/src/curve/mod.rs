@@ -0,0 +1,143 @@
+pub mod concentrated;
+pub mod stable_swap;
+pub mod weighted;
+
+use anchor_lang::prelude::*;
+use constant_product_curve::{ConstantProduct, LiquidityPair};
+
+use crate::error::AmmError;
+
+pub use u256::U256;
+
+// Generated code, lints are the macro's business
+#[allow(clippy::all)]
+mod u256 {
+    uint::construct_uint! {
+        pub struct U256(4);
+    }
+}
+
+/// The invariant a pool prices swaps against, with the parameters in effect
+/// right now. Deposits and withdrawals are proportional to the reserves under
+/// every curve, only swaps need to know which one it is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Curve {
+    ConstantProduct,
+    StableSwap {
+        amp: u64,
+    },
+    /// Weights in basis points, summing to 10 000
+    Weighted {
+        weight_x: u16,
+        weight_y: u16,
+    },
+}
+
+impl Curve {
+    /// Output of an exact-input swap.
+    pub fn swap_amount_out(
+        &self,
+        reserve_x: u64,
+        reserve_y: u64,
+        supply: u64,
+        fee: u16,
+        is_x: bool,
+        amount_in: u64,
+    ) -> Result<u64> {
+        match *self {
+            Curve::ConstantProduct => {
+                let mut curve = ConstantProduct::init(reserve_x, reserve_y, supply, fee, None)
+                    .map_err(AmmError::from)?;
+
+                let p = if is_x {
+                    LiquidityPair::X
+                } else {
+                    LiquidityPair::Y
+                };
+
+                Ok(curve
+                    .swap(p, amount_in, 0)
+                    .map_err(AmmError::from)?
+                    .withdraw)
+            }
+            Curve::StableSwap { amp } => {
+                let (reserve_in, reserve_out) = if is_x {
+                    (reserve_x, reserve_y)
+                } else {
+                    (reserve_y, reserve_x)
+                };
+
+                stable_swap::swap_amount_out(amp, reserve_in, reserve_out, fee, amount_in)
+                    .ok_or(AmmError::CurveCalculationError.into())
+            }
+            Curve::Weighted { weight_x, weight_y } => {
+                let (weight_in, weight_out, reserve_in, reserve_out) = if is_x {
+                    (weight_x, weight_y, reserve_x, reserve_y)
+                } else {
+                    (weight_y, weight_x, reserve_y, reserve_x)
+                };
+
+                weighted::swap_amount_out(
+                    weight_in,
+                    weight_out,
+                    reserve_in,
+                    reserve_out,
+                    fee,
+                    amount_in,
+                )
+                .ok_or(AmmError::CurveCalculationError.into())
+            }
+        }
+    }
+
+    /// Input needed to take exactly `amount_out`, fee included. Rounds up so
+    /// the pool never gives away value. `is_x` is whether x is the side paid in.
+    pub fn swap_amount_in(
+        &self,
+        reserve_in: u64,
+        reserve_out: u64,
+        fee: u16,
+        is_x: bool,
+        amount_out: u64,
+    ) -> Result<u64> {
+        require!(amount_out < reserve_out, AmmError::InvalidAmount);
+        require!(fee < 10_000, AmmError::InvalidFee);
+
+        match *self {
+            Curve::ConstantProduct => {
+                let numerator = (reserve_in as u128)
+                    .checked_mul(amount_out as u128)
+                    .ok_or(AmmError::Overflow)?;
+                let amount_in_after_fee = numerator.div_ceil((reserve_out - amount_out) as u128);
+
+                let amount_in = amount_in_after_fee
+                    .checked_mul(10_000)
+                    .ok_or(AmmError::Overflow)?
+                    .div_ceil(10_000 - fee as u128);
+
+                u64::try_from(amount_in).map_err(|_| AmmError::Overflow.into())
+            }
+            Curve::StableSwap { amp } => {
+                stable_swap::swap_amount_in(amp, reserve_in, reserve_out, fee, amount_out)
+                    .ok_or(AmmError::CurveCalculationError.into())
+            }
+            Curve::Weighted { weight_x, weight_y } => {
+                let (weight_in, weight_out) = if is_x {
+                    (weight_x, weight_y)
+                } else {
+                    (weight_y, weight_x)
+                };
+
+                weighted::swap_amount_in(
+                    weight_in,
+                    weight_out,
+                    reserve_in,
+                    reserve_out,
+                    fee,
+                    amount_out,
+                )
+                .ok_or(AmmError::CurveCalculationError.into())
+            }
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! Two-coin StableSwap invariant, as in Curve's `get_D` / `get_y`:
+//!
+//! `A·n^n·Σx + D = A·n^n·D + D^(n+1) / (n^n·Πx)`
+//!
+//! Close to `x + y = D` around the balanced point and bends into a constant
+//! product as the pool drifts away from it. `amp` is `A·n^(n-1)`, so the
+//! leverage `A·n^n` is `amp * N_COINS`.
+
+use super::U256;
+
+const N_COINS: u64 = 2;
+
+/// Newton's method converges in a handful of rounds, this is only a backstop
+const MAX_ITERATIONS: usize = 256;
+
+/// The invariant `D` for reserves `x` and `y`.
+pub fn compute_d(amp: u64, x: u64, y: u64) -> Option<U256> {
+    let sum = U256::from(x) + U256::from(y);
+    if sum.is_zero() {
+        return Some(U256::zero());
+    }
+    if amp == 0 || x == 0 || y == 0 {
+        return None;
+    }
+
+    let leverage = U256::from(amp.checked_mul(N_COINS)?);
+    let n = U256::from(N_COINS);
+
+    let mut d = sum;
+    for _ in 0..MAX_ITERATIONS {
+        // D^(n+1) / (n^n·Πx), one balance at a time to stay small
+        let mut d_p = d;
+        d_p = d_p.checked_mul(d)? / (U256::from(x) * n);
+        d_p = d_p.checked_mul(d)? / (U256::from(y) * n);
+
+        let d_prev = d;
+        let numerator = (leverage * sum + d_p * n).checked_mul(d)?;
+        let denominator = (leverage.checked_sub(U256::one())?)
+            .checked_mul(d)?
+            .checked_add((n + 1) * d_p)?;
+        d = numerator / denominator;
+
+        if converged(d, d_prev) {
+            return Some(d);
+        }
+    }
+
+    None
+}
+
+/// The balance of one side that keeps the invariant at `d` when the other
+/// side holds `x`.
+pub fn compute_y(amp: u64, x: u64, d: U256) -> Option<U256> {
+    if amp == 0 || x == 0 {
+        return None;
+    }
+
+    let leverage = U256::from(amp.checked_mul(N_COINS)?);
+    let n = U256::from(N_COINS);
+
+    // y^2 + (b - D)·y = c
+    let mut c = d.checked_mul(d)? / (U256::from(x) * n);
+    c = c.checked_mul(d)? / (leverage * n);
+    let b = U256::from(x) + d / leverage;
+
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let y_prev = y;
+        let denominator = (y * U256::from(2u8) + b).checked_sub(d)?;
+        y = y.checked_mul(y)?.checked_add(c)? / denominator;
+
+        if converged(y, y_prev) {
+            return Some(y);
+        }
+    }
+
+    None
+}
+
+/// Marginal price of x in y, `-dy/dx` along the invariant, as Q64.64.
+/// Differentiating the invariant and scaling by `4x²y²/D` gives
+/// `y·(4L·x·p + D²) / (x·(4L·y·p + D²))` with `L = amp·n` and `p = x·y / D`,
+/// which is exactly 1 at balance and tends to `y / x` as `amp` goes to 0.
+/// Saturates at `u128::MAX`.
+pub fn spot_price(amp: u64, x: u64, y: u64) -> Option<u128> {
+    if x == 0 || y == 0 {
+        return None;
+    }
+
+    let d = compute_d(amp, x, y)?;
+    let leverage4 = U256::from(amp.checked_mul(N_COINS * 4)?);
+    let p = U256::from(x) * U256::from(y) / d;
+    let d2 = d.checked_mul(d)?;
+
+    let weight_x = (leverage4 * U256::from(x))
+        .checked_mul(p)?
+        .checked_add(d2)?;
+    let weight_y = (leverage4 * U256::from(y))
+        .checked_mul(p)?
+        .checked_add(d2)?;
+
+    // (y / x) · (weight_x / weight_y), each factor in Q64.64
+    let ratio = (U256::from(y) << 64) / U256::from(x);
+    let adjustment = (weight_x << 64) / weight_y;
+    let price = ratio
+        .checked_mul(adjustment)
+        .map(|price| price >> 64)
+        .unwrap_or(U256::MAX);
+
+    Some(u128::try_from(price).unwrap_or(u128::MAX))
+}
+
+fn converged(a: U256, b: U256) -> bool {
+    if a > b {
+        a - b <= U256::one()
+    } else {
+        b - a <= U256::one()
+    }
+}
+
+/// Output of an exact-input swap, fee taken from the input. Rounds the output
+/// down by one so Newton's tolerance always works in the pool's favour.
+pub fn swap_amount_out(
+    amp: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee: u16,
+    amount_in: u64,
+) -> Option<u64> {
+    let amount_in_after_fee = (amount_in as u128 * (10_000 - fee as u128) / 10_000) as u64;
+
+    let d = compute_d(amp, reserve_in, reserve_out)?;
+    let new_reserve_out = compute_y(amp, reserve_in.checked_add(amount_in_after_fee)?, d)?;
+
+    let amount_out = U256::from(reserve_out)
+        .checked_sub(new_reserve_out)?
+        .saturating_sub(U256::one());
+
+    u64::try_from(amount_out).ok()
+}
+
+/// Input needed to take exactly `amount_out`, fee included. Rounds up
+/// everywhere so the pool never gives away value.
+pub fn swap_amount_in(
+    amp: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee: u16,
+    amount_out: u64,
+) -> Option<u64> {
+    if amount_out >= reserve_out || fee >= 10_000 {
+        return None;
+    }
+
+    let d = compute_d(amp, reserve_in, reserve_out)?;
+    let new_reserve_in = compute_y(amp, reserve_out - amount_out, d)? + 1;
+
+    let amount_in_after_fee =
+        u128::try_from(new_reserve_in.checked_sub(U256::from(reserve_in))?).ok()?;
+    let amount_in = amount_in_after_fee
+        .checked_mul(10_000)?
+        .div_ceil(10_000 - fee as u128);
+
+    u64::try_from(amount_in).ok()
+}
@@ -0,0 +1,130 @@
+//! Two-coin weighted invariant, as in Balancer:
+//!
+//! `x^w_x · y^w_y = V`
+//!
+//! Weights are in basis points and sum to 10 000, an 80/20 pool holds 80% of
+//! its value in x. Equal weights give the constant product. Fractional powers
+//! go through `ln` and `exp` in Q64.64, so everything fits in a `u128`.
+
+const ONE: u128 = 1 << 64;
+
+/// `ln(2)` in Q64.64
+const LN_2: u128 = 12_786_308_645_202_655_660;
+
+/// Every power is rounded up by `2^-POW_MARGIN_SHIFT` of itself, far more
+/// than the error of `ln` and `exp`, so rounding always favours the pool
+const POW_MARGIN_SHIFT: u32 = 40;
+
+/// Output of an exact-input swap, fee taken from the input:
+/// `reserve_out · (1 - (reserve_in / (reserve_in + amount_in))^(weight_in / weight_out))`
+pub fn swap_amount_out(
+    weight_in: u16,
+    weight_out: u16,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee: u16,
+    amount_in: u64,
+) -> Option<u64> {
+    if reserve_in == 0 || reserve_out == 0 {
+        return None;
+    }
+
+    let amount_in = amount_in as u128 * 10_000u128.checked_sub(fee as u128)? / 10_000;
+    let base = (reserve_in as u128 * ONE).div_ceil(reserve_in as u128 + amount_in);
+    let ratio = pow_up(base, weight_in, weight_out)?;
+    if ratio >= ONE {
+        return Some(0);
+    }
+
+    u64::try_from((reserve_out as u128 * (ONE - ratio)) >> 64).ok()
+}
+
+/// Input needed to take exactly `amount_out`, fee included:
+/// `reserve_in · ((reserve_out / (reserve_out - amount_out))^(weight_out / weight_in) - 1)`
+/// grossed up by the fee. Rounds up.
+pub fn swap_amount_in(
+    weight_in: u16,
+    weight_out: u16,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee: u16,
+    amount_out: u64,
+) -> Option<u64> {
+    if reserve_in == 0 || amount_out >= reserve_out {
+        return None;
+    }
+
+    let base = (reserve_out as u128 * ONE).div_ceil((reserve_out - amount_out) as u128);
+    let ratio = pow_up(base, weight_out, weight_in)?;
+
+    let amount_in = (reserve_in as u128)
+        .checked_mul(ratio.saturating_sub(ONE))?
+        .div_ceil(ONE);
+    let amount_in = amount_in
+        .checked_mul(10_000)?
+        .div_ceil(10_000u128.checked_sub(fee as u128)?);
+
+    u64::try_from(amount_in).ok()
+}
+
+/// `base^(numerator / denominator)` for a Q64.64 `base`, rounded up.
+pub fn pow_up(base: u128, numerator: u16, denominator: u16) -> Option<u128> {
+    if denominator == 0 {
+        return None;
+    }
+
+    // Truncating towards zero lowers a positive exponent by under 2^-64, and
+    // the power by about as much of itself, well inside the margin below
+    let exponent = ln(base)?.checked_mul(numerator as i128)? / denominator as i128;
+    let power = exp(exponent)?;
+
+    power.checked_add((power >> POW_MARGIN_SHIFT) + 1)
+}
+
+/// Natural logarithm of a Q64.64 number, in signed Q64.64.
+pub fn ln(x: u128) -> Option<i128> {
+    if x == 0 {
+        return None;
+    }
+
+    // x = m · 2^k with m in [1, 2)
+    let k = 63 - x.leading_zeros() as i128;
+    let m = if k >= 0 { x >> k } else { x << -k };
+
+    // ln(m) = 2·atanh(z) = 2·(z + z³/3 + z⁵/5 + …) with z = (m - 1) / (m + 1) < 1/3
+    let z = ((m - ONE) << 64) / (m + ONE);
+    let z_squared = (z * z) >> 64;
+
+    let (mut term, mut sum, mut n) = (z, 0u128, 1u128);
+    while term != 0 {
+        sum += term / n;
+        term = (term * z_squared) >> 64;
+        n += 2;
+    }
+
+    Some(k * LN_2 as i128 + 2 * sum as i128)
+}
+
+/// `e^y` for a signed Q64.64 `y`, in Q64.64. None if it doesn't fit.
+pub fn exp(y: i128) -> Option<u128> {
+    // e^y = 2^k · e^r with r in [0, ln 2)
+    let k = y.div_euclid(LN_2 as i128);
+    let r = y.rem_euclid(LN_2 as i128) as u128;
+
+    // Taylor series, every term is below one so the products fit
+    let (mut term, mut sum, mut n) = (ONE, ONE, 1u128);
+    while term != 0 {
+        term = ((term * r) >> 64) / n;
+        sum += term;
+        n += 1;
+    }
+
+    // e^r < 2, so shifting left by up to 62 still fits
+    if k >= 0 {
+        (k <= 62).then(|| sum << k)
+    } else if k > -128 {
+        Some(sum >> -k)
+    } else {
+        Some(0)
+    }
+}
@@ -9,6 +9,60 @@ pub enum AmmError {
     SlippageExceeded,
     #[msg("Curve calculation error")]
     CurveCalculationError,
+    #[msg("Pool is locked")]
+    PoolLocked,
+    #[msg("Pool has no authority and cannot be updated")]
+    NoAuthority,
+    #[msg("Signer is not the pool authority")]
+    InvalidAuthority,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+    #[msg("Invalid fee provided")]
+    InvalidFee,
+    #[msg("Transaction has expired")]
+    TransactionExpired,
+    #[msg("Pool has no liquidity")]
+    PoolEmpty,
+    #[msg("Token account does not match the pool mint")]
+    InvalidMint,
+    #[msg("Not enough oracle history for the requested window")]
+    OracleWindowTooLong,
+    #[msg("Initial deposit is below the minimum liquidity")]
+    InsufficientInitialLiquidity,
+    #[msg("Route does not connect")]
+    InvalidRoute,
+    #[msg("Invalid amplification coefficient")]
+    InvalidAmp,
+    #[msg("Amplification ramp is too short")]
+    RampTooShort,
+    #[msg("Operation is not supported by this pool type")]
+    InvalidPoolType,
+    #[msg("Tick is out of range or not on the tick spacing")]
+    InvalidTick,
+    #[msg("Sqrt price is out of range")]
+    InvalidSqrtPrice,
+    #[msg("Tick array does not match the pool or tick")]
+    InvalidTickArray,
+    #[msg("Signer does not hold the position")]
+    InvalidPositionOwner,
+    #[msg("Position still holds liquidity or fees")]
+    PositionNotEmpty,
+    #[msg("A flash loan is outstanding")]
+    FlashLoanActive,
+    #[msg("Flash borrow has no matching repay later in the transaction")]
+    MissingFlashRepay,
+    #[msg("No flash loan to repay")]
+    NoFlashLoan,
+    #[msg("Pool would not pay the order's limit amount")]
+    LimitPriceNotReached,
+    #[msg("Trade is larger than the pool allows")]
+    TradeTooLarge,
+    #[msg("Trade moves the price further than the pool allows in this window")]
+    PriceMoveTooLarge,
+    #[msg("Invalid circuit breaker limits")]
+    InvalidCircuitBreaker,
+    #[msg("Pool weights are out of range")]
+    InvalidWeight,
 }
 
 impl From<CurveError> for AmmError {
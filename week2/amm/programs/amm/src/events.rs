@@ -0,0 +1,77 @@
+use anchor_lang::prelude::*;
+
+use crate::state::PoolType;
+
+#[event]
+pub struct PoolInitialized {
+    pub config: Pubkey,
+    pub authority: Option<Pubkey>,
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub mint_lp: Pubkey,
+    pub fee: u16,
+    pub protocol_fee: u16,
+    pub pool_type: PoolType,
+    pub amp: u64,
+    pub weight_x: u16,
+    pub weight_y: u16,
+}
+
+/// `amount_in` is what reached the vault, `fee` is the whole fee taken out of
+/// it and `protocol_fee` the part of that sent to the protocol vault. The
+/// reserves are those of the pool that swapped, `sqrt_price` and `liquidity`
+/// are where a concentrated-liquidity swap left that pool, `None` otherwise.
+#[event]
+pub struct SwapEvent {
+    pub config: Pubkey,
+    pub user: Pubkey,
+    pub is_x: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee: u64,
+    pub protocol_fee: u64,
+    pub reserve_x: u64,
+    pub reserve_y: u64,
+    pub sqrt_price: Option<u128>,
+    pub liquidity: Option<u128>,
+}
+
+/// Amounts are what reached the vaults. `fee` is the fee on the internal swap
+/// of a single-sided deposit or on a repaid flash loan, `None` otherwise.
+/// Flash loan repays and concentrated positions are reported here too,
+/// `lp_amount` is zero and `lp_supply` `None` where no LP token is involved. The reserves are those of
+/// the pool the tokens went into, `liquidity` is where a concentrated position
+/// left that pool, `None` otherwise.
+#[event]
+pub struct DepositEvent {
+    pub config: Pubkey,
+    pub user: Pubkey,
+    pub amount_x: u64,
+    pub amount_y: u64,
+    pub lp_amount: u64,
+    pub fee: Option<u64>,
+    pub reserve_x: u64,
+    pub reserve_y: u64,
+    pub lp_supply: Option<u64>,
+    pub liquidity: Option<u128>,
+}
+
+/// Amounts are what reached the user. `fee` is the fee on the internal swap
+/// of a single-sided withdrawal, `None` otherwise. Flash loans, collected fees
+/// and concentrated positions are reported here too, `lp_amount` is zero and
+/// `lp_supply` `None` where no LP token is involved. The reserves are those of
+/// the pool the tokens left, `liquidity` is where a concentrated position left
+/// that pool, `None` otherwise.
+#[event]
+pub struct WithdrawEvent {
+    pub config: Pubkey,
+    pub user: Pubkey,
+    pub amount_x: u64,
+    pub amount_y: u64,
+    pub lp_amount: u64,
+    pub fee: Option<u64>,
+    pub reserve_x: u64,
+    pub reserve_y: u64,
+    pub lp_supply: Option<u64>,
+    pub liquidity: Option<u128>,
+}
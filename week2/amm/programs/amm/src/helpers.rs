@@ -0,0 +1,354 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer};
+use anchor_spl::token::spl_token;
+use anchor_spl::token_interface::{
+    close_account, get_mint_extension_data, harvest_withheld_tokens_to_mint, mint_to,
+    spl_token_2022, spl_token_2022::extension::transfer_fee::TransferFeeConfig, sync_native,
+    transfer_checked, CloseAccount, HarvestWithheldTokensToMint, Mint, MintTo, SyncNative,
+    TokenAccount, TransferChecked,
+};
+
+pub use anchor_spl::token_interface::spl_token_2022::extension::transfer_fee::TransferFee;
+
+use crate::constants::NO_EXPIRATION;
+use crate::error::AmmError;
+use crate::state::{ClPool, Config, SwapAmounts};
+
+pub fn check_expiration(expiration: i64) -> Result<()> {
+    if expiration == NO_EXPIRATION {
+        return Ok(());
+    }
+
+    require!(
+        Clock::get()?.unix_timestamp <= expiration,
+        AmmError::TransactionExpired
+    );
+
+    Ok(())
+}
+
+/// The mint's Token-2022 transfer fee in the current epoch, `None` for mints
+/// without the extension.
+pub fn epoch_transfer_fee(mint: &AccountInfo) -> Result<Option<TransferFee>> {
+    match get_mint_extension_data::<TransferFeeConfig>(mint) {
+        Ok(transfer_fee_config) => Ok(Some(
+            *transfer_fee_config.get_epoch_fee(Clock::get()?.epoch),
+        )),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Amount a sender has to add on top of `amount` so that `amount` still arrives
+/// after the mint's Token-2022 transfer fee. Zero for mints without the extension.
+pub fn transfer_fee_on_top(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    fee_on_top(epoch_transfer_fee(mint)?.as_ref(), amount)
+}
+
+/// Amount the mint's Token-2022 transfer fee takes out of a transfer of
+/// `amount`. Zero for mints without the extension.
+pub fn transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    fee_of(epoch_transfer_fee(mint)?.as_ref(), amount)
+}
+
+/// `transfer_fee_on_top` for a known fee, `None` meaning no fee.
+pub fn fee_on_top(transfer_fee: Option<&TransferFee>, amount: u64) -> Result<u64> {
+    match transfer_fee {
+        Some(transfer_fee) => Ok(transfer_fee
+            .calculate_inverse_fee(amount)
+            .ok_or(AmmError::Overflow)?),
+        None => Ok(0),
+    }
+}
+
+/// `transfer_fee` for a known fee, `None` meaning no fee.
+pub fn fee_of(transfer_fee: Option<&TransferFee>, amount: u64) -> Result<u64> {
+    match transfer_fee {
+        Some(transfer_fee) => Ok(transfer_fee
+            .calculate_fee(amount)
+            .ok_or(AmmError::Overflow)?),
+        None => Ok(0),
+    }
+}
+
+/// Moves the Token-2022 transfer fees withheld in `token_account` to the mint,
+/// which has to happen before the account can be closed. Anyone can harvest,
+/// so no authority signs. Does nothing for mints without the extension.
+pub fn harvest_withheld_fees<'info>(
+    token_program: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    token_account: AccountInfo<'info>,
+) -> Result<()> {
+    if get_mint_extension_data::<TransferFeeConfig>(&mint).is_err() {
+        return Ok(());
+    }
+
+    let cpi_accounts = HarvestWithheldTokensToMint {
+        token_program_id: token_program.clone(),
+        mint,
+    };
+    harvest_withheld_tokens_to_mint(
+        CpiContext::new(token_program, cpi_accounts),
+        vec![token_account],
+    )
+}
+
+/// Whether `mint` is wrapped SOL, under either token program
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+    *mint == spl_token::native_mint::ID || *mint == spl_token_2022::native_mint::ID
+}
+
+/// Tops a wrapped SOL account up from `owner`'s native SOL until it holds
+/// `amount`, so it can be spent like any other token account.
+pub fn wrap_sol<'info>(
+    owner: AccountInfo<'info>,
+    token_account: &mut InterfaceAccount<'info, TokenAccount>,
+    amount: u64,
+    token_program: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+) -> Result<()> {
+    let shortfall = amount.saturating_sub(token_account.amount);
+    if shortfall == 0 {
+        return Ok(());
+    }
+
+    let cpi_accounts = Transfer {
+        from: owner,
+        to: token_account.to_account_info(),
+    };
+    transfer(CpiContext::new(system_program, cpi_accounts), shortfall)?;
+
+    let cpi_accounts = SyncNative {
+        account: token_account.to_account_info(),
+    };
+    sync_native(CpiContext::new(token_program, cpi_accounts))?;
+
+    token_account.reload()
+}
+
+/// Whether `token_account` is wrapped SOL that held nothing when the
+/// instruction started, so it only carries what the instruction itself wraps
+/// or pays out and can be unwrapped on the way out. Wrapped SOL a user already
+/// held stays wrapped, in an account that stays open.
+pub fn is_transient_native(mint: &Pubkey, token_account: &TokenAccount) -> bool {
+    is_native_mint(mint) && token_account.amount == 0
+}
+
+/// Closes a wrapped SOL account, handing its balance and rent back to `owner`
+/// as plain SOL. Does nothing if it was already closed earlier on.
+pub fn unwrap_sol<'info>(
+    owner: AccountInfo<'info>,
+    token_account: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+) -> Result<()> {
+    if token_account.lamports() == 0 {
+        return Ok(());
+    }
+
+    let cpi_accounts = CloseAccount {
+        account: token_account,
+        destination: owner.clone(),
+        authority: owner,
+    };
+    close_account(CpiContext::new(token_program, cpi_accounts))
+}
+
+/// Moves `amount` of `mint` from `from` to `to`, signed by `authority`, or by
+/// the PDA `signer_seeds` derive when it's one. Returns the amount that arrived
+/// in `to` after any transfer fee.
+pub fn transfer_tokens<'info>(
+    from: AccountInfo<'info>,
+    to: &mut InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    authority: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<u64> {
+    let cpi_accounts = TransferChecked {
+        from,
+        to: to.to_account_info(),
+        mint: mint.to_account_info(),
+        authority,
+    };
+
+    let before = to.amount;
+
+    let cpi_ctx = CpiContext::new_with_signer(token_program, cpi_accounts, signer_seeds);
+    transfer_checked(cpi_ctx, amount, mint.decimals)?;
+
+    to.reload()?;
+    Ok(to.amount - before)
+}
+
+/// `transfer_tokens` out of an account the pool's config owns, one of its
+/// vaults or protocol vaults.
+pub fn transfer_from_pool<'info>(
+    config: &Account<'info, Config>,
+    from: AccountInfo<'info>,
+    to: &mut InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: AccountInfo<'info>,
+    amount: u64,
+) -> Result<u64> {
+    transfer_tokens(
+        from,
+        to,
+        mint,
+        config.to_account_info(),
+        token_program,
+        &[&config.signer_seeds().as_seeds()],
+        amount,
+    )
+}
+
+/// `transfer_from_pool` for the concentrated pool, whose vaults it owns itself.
+pub fn transfer_from_cl_pool<'info>(
+    cl_pool: &Account<'info, ClPool>,
+    from: AccountInfo<'info>,
+    to: &mut InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: AccountInfo<'info>,
+    amount: u64,
+) -> Result<u64> {
+    transfer_tokens(
+        from,
+        to,
+        mint,
+        cl_pool.to_account_info(),
+        token_program,
+        &[&cl_pool.signer_seeds()],
+        amount,
+    )
+}
+
+/// Mints `amount` of the pool's LP token to `to`.
+pub fn mint_lp<'info>(
+    config: &Account<'info, Config>,
+    mint_lp: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    let cpi_accounts = MintTo {
+        mint: mint_lp,
+        to,
+        authority: config.to_account_info(),
+    };
+
+    let seeds = config.signer_seeds();
+    let signer_seeds: &[&[&[u8]]] = &[&seeds.as_seeds()];
+
+    let cpi_ctx = CpiContext::new_with_signer(token_program, cpi_accounts, signer_seeds);
+    mint_to(cpi_ctx, amount)
+}
+
+/// Moves the protocol's cut of the fee on `amount_in` from a pool vault to the
+/// protocol vault of the same mint, the rest of the fee stays with LPs.
+/// Returns the amount moved.
+pub fn transfer_protocol_fee<'info>(
+    config: &Account<'info, Config>,
+    vault: AccountInfo<'info>,
+    protocol_vault: &mut InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: AccountInfo<'info>,
+    amount_in: u64,
+    fee: u16,
+) -> Result<u64> {
+    let amount = config.protocol_fee_amount(amount_in, fee)?;
+    if amount == 0 {
+        return Ok(0);
+    }
+
+    transfer_from_pool(config, vault, protocol_vault, mint, token_program, amount)?;
+
+    Ok(amount)
+}
+
+/// The pool's half of an exact-input swap once `received` has reached
+/// `vault_in`, shared by `swap` and `fill_limit_order`: prices it against the
+/// reserves from before it arrived, moves the protocol's cut of the fee and
+/// the output to `to`, then runs the circuit breaker, feeds the price move
+/// into the dynamic fee and syncs the oracle's reserves. Returns the priced
+/// swap and what arrived in `to`.
+#[allow(clippy::too_many_arguments)]
+pub fn settle_swap<'info>(
+    config: &mut Account<'info, Config>,
+    supply: u64,
+    is_x: bool,
+    (reserve_x, reserve_y): (u64, u64),
+    received: u64,
+    vault_in: &mut InterfaceAccount<'info, TokenAccount>,
+    vault_out: &mut InterfaceAccount<'info, TokenAccount>,
+    protocol_vault_in: &mut InterfaceAccount<'info, TokenAccount>,
+    mint_in: &InterfaceAccount<'info, Mint>,
+    mint_out: &InterfaceAccount<'info, Mint>,
+    token_program_in: AccountInfo<'info>,
+    token_program_out: AccountInfo<'info>,
+    to: &mut InterfaceAccount<'info, TokenAccount>,
+) -> Result<(SwapAmounts, u64)> {
+    let swap = config.swap_amounts(reserve_x, reserve_y, supply, is_x, received)?;
+
+    transfer_protocol_fee(
+        config,
+        vault_in.to_account_info(),
+        protocol_vault_in,
+        mint_in,
+        token_program_in,
+        received,
+        swap.fee,
+    )?;
+
+    let amount_out = transfer_from_pool(
+        config,
+        vault_out.to_account_info(),
+        to,
+        mint_out,
+        token_program_out,
+        swap.amount_out,
+    )?;
+
+    vault_in.reload()?;
+    vault_out.reload()?;
+    let after = if is_x {
+        (vault_in.amount, vault_out.amount)
+    } else {
+        (vault_out.amount, vault_in.amount)
+    };
+    config.check_price_move((reserve_x, reserve_y), after)?;
+    config.record_price_move((reserve_x, reserve_y), after)?;
+    config.sync_reserves(after.0, after.1);
+
+    Ok((swap, amount_out))
+}
+
+/// `amount` LP's share of `reserve`, out of `supply` LP. Deposits round it up
+/// and withdrawals down, so rounding never moves value from the pool to the
+/// user.
+pub fn lp_share(reserve: u64, amount: u64, supply: u64, round_up: bool) -> Result<u64> {
+    require!(supply != 0, AmmError::CurveCalculationError);
+
+    let numerator = reserve as u128 * amount as u128;
+    let share = if round_up {
+        numerator.div_ceil(supply as u128)
+    } else {
+        numerator / supply as u128
+    };
+    Ok(u64::try_from(share).map_err(|_| AmmError::Overflow)?)
+}
+
+/// Integer square root, rounded down
+pub fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+
+    // Newton's method from an initial guess that is always above the root
+    let mut x = 1u128 << ((128 - n.leading_zeros()).div_ceil(2));
+    loop {
+        let y = (x + n / x) / 2;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
@@ -0,0 +1,99 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{close_account, CloseAccount, Mint, TokenAccount, TokenInterface},
+};
+
+use crate::error::AmmError;
+use crate::helpers;
+use crate::state::{Config, LimitOrder};
+
+#[derive(Accounts)]
+pub struct CancelLimitOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    // Writable to harvest the escrow's withheld transfer fees into
+    #[account(mut, mint::token_program = token_program)]
+    pub mint_in: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [
+            b"order",
+            config.key().as_ref(),
+            owner.key().as_ref(),
+            order.seed.to_le_bytes().as_ref(),
+        ],
+        bump = order.bump,
+        has_one = config,
+        has_one = owner,
+        constraint = order.mints(&config).0 == mint_in.key() @ AmmError::InvalidMint,
+    )]
+    pub order: Account<'info, LimitOrder>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_in,
+        associated_token::authority = order,
+        associated_token::token_program = token_program,
+    )]
+    pub order_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = mint_in,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub owner_token_account_in: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CancelLimitOrder<'info> {
+    /// Returns the escrow to the owner and closes the order, tip included.
+    /// Expired orders can still be cancelled.
+    pub fn cancel_limit_order(&mut self) -> Result<()> {
+        let seeds = self.order.signer_seeds();
+        let signer_seeds: &[&[&[u8]]] = &[&seeds.as_seeds()];
+
+        helpers::transfer_tokens(
+            self.order_vault.to_account_info(),
+            &mut self.owner_token_account_in,
+            &self.mint_in,
+            self.order.to_account_info(),
+            self.token_program.to_account_info(),
+            signer_seeds,
+            self.order_vault.amount,
+        )?;
+
+        helpers::harvest_withheld_fees(
+            self.token_program.to_account_info(),
+            self.mint_in.to_account_info(),
+            self.order_vault.to_account_info(),
+        )?;
+
+        let cpi_accounts = CloseAccount {
+            account: self.order_vault.to_account_info(),
+            destination: self.owner.to_account_info(),
+            authority: self.order.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        close_account(cpi_ctx)
+    }
+}
@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::helpers;
+use crate::state::{Farm, Stake};
+
+#[derive(Accounts)]
+pub struct Claim<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"farm", farm.config.as_ref()],
+        bump = farm.bump,
+        has_one = reward_mint,
+    )]
+    pub farm: Account<'info, Farm>,
+
+    #[account(
+        mut,
+        seeds = [b"stake", farm.key().as_ref(), owner.key().as_ref()],
+        bump = stake.bump,
+        has_one = farm,
+        has_one = owner,
+    )]
+    pub stake: Account<'info, Stake>,
+
+    #[account(mint::token_program = reward_token_program)]
+    pub reward_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = farm,
+        associated_token::token_program = reward_token_program,
+    )]
+    pub reward_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = reward_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = reward_token_program,
+    )]
+    pub user_reward_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub reward_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Claim<'info> {
+    /// Pays out everything owed. Emissions never outrun what the farm was
+    /// funded with, so the vault always covers it.
+    pub fn claim(&mut self) -> Result<()> {
+        self.farm.update(Clock::get()?.unix_timestamp)?;
+        self.stake.update(0, self.farm.reward_per_share)?;
+
+        let amount = self.stake.rewards_owed;
+        if amount == 0 {
+            return Ok(());
+        }
+        self.stake.rewards_owed = 0;
+
+        helpers::transfer_tokens(
+            self.reward_vault.to_account_info(),
+            &mut self.user_reward_account,
+            &self.reward_mint,
+            self.farm.to_account_info(),
+            self.reward_token_program.to_account_info(),
+            &[&self.farm.signer_seeds()],
+            amount,
+        )?;
+
+        Ok(())
+    }
+}
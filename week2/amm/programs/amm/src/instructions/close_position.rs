@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    burn, close_account, Burn, CloseAccount, Mint, TokenAccount, TokenInterface,
+};
+
+use crate::error::AmmError;
+use crate::state::Position;
+
+#[derive(Accounts)]
+pub struct ClosePosition<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"position", position_mint.key().as_ref()],
+        bump = position.bump,
+        has_one = position_mint,
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(
+        mut,
+        mint::token_program = token_program,
+    )]
+    pub position_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        token::mint = position_mint,
+        token::authority = owner,
+        token::token_program = token_program,
+        constraint = position_token_account.amount == 1 @ AmmError::InvalidPositionOwner,
+    )]
+    pub position_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ClosePosition<'info> {
+    pub fn close_position(&mut self) -> Result<()> {
+        require!(
+            self.position.liquidity == 0
+                && self.position.fees_owed_x == 0
+                && self.position.fees_owed_y == 0,
+            AmmError::PositionNotEmpty
+        );
+
+        let cpi_accounts = Burn {
+            mint: self.position_mint.to_account_info(),
+            from: self.position_token_account.to_account_info(),
+            authority: self.owner.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+        burn(cpi_ctx, 1)?;
+
+        let cpi_accounts = CloseAccount {
+            account: self.position_token_account.to_account_info(),
+            destination: self.owner.to_account_info(),
+            authority: self.owner.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+        close_account(cpi_ctx)
+    }
+}
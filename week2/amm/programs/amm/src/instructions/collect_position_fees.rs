@@ -0,0 +1,155 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::error::AmmError;
+use crate::events::WithdrawEvent;
+use crate::helpers;
+use crate::state::{ClPool, Config, Position, TickArray};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CollectPositionFees<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        has_one = mint_x,
+        has_one = mint_y,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"cl_pool", config.key().as_ref()],
+        bump = cl_pool.bump,
+        has_one = config,
+    )]
+    pub cl_pool: Account<'info, ClPool>,
+
+    #[account(
+        mut,
+        seeds = [b"position", position.position_mint.as_ref()],
+        bump = position.bump,
+        has_one = cl_pool,
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(
+        token::mint = position.position_mint,
+        token::authority = owner,
+        constraint = position_token_account.amount == 1 @ AmmError::InvalidPositionOwner,
+    )]
+    pub position_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(has_one = cl_pool)]
+    pub tick_array_lower: Box<Account<'info, TickArray>>,
+
+    #[account(has_one = cl_pool)]
+    pub tick_array_upper: Box<Account<'info, TickArray>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = cl_pool,
+        associated_token::token_program = token_program_x,
+    )]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = cl_pool,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program_x,
+    )]
+    pub user_token_account_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program_y,
+    )]
+    pub user_token_account_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+}
+
+impl<'info> CollectPositionFees<'info> {
+    pub fn collect_position_fees(&mut self) -> Result<WithdrawEvent> {
+        let (tick_lower, tick_upper) = (self.position.tick_lower, self.position.tick_upper);
+        let spacing = self.cl_pool.tick_spacing;
+
+        // Credit whatever the position earned since it was last touched
+        if self.position.liquidity > 0 {
+            let lower = self.tick_array_lower.tick(tick_lower, spacing)?;
+            let upper = self.tick_array_upper.tick(tick_upper, spacing)?;
+            let (inside_x, inside_y) = self
+                .cl_pool
+                .fee_growth_inside(tick_lower, lower, tick_upper, upper);
+            self.position.update(0, inside_x, inside_y)?;
+        }
+
+        let (fees_x, fees_y) = (self.position.fees_owed_x, self.position.fees_owed_y);
+        self.position.fees_owed_x = 0;
+        self.position.fees_owed_y = 0;
+
+        let x = self.withdraw_token(true, fees_x)?;
+        let y = self.withdraw_token(false, fees_y)?;
+
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+
+        Ok(WithdrawEvent {
+            config: self.config.key(),
+            user: self.owner.key(),
+            amount_x: x,
+            amount_y: y,
+            lp_amount: 0,
+            fee: None,
+            reserve_x: self.vault_x.amount,
+            reserve_y: self.vault_y.amount,
+            lp_supply: None,
+            liquidity: Some(self.cl_pool.liquidity),
+        })
+    }
+
+    /// Returns the amount that arrived in the owner's token account.
+    fn withdraw_token(&mut self, is_x: bool, amount: u64) -> Result<u64> {
+        if amount == 0 {
+            return Ok(0);
+        }
+
+        let (vault, to, mint, token_program) = if is_x {
+            (
+                self.vault_x.to_account_info(),
+                &mut self.user_token_account_x,
+                &self.mint_x,
+                self.token_program_x.to_account_info(),
+            )
+        } else {
+            (
+                self.vault_y.to_account_info(),
+                &mut self.user_token_account_y,
+                &self.mint_y,
+                self.token_program_y.to_account_info(),
+            )
+        };
+
+        helpers::transfer_from_cl_pool(&self.cl_pool, vault, to, mint, token_program, amount)
+    }
+}
@@ -0,0 +1,115 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::events::WithdrawEvent;
+use crate::helpers;
+use crate::state::Config;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CollectProtocolFees<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        has_one = mint_x,
+        has_one = mint_y,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol", config.key().as_ref(), mint_x.key().as_ref()],
+        bump = config.protocol_x_bump,
+    )]
+    pub protocol_vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol", config.key().as_ref(), mint_y.key().as_ref()],
+        bump = config.protocol_y_bump,
+    )]
+    pub protocol_vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint_x,
+        associated_token::authority = authority,
+        associated_token::token_program = token_program_x
+    )]
+    pub authority_token_account_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = mint_y,
+        associated_token::authority = authority,
+        associated_token::token_program = token_program_y
+    )]
+    pub authority_token_account_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CollectProtocolFees<'info> {
+    pub fn collect_protocol_fees(&mut self) -> Result<WithdrawEvent> {
+        self.config.check_authority(self.authority.key())?;
+
+        let x = self.collect(true, self.protocol_vault_x.amount)?;
+        let y = self.collect(false, self.protocol_vault_y.amount)?;
+
+        // The fees sit outside the vaults, the pool's reserves are untouched
+        Ok(WithdrawEvent {
+            config: self.config.key(),
+            user: self.authority.key(),
+            amount_x: x,
+            amount_y: y,
+            lp_amount: 0,
+            fee: None,
+            reserve_x: self.config.last_reserve_x,
+            reserve_y: self.config.last_reserve_y,
+            lp_supply: None,
+            liquidity: None,
+        })
+    }
+
+    /// Returns the amount that arrived in the authority's token account.
+    fn collect(&mut self, is_x: bool, amount: u64) -> Result<u64> {
+        if amount == 0 {
+            return Ok(0);
+        }
+
+        let (from, to, mint, token_program) = if is_x {
+            (
+                self.protocol_vault_x.to_account_info(),
+                &mut self.authority_token_account_x,
+                &self.mint_x,
+                self.token_program_x.to_account_info(),
+            )
+        } else {
+            (
+                self.protocol_vault_y.to_account_info(),
+                &mut self.authority_token_account_y,
+                &self.mint_y,
+                self.token_program_y.to_account_info(),
+            )
+        };
+
+        helpers::transfer_from_pool(&self.config, from, to, mint, token_program, amount)
+    }
+}
@@ -0,0 +1,87 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::error::AmmError;
+use crate::state::{Config, Farm};
+
+#[derive(Accounts)]
+pub struct CreateFarm<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"farm", config.key().as_ref()],
+        bump,
+        space = Farm::INIT_SPACE,
+    )]
+    pub farm: Account<'info, Farm>,
+
+    #[account(
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump
+    )]
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+
+    // Rewards paid in LP would come out of the staked LP
+    #[account(
+        mint::token_program = reward_token_program,
+        constraint = reward_mint.key() != mint_lp.key() @ AmmError::InvalidMint,
+    )]
+    pub reward_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = mint_lp,
+        associated_token::authority = farm,
+        associated_token::token_program = token_program,
+    )]
+    pub lp_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = reward_mint,
+        associated_token::authority = farm,
+        associated_token::token_program = reward_token_program,
+    )]
+    pub reward_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub reward_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateFarm<'info> {
+    /// Starts emitting `reward_per_second` to the pool's stakers. Rewards only
+    /// go out as far as the farm has been funded, see `fund_farm`.
+    pub fn create_farm(&mut self, reward_per_second: u64, bumps: &CreateFarmBumps) -> Result<()> {
+        self.config.check_authority(self.authority.key())?;
+
+        self.farm.set_inner(Farm {
+            config: self.config.key(),
+            reward_mint: self.reward_mint.key(),
+            reward_per_second,
+            reward_per_share: 0,
+            total_staked: 0,
+            reward_funded: 0,
+            reward_emitted: 0,
+            last_update_ts: Clock::get()?.unix_timestamp,
+            bump: bumps.farm,
+        });
+
+        Ok(())
+    }
+}
@@ -1,26 +1,34 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{mint_to, transfer_checked, Mint, MintTo, Token, TokenAccount, TransferChecked},
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
-use constant_product_curve::ConstantProduct;
 
+use crate::constants::MINIMUM_LIQUIDITY;
 use crate::error::AmmError;
+use crate::events::DepositEvent;
+use crate::helpers::{
+    self, check_expiration, epoch_transfer_fee, fee_of, fee_on_top, is_native_mint,
+    is_transient_native, isqrt, lp_share, unwrap_sol, wrap_sol, TransferFee,
+};
+use crate::instructions::DepositQuote;
 use crate::state::Config;
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct Deposit<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
 
-    #[account(mint::token_program = token_program)]
-    pub mint_x: Account<'info, Mint>,
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
 
-    #[account(mint::token_program = token_program)]
-    pub mint_y: Account<'info, Mint>,
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
-        seeds = [b"config"],
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
         bump = config.config_bump,
         has_one = mint_x,
         has_one = mint_y,
@@ -32,39 +40,41 @@ pub struct Deposit<'info> {
         seeds = [b"lp", config.key().as_ref()],
         bump = config.lp_bump
     )]
-    pub mint_lp: Account<'info, Mint>,
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
         associated_token::mint = mint_x,
         associated_token::authority = config,
-        associated_token::token_program = token_program,
+        associated_token::token_program = token_program_x,
     )]
-    pub vault_x: Account<'info, TokenAccount>,
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
         associated_token::mint = mint_y,
         associated_token::authority = config,
-        associated_token::token_program = token_program,
+        associated_token::token_program = token_program_y,
     )]
-    pub vault_y: Account<'info, TokenAccount>,
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
-        mut,
+        init_if_needed,
+        payer = user,
         associated_token::mint = mint_x,
         associated_token::authority = user,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_x
     )]
-    pub user_token_account_x: Account<'info, TokenAccount>,
+    pub user_token_account_x: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
-        mut,
+        init_if_needed,
+        payer = user,
         associated_token::mint = mint_y,
         associated_token::authority = user,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_y
     )]
-    pub user_token_account_y: Account<'info, TokenAccount>,
+    pub user_token_account_y: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         init_if_needed,
@@ -73,87 +83,241 @@ pub struct Deposit<'info> {
         associated_token::authority = user,
         associated_token::token_program = token_program
     )]
-    pub user_token_account_lp: Account<'info, TokenAccount>,
+    pub user_token_account_lp: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    pub token_program: Program<'info, Token>,
+    #[account(
+        mut,
+        seeds = [b"lp_lock", config.key().as_ref()],
+        bump = config.lp_lock_bump,
+    )]
+    pub lp_lock: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> Deposit<'info> {
-    pub fn deposit(&mut self, amount: u64, max_x: u64, max_y: u64) -> Result<()> {
+    pub fn deposit(
+        &mut self,
+        amount: u64,
+        max_x: u64,
+        max_y: u64,
+        expiration: i64,
+    ) -> Result<DepositEvent> {
+        check_expiration(expiration)?;
+        require!(!self.config.locked, AmmError::PoolLocked);
+        self.config.check_no_flash_loan()?;
         require!(amount > 0, AmmError::InvalidAmount);
 
-        // Calculate deposit amounts based on current pool ratio
-        let (x, y) = if self.mint_lp.supply == 0 {
-            // First deposit - accept provided maximums
-            (max_x, max_y)
-        } else {
-            // Calculate proportional amounts based on current pool
-            let amounts = ConstantProduct::xy_deposit_amounts_from_l(
-                self.vault_x.amount,
-                self.vault_y.amount,
-                self.mint_lp.supply,
-                amount,
-                6, // rounding precision
-            )
-            .unwrap();
-            (amounts.x, amounts.y)
-        };
+        self.config.update_oracle()?;
+        let transient = self.transient_native();
 
-        // Check slippage protection
-        require!(x <= max_x && y <= max_y, AmmError::SlippageExceeded);
+        let is_first_deposit = self.mint_lp.supply == 0;
+        let quote = deposit_amounts(
+            self.vault_x.amount,
+            self.vault_y.amount,
+            self.mint_lp.supply,
+            amount,
+            max_x,
+            max_y,
+            epoch_transfer_fee(&self.mint_x.to_account_info())?.as_ref(),
+            epoch_transfer_fee(&self.mint_y.to_account_info())?.as_ref(),
+        )?;
 
         // Transfer tokens from user to vaults
-        self.deposit_token_x(x)?;
-        self.deposit_token_y(y)?;
+        self.wrap_native(quote.amount_x, quote.amount_y)?;
+        let received_x = self.deposit_token(true, quote.amount_x)?;
+        let received_y = self.deposit_token(false, quote.amount_y)?;
+
+        // The first deposit permanently locks a slice of the LP so the share
+        // price can't be inflated
+        if is_first_deposit {
+            self.mint_lp_tokens(self.lp_lock.to_account_info(), MINIMUM_LIQUIDITY)?;
+        }
+        self.mint_lp_tokens(
+            self.user_token_account_lp.to_account_info(),
+            quote.lp_amount,
+        )?;
+
+        self.unwrap_native(transient)?;
+        self.mint_lp.reload()?;
+        self.config
+            .sync_reserves(self.vault_x.amount, self.vault_y.amount);
+
+        Ok(DepositEvent {
+            config: self.config.key(),
+            user: self.user.key(),
+            amount_x: received_x,
+            amount_y: received_y,
+            lp_amount: quote.lp_amount,
+            fee: None,
+            reserve_x: self.vault_x.amount,
+            reserve_y: self.vault_y.amount,
+            lp_supply: Some(self.mint_lp.supply),
+            liquidity: None,
+        })
+    }
 
-        // Mint LP tokens to user
-        self.mint_lp_tokens(amount)?;
+    /// Wraps the user's native SOL into their token account for whichever
+    /// side is the native mint.
+    fn wrap_native(&mut self, x: u64, y: u64) -> Result<()> {
+        if is_native_mint(&self.mint_x.key()) {
+            wrap_sol(
+                self.user.to_account_info(),
+                &mut self.user_token_account_x,
+                x,
+                self.token_program_x.to_account_info(),
+                self.system_program.to_account_info(),
+            )?;
+        }
+
+        if is_native_mint(&self.mint_y.key()) {
+            wrap_sol(
+                self.user.to_account_info(),
+                &mut self.user_token_account_y,
+                y,
+                self.token_program_y.to_account_info(),
+                self.system_program.to_account_info(),
+            )?;
+        }
 
         Ok(())
     }
 
-    fn deposit_token_x(&mut self, amount: u64) -> Result<()> {
-        let cpi_program = self.token_program.to_account_info();
-        let cpi_accounts = TransferChecked {
-            from: self.user_token_account_x.to_account_info(),
-            to: self.vault_x.to_account_info(),
-            mint: self.mint_x.to_account_info(),
-            authority: self.user.to_account_info(),
-        };
+    /// Which of the user's token accounts, x then y, only carry native SOL
+    /// through this instruction. Read before anything moves.
+    fn transient_native(&self) -> [bool; 2] {
+        [
+            is_transient_native(&self.mint_x.key(), &self.user_token_account_x),
+            is_transient_native(&self.mint_y.key(), &self.user_token_account_y),
+        ]
+    }
 
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        transfer_checked(cpi_ctx, amount, self.mint_x.decimals)
+    /// Closes the `transient` wrapped SOL accounts so the wallet ends up
+    /// holding plain SOL.
+    fn unwrap_native(&mut self, [x, y]: [bool; 2]) -> Result<()> {
+        if x {
+            unwrap_sol(
+                self.user.to_account_info(),
+                self.user_token_account_x.to_account_info(),
+                self.token_program_x.to_account_info(),
+            )?;
+        }
+
+        if y {
+            unwrap_sol(
+                self.user.to_account_info(),
+                self.user_token_account_y.to_account_info(),
+                self.token_program_y.to_account_info(),
+            )?;
+        }
+
+        Ok(())
     }
 
-    fn deposit_token_y(&mut self, amount: u64) -> Result<()> {
-        let cpi_program = self.token_program.to_account_info();
-        let cpi_accounts = TransferChecked {
-            from: self.user_token_account_y.to_account_info(),
-            to: self.vault_y.to_account_info(),
-            mint: self.mint_y.to_account_info(),
-            authority: self.user.to_account_info(),
+    /// Returns the amount that arrived in the vault.
+    fn deposit_token(&mut self, is_x: bool, amount: u64) -> Result<u64> {
+        let (from, to, mint, token_program) = if is_x {
+            (
+                self.user_token_account_x.to_account_info(),
+                &mut self.vault_x,
+                &self.mint_x,
+                self.token_program_x.to_account_info(),
+            )
+        } else {
+            (
+                self.user_token_account_y.to_account_info(),
+                &mut self.vault_y,
+                &self.mint_y,
+                self.token_program_y.to_account_info(),
+            )
         };
 
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        transfer_checked(cpi_ctx, amount, self.mint_y.decimals)
+        helpers::transfer_tokens(
+            from,
+            to,
+            mint,
+            self.user.to_account_info(),
+            token_program,
+            &[],
+            amount,
+        )
     }
 
-    fn mint_lp_tokens(&mut self, amount: u64) -> Result<()> {
-        let cpi_program = self.token_program.to_account_info();
-        let cpi_accounts = MintTo {
-            mint: self.mint_lp.to_account_info(),
-            to: self.user_token_account_lp.to_account_info(),
-            authority: self.config.to_account_info(),
-        };
+    fn mint_lp_tokens(&mut self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        helpers::mint_lp(
+            &self.config,
+            self.mint_lp.to_account_info(),
+            to,
+            self.token_program.to_account_info(),
+            amount,
+        )
+    }
+}
+
+/// What a deposit of `amount` LP takes from the user and mints to them. Later
+/// deposits pay their share of each reserve, rounded up and grossed up so that share still
+/// arrives after transfer fees. The first deposit pays the maximums as given
+/// and sets the LP supply from what arrives, not from the caller, with
+/// `amount` as the least LP accepted. Shared by `deposit`, `quote_deposit`
+/// and off-chain simulators.
+#[allow(clippy::too_many_arguments)]
+pub fn deposit_amounts(
+    reserve_x: u64,
+    reserve_y: u64,
+    supply: u64,
+    amount: u64,
+    max_x: u64,
+    max_y: u64,
+    transfer_fee_x: Option<&TransferFee>,
+    transfer_fee_y: Option<&TransferFee>,
+) -> Result<DepositQuote> {
+    require!(amount > 0, AmmError::InvalidAmount);
+
+    if supply == 0 {
+        let received_x = max_x - fee_of(transfer_fee_x, max_x)?;
+        let received_y = max_y - fee_of(transfer_fee_y, max_y)?;
 
-        let signer_seeds: &[&[&[u8]]] = &[&[
-            b"config",
-            &[self.config.config_bump],
-        ]];
+        let liquidity = initial_lp_amount(received_x, received_y)?;
+        require!(liquidity >= amount, AmmError::SlippageExceeded);
 
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
-        mint_to(cpi_ctx, amount)
+        return Ok(DepositQuote {
+            amount_x: max_x,
+            amount_y: max_y,
+            lp_amount: liquidity,
+        });
     }
-}
\ No newline at end of file
+
+    let x = lp_share(reserve_x, amount, supply, true)?;
+    let y = lp_share(reserve_y, amount, supply, true)?;
+    let x = x
+        .checked_add(fee_on_top(transfer_fee_x, x)?)
+        .ok_or(AmmError::Overflow)?;
+    let y = y
+        .checked_add(fee_on_top(transfer_fee_y, y)?)
+        .ok_or(AmmError::Overflow)?;
+
+    require!(x <= max_x && y <= max_y, AmmError::SlippageExceeded);
+
+    Ok(DepositQuote {
+        amount_x: x,
+        amount_y: y,
+        lp_amount: amount,
+    })
+}
+
+/// LP minted to the first depositor: the geometric mean of the deposits, less
+/// the `MINIMUM_LIQUIDITY` that gets locked. Fails for deposits too small to
+/// cover the lock.
+pub fn initial_lp_amount(x: u64, y: u64) -> Result<u64> {
+    let liquidity = isqrt(x as u128 * y as u128) as u64;
+    require!(
+        liquidity > MINIMUM_LIQUIDITY,
+        AmmError::InsufficientInitialLiquidity
+    );
+
+    Ok(liquidity - MINIMUM_LIQUIDITY)
+}
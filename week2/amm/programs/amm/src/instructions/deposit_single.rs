@@ -0,0 +1,289 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::error::AmmError;
+use crate::events::DepositEvent;
+use crate::helpers::{self, check_expiration};
+use crate::state::{Config, SwapAmounts};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct DepositSingle<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        has_one = mint_x,
+        has_one = mint_y,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump
+    )]
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_x,
+    )]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol", config.key().as_ref(), mint_x.key().as_ref()],
+        bump = config.protocol_x_bump,
+    )]
+    pub protocol_vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol", config.key().as_ref(), mint_y.key().as_ref()],
+        bump = config.protocol_y_bump,
+    )]
+    pub protocol_vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // Only the side being deposited, so users don't need an account for the other mint
+    #[account(
+        mut,
+        token::authority = user,
+    )]
+    pub user_token_account_in: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = mint_lp,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_token_account_lp: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> DepositSingle<'info> {
+    pub fn deposit_single(
+        &mut self,
+        is_x: bool,
+        amount_in: u64,
+        min_lp_out: u64,
+        expiration: i64,
+    ) -> Result<DepositEvent> {
+        check_expiration(expiration)?;
+        require!(!self.config.locked, AmmError::PoolLocked);
+        self.config.check_no_flash_loan()?;
+        require!(amount_in > 0, AmmError::InvalidAmount);
+        require!(self.mint_lp.supply > 0, AmmError::PoolEmpty);
+
+        self.config.update_oracle()?;
+
+        let mint_in = if is_x {
+            self.mint_x.key()
+        } else {
+            self.mint_y.key()
+        };
+        require_keys_eq!(
+            self.user_token_account_in.mint,
+            mint_in,
+            AmmError::InvalidMint
+        );
+
+        let (reserve_x, reserve_y) = (self.vault_x.amount, self.vault_y.amount);
+        let supply = self.mint_lp.supply;
+
+        // The whole input lands in the vault: part of it is swapped on the curve
+        // and its output never leaves, the rest is paired with that output
+        let received = self.deposit_token(is_x, amount_in)?;
+
+        let (swap_in, swap) = zap_swap_amounts(
+            &self.config,
+            reserve_x,
+            reserve_y,
+            supply,
+            is_x,
+            received,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let swap_out = swap.amount_out;
+        let protocol_fee = self.transfer_protocol_fee(is_x, swap_in, swap.fee)?;
+
+        let (reserve_in, reserve_out) = if is_x {
+            (reserve_x, reserve_y)
+        } else {
+            (reserve_y, reserve_x)
+        };
+        let reserve_in = reserve_in as u128 + swap_in as u128 - protocol_fee as u128;
+        let reserve_out = (reserve_out - swap_out) as u128;
+
+        let lp_from_in = (received - swap_in) as u128 * supply as u128 / reserve_in;
+        let lp_from_out = swap_out as u128 * supply as u128 / reserve_out;
+        let lp = u64::try_from(lp_from_in.min(lp_from_out)).map_err(|_| AmmError::Overflow)?;
+
+        require!(lp > 0, AmmError::InvalidAmount);
+        require!(lp >= min_lp_out, AmmError::SlippageExceeded);
+
+        helpers::mint_lp(
+            &self.config,
+            self.mint_lp.to_account_info(),
+            self.user_token_account_lp.to_account_info(),
+            self.token_program.to_account_info(),
+            lp,
+        )?;
+
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+        self.mint_lp.reload()?;
+
+        // Only the internal swap moves the price, the rest is added at the new ratio
+        let after = (self.vault_x.amount, self.vault_y.amount);
+        self.config
+            .check_price_move((reserve_x, reserve_y), after)?;
+        self.config
+            .record_price_move((reserve_x, reserve_y), after)?;
+        self.config.sync_reserves(after.0, after.1);
+
+        let (amount_x, amount_y) = if is_x { (received, 0) } else { (0, received) };
+        Ok(DepositEvent {
+            config: self.config.key(),
+            user: self.user.key(),
+            amount_x,
+            amount_y,
+            lp_amount: lp,
+            fee: Some((swap_in as u128 * swap.fee as u128 / 10_000) as u64),
+            reserve_x: self.vault_x.amount,
+            reserve_y: self.vault_y.amount,
+            lp_supply: Some(self.mint_lp.supply),
+            liquidity: None,
+        })
+    }
+
+    /// Returns the amount that arrived in the vault.
+    fn deposit_token(&mut self, is_x: bool, amount: u64) -> Result<u64> {
+        let (vault, mint, token_program) = if is_x {
+            (
+                &mut self.vault_x,
+                &self.mint_x,
+                self.token_program_x.to_account_info(),
+            )
+        } else {
+            (
+                &mut self.vault_y,
+                &self.mint_y,
+                self.token_program_y.to_account_info(),
+            )
+        };
+
+        helpers::transfer_tokens(
+            self.user_token_account_in.to_account_info(),
+            vault,
+            mint,
+            self.user.to_account_info(),
+            token_program,
+            &[],
+            amount,
+        )
+    }
+
+    /// Moves the protocol's cut of the internal swap's fee out of the vault.
+    fn transfer_protocol_fee(&mut self, is_x: bool, amount_in: u64, fee: u16) -> Result<u64> {
+        let (vault, protocol_vault, mint, token_program) = if is_x {
+            (
+                self.vault_x.to_account_info(),
+                &mut self.protocol_vault_x,
+                &self.mint_x,
+                self.token_program_x.to_account_info(),
+            )
+        } else {
+            (
+                self.vault_y.to_account_info(),
+                &mut self.protocol_vault_y,
+                &self.mint_y,
+                self.token_program_y.to_account_info(),
+            )
+        };
+
+        helpers::transfer_protocol_fee(
+            &self.config,
+            vault,
+            protocol_vault,
+            mint,
+            token_program,
+            amount_in,
+            fee,
+        )
+    }
+}
+
+/// Splits a single-sided `amount_in` into the part to swap on the curve and that
+/// swap's amounts, such that the unswapped remainder and the output are in the
+/// post-swap pool ratio. Bisects on the pool's own pricing instead of the closed
+/// form so it always agrees with `swap` and needs no 256-bit square root, then
+/// prices the result through `Config::swap_amounts_at` like any other swap.
+pub fn zap_swap_amounts(
+    config: &Config,
+    reserve_x: u64,
+    reserve_y: u64,
+    supply: u64,
+    is_x: bool,
+    amount_in: u64,
+    now: i64,
+) -> Result<(u64, SwapAmounts)> {
+    let (reserve_in, reserve_out) = if is_x {
+        (reserve_x, reserve_y)
+    } else {
+        (reserve_y, reserve_x)
+    };
+
+    let (mut lo, mut hi) = (0u64, amount_in);
+    while lo < hi {
+        let mid = lo + (hi - lo).div_ceil(2);
+        let out = config
+            .price_swap_at(reserve_x, reserve_y, supply, is_x, mid, now)?
+            .amount_out;
+
+        // Is the remainder still enough to pair with the output at the new ratio?
+        let remainder = (amount_in - mid) as u128 * (reserve_out - out) as u128;
+        let needed = (out as u128)
+            .checked_mul(reserve_in as u128 + mid as u128)
+            .ok_or(AmmError::Overflow)?;
+
+        if remainder >= needed {
+            lo = mid;
+        } else {
+            hi = mid - 1;
+        }
+    }
+
+    let swap = config.swap_amounts_at(reserve_x, reserve_y, supply, is_x, lo, now)?;
+
+    Ok((lo, swap))
+}
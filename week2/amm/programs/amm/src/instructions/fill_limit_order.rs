@@ -0,0 +1,224 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{close_account, CloseAccount, Mint, TokenAccount, TokenInterface},
+};
+
+use crate::error::AmmError;
+use crate::events::SwapEvent;
+use crate::helpers::{self, check_expiration};
+use crate::state::{Config, LimitOrder};
+
+/// Accounts are laid out by the order's direction rather than as x and y,
+/// the order decides which pool mint is which.
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FillLimitOrder<'info> {
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    #[account(mut)]
+    pub owner: SystemAccount<'info>,
+
+    // Writable to harvest the escrow's withheld transfer fees into
+    #[account(mut, mint::token_program = token_program_in)]
+    pub mint_in: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mint::token_program = token_program_out)]
+    pub mint_out: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump
+    )]
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [
+            b"order",
+            config.key().as_ref(),
+            owner.key().as_ref(),
+            order.seed.to_le_bytes().as_ref(),
+        ],
+        bump = order.bump,
+        has_one = config,
+        has_one = owner,
+        constraint = order.mints(&config) == (mint_in.key(), mint_out.key())
+            @ AmmError::InvalidMint,
+    )]
+    pub order: Account<'info, LimitOrder>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_in,
+        associated_token::authority = order,
+        associated_token::token_program = token_program_in,
+    )]
+    pub order_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_in,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_in,
+    )]
+    pub vault_in: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_out,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_out,
+    )]
+    pub vault_out: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol", config.key().as_ref(), mint_in.key().as_ref()],
+        bump = if order.is_x { config.protocol_x_bump } else { config.protocol_y_bump },
+    )]
+    pub protocol_vault_in: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_out,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program_out,
+    )]
+    pub owner_token_account_out: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_in: Interface<'info, TokenInterface>,
+    pub token_program_out: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> FillLimitOrder<'info> {
+    /// Sells the whole escrow into the pool, provided the owner gets at least
+    /// `limit_amount_out` for it, pays the keeper the tip and closes the order.
+    /// The limit bounds the average price of the fill, price impact included.
+    pub fn fill_limit_order(&mut self) -> Result<SwapEvent> {
+        check_expiration(self.order.expiration)?;
+        require!(!self.config.locked, AmmError::PoolLocked);
+        self.config.check_no_flash_loan()?;
+
+        let is_x = self.order.is_x;
+        let (reserve_x, reserve_y) = self.reserves();
+        self.config.update_oracle()?;
+
+        // Price only what actually reached the vault after any transfer fee
+        let received = self.deposit_escrow()?;
+        let (swap, amount_out) = helpers::settle_swap(
+            &mut self.config,
+            self.mint_lp.supply,
+            is_x,
+            (reserve_x, reserve_y),
+            received,
+            &mut self.vault_in,
+            &mut self.vault_out,
+            &mut self.protocol_vault_in,
+            &self.mint_in,
+            &self.mint_out,
+            self.token_program_in.to_account_info(),
+            self.token_program_out.to_account_info(),
+            &mut self.owner_token_account_out,
+        )?;
+
+        // Checked on what the owner received, so price impact and transfer fees
+        // count against the limit
+        require!(
+            amount_out >= self.order.limit_amount_out,
+            AmmError::LimitPriceNotReached
+        );
+
+        let after = self.reserves();
+        self.close_escrow()?;
+        self.pay_tip()?;
+
+        Ok(SwapEvent {
+            config: self.config.key(),
+            user: self.owner.key(),
+            is_x,
+            amount_in: received,
+            amount_out,
+            fee: (received as u128 * swap.fee as u128 / 10_000) as u64,
+            protocol_fee: swap.protocol_fee,
+            reserve_x: after.0,
+            reserve_y: after.1,
+            sqrt_price: None,
+            liquidity: None,
+        })
+    }
+
+    /// The vault balances as `(x, y)`
+    fn reserves(&self) -> (u64, u64) {
+        if self.order.is_x {
+            (self.vault_in.amount, self.vault_out.amount)
+        } else {
+            (self.vault_out.amount, self.vault_in.amount)
+        }
+    }
+
+    /// Moves the escrow into the pool. Returns the amount that arrived.
+    fn deposit_escrow(&mut self) -> Result<u64> {
+        helpers::transfer_tokens(
+            self.order_vault.to_account_info(),
+            &mut self.vault_in,
+            &self.mint_in,
+            self.order.to_account_info(),
+            self.token_program_in.to_account_info(),
+            &[&self.order.signer_seeds().as_seeds()],
+            self.order_vault.amount,
+        )
+    }
+
+    /// Closes the emptied escrow, its rent goes back to the owner. Fees the
+    /// mint withheld on the way in are harvested first, Token-2022 won't close
+    /// an account still holding them.
+    fn close_escrow(&mut self) -> Result<()> {
+        helpers::harvest_withheld_fees(
+            self.token_program_in.to_account_info(),
+            self.mint_in.to_account_info(),
+            self.order_vault.to_account_info(),
+        )?;
+
+        let cpi_accounts = CloseAccount {
+            account: self.order_vault.to_account_info(),
+            destination: self.owner.to_account_info(),
+            authority: self.order.to_account_info(),
+        };
+
+        let seeds = self.order.signer_seeds();
+        let signer_seeds: &[&[&[u8]]] = &[&seeds.as_seeds()];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program_in.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        close_account(cpi_ctx)
+    }
+
+    /// The order's rent goes back to the owner when it closes, only the tip
+    /// is moved out before that.
+    fn pay_tip(&mut self) -> Result<()> {
+        let tip = self.order.tip;
+        if tip == 0 {
+            return Ok(());
+        }
+
+        self.order.sub_lamports(tip)?;
+        self.keeper.add_lamports(tip)?;
+
+        Ok(())
+    }
+}
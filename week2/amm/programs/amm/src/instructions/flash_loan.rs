@@ -0,0 +1,227 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions::{
+    load_current_index_checked, load_instruction_at_checked,
+};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::error::AmmError;
+use crate::events::{DepositEvent, WithdrawEvent};
+use crate::helpers::{self, transfer_fee_on_top};
+use crate::state::Config;
+
+/// Where `config` sits in `FlashLoan`'s accounts, to match a repay to its pool
+pub const FLASH_LOAN_CONFIG_INDEX: usize = 3;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct FlashLoan<'info> {
+    pub user: Signer<'info>,
+
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        has_one = mint_x,
+        has_one = mint_y,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_x,
+    )]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = user,
+        associated_token::token_program = token_program_x,
+    )]
+    pub user_token_account_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = user,
+        associated_token::token_program = token_program_y,
+    )]
+    pub user_token_account_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    /// CHECK: This is the instructions sysvar
+    pub instruction_sysvar: AccountInfo<'info>,
+
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+}
+
+impl<'info> FlashLoan<'info> {
+    /// Lends `amount` straight out of a vault. Only allowed when a
+    /// `flash_repay` for this pool follows in the same transaction, and the
+    /// pool refuses everything else until it has run.
+    pub fn flash_borrow(&mut self, is_x: bool, amount: u64) -> Result<WithdrawEvent> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        self.config.check_no_flash_loan()?;
+        require!(amount > 0, AmmError::InvalidAmount);
+
+        let reserve = if is_x {
+            self.vault_x.amount
+        } else {
+            self.vault_y.amount
+        };
+        require!(amount < reserve, AmmError::InvalidAmount);
+
+        self.verify_repay()?;
+
+        self.config.flash_loan_amount = amount;
+        self.config.flash_loan_is_x = is_x;
+
+        let received = self.withdraw_token(is_x, amount)?;
+
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+
+        let (amount_x, amount_y) = if is_x { (received, 0) } else { (0, received) };
+        Ok(WithdrawEvent {
+            config: self.config.key(),
+            user: self.user.key(),
+            amount_x,
+            amount_y,
+            lp_amount: 0,
+            fee: None,
+            reserve_x: self.vault_x.amount,
+            reserve_y: self.vault_y.amount,
+            lp_supply: None,
+            liquidity: None,
+        })
+    }
+
+    /// Pays back the outstanding loan plus the pool fee on it, all of which
+    /// stays in the vault for LPs.
+    pub fn flash_repay(&mut self) -> Result<DepositEvent> {
+        let (is_x, amount) = (self.config.flash_loan_is_x, self.config.flash_loan_amount);
+        require!(amount > 0, AmmError::NoFlashLoan);
+
+        // Accrued at the price from before the loan, the vault is still short
+        self.config.update_oracle()?;
+
+        let fee = (amount as u128 * self.config.fee as u128).div_ceil(10_000);
+        let owed = u64::try_from(amount as u128 + fee).map_err(|_| AmmError::Overflow)?;
+
+        let received = self.deposit_token(is_x, owed)?;
+        require!(received >= owed, AmmError::InvalidAmount);
+
+        self.config.flash_loan_amount = 0;
+        self.config.flash_loan_is_x = false;
+        self.config
+            .sync_reserves(self.vault_x.amount, self.vault_y.amount);
+
+        let (amount_x, amount_y) = if is_x { (received, 0) } else { (0, received) };
+        Ok(DepositEvent {
+            config: self.config.key(),
+            user: self.user.key(),
+            amount_x,
+            amount_y,
+            lp_amount: 0,
+            fee: Some(owed - amount),
+            reserve_x: self.vault_x.amount,
+            reserve_y: self.vault_y.amount,
+            lp_supply: None,
+            liquidity: None,
+        })
+    }
+
+    /// Looks through the rest of the transaction for a `flash_repay` on this
+    /// pool, the same way `resolve_bet` finds its ed25519 instruction.
+    fn verify_repay(&self) -> Result<()> {
+        let sysvar = self.instruction_sysvar.to_account_info();
+        let current = load_current_index_checked(&sysvar)? as usize;
+
+        let mut index = current + 1;
+        while let Ok(ix) = load_instruction_at_checked(index, &sysvar) {
+            if ix.program_id == crate::ID
+                && ix
+                    .data
+                    .starts_with(crate::instruction::FlashRepay::DISCRIMINATOR)
+                && ix
+                    .accounts
+                    .get(FLASH_LOAN_CONFIG_INDEX)
+                    .is_some_and(|meta| meta.pubkey == self.config.key())
+            {
+                return Ok(());
+            }
+            index += 1;
+        }
+
+        err!(AmmError::MissingFlashRepay)
+    }
+
+    /// Sends enough on top of `amount` that the vault receives all of it
+    /// after any transfer fee. Returns the amount that arrived.
+    fn deposit_token(&mut self, is_x: bool, amount: u64) -> Result<u64> {
+        let (from, vault, mint, token_program) = if is_x {
+            (
+                self.user_token_account_x.to_account_info(),
+                &mut self.vault_x,
+                &self.mint_x,
+                self.token_program_x.to_account_info(),
+            )
+        } else {
+            (
+                self.user_token_account_y.to_account_info(),
+                &mut self.vault_y,
+                &self.mint_y,
+                self.token_program_y.to_account_info(),
+            )
+        };
+
+        let gross = amount + transfer_fee_on_top(&mint.to_account_info(), amount)?;
+        helpers::transfer_tokens(
+            from,
+            vault,
+            mint,
+            self.user.to_account_info(),
+            token_program,
+            &[],
+            gross,
+        )
+    }
+
+    /// Returns the amount that arrived in the borrower's token account.
+    fn withdraw_token(&mut self, is_x: bool, amount: u64) -> Result<u64> {
+        let (vault, to, mint, token_program) = if is_x {
+            (
+                self.vault_x.to_account_info(),
+                &mut self.user_token_account_x,
+                &self.mint_x,
+                self.token_program_x.to_account_info(),
+            )
+        } else {
+            (
+                self.vault_y.to_account_info(),
+                &mut self.user_token_account_y,
+                &self.mint_y,
+                self.token_program_y.to_account_info(),
+            )
+        };
+
+        helpers::transfer_from_pool(&self.config, vault, to, mint, token_program, amount)
+    }
+}
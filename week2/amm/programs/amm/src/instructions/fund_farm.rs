@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::error::AmmError;
+use crate::helpers;
+use crate::state::Farm;
+
+#[derive(Accounts)]
+pub struct FundFarm<'info> {
+    pub funder: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"farm", farm.config.as_ref()],
+        bump = farm.bump,
+        has_one = reward_mint,
+    )]
+    pub farm: Account<'info, Farm>,
+
+    #[account(mint::token_program = reward_token_program)]
+    pub reward_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = reward_mint,
+        associated_token::authority = farm,
+        associated_token::token_program = reward_token_program,
+    )]
+    pub reward_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = reward_mint,
+        token::authority = funder,
+    )]
+    pub funder_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub reward_token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+impl<'info> FundFarm<'info> {
+    /// Tops up the rewards, anyone can. Only what reaches the vault after the
+    /// mint's transfer fee is emitted.
+    pub fn fund_farm(&mut self, amount: u64) -> Result<()> {
+        require!(amount > 0, AmmError::InvalidAmount);
+
+        let received = helpers::transfer_tokens(
+            self.funder_token_account.to_account_info(),
+            &mut self.reward_vault,
+            &self.reward_mint,
+            self.funder.to_account_info(),
+            self.reward_token_program.to_account_info(),
+            &[],
+            amount,
+        )?;
+
+        self.farm.fund(received, Clock::get()?.unix_timestamp)
+    }
+}
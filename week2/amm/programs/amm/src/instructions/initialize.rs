@@ -1,23 +1,31 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{Mint, Token, TokenAccount},
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
 
-use crate::state::Config;
+use crate::constants::{MAX_AMP, MIN_WEIGHT};
+use crate::error::AmmError;
+use crate::events::PoolInitialized;
+use crate::state::{Config, PoolType};
 
+#[event_cpi]
 #[derive(Accounts)]
+#[instruction(seed: u64)]
 pub struct Initialize<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
 
-    pub mint_x: Account<'info, Mint>,
-    pub mint_y: Account<'info, Mint>,
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         init,
         payer = admin,
-        seeds = [b"config"],
+        seeds = [b"config", seed.to_le_bytes().as_ref()],
         bump,
         space = Config::INIT_SPACE
     )]
@@ -28,50 +36,165 @@ pub struct Initialize<'info> {
         payer = admin,
         mint::decimals = 6,
         mint::authority = config.key(),
+        mint::token_program = token_program,
         seeds = [b"lp", config.key().as_ref()],
         bump,
     )]
-    pub mint_lp: Account<'info, Mint>,
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+
+    // Holds the MINIMUM_LIQUIDITY locked on the first deposit, nothing ever moves it out
+    #[account(
+        init,
+        payer = admin,
+        token::mint = mint_lp,
+        token::authority = config,
+        token::token_program = token_program,
+        seeds = [b"lp_lock", config.key().as_ref()],
+        bump,
+    )]
+    pub lp_lock: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         init,
         payer = admin,
         associated_token::mint = mint_x,
         associated_token::authority = config,
-        associated_token::token_program = token_program,
+        associated_token::token_program = token_program_x,
     )]
-    pub vault_x: Account<'info, TokenAccount>,
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         init,
         payer = admin,
         associated_token::mint = mint_y,
         associated_token::authority = config,
-        associated_token::token_program = token_program,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = admin,
+        token::mint = mint_x,
+        token::authority = config,
+        token::token_program = token_program_x,
+        seeds = [b"protocol", config.key().as_ref(), mint_x.key().as_ref()],
+        bump,
     )]
-    pub vault_y: Account<'info, TokenAccount>,
+    pub protocol_vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    pub token_program: Program<'info, Token>,
+    #[account(
+        init,
+        payer = admin,
+        token::mint = mint_y,
+        token::authority = config,
+        token::token_program = token_program_y,
+        seeds = [b"protocol", config.key().as_ref(), mint_y.key().as_ref()],
+        bump,
+    )]
+    pub protocol_vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> Initialize<'info> {
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize(
         &mut self,
+        seed: u64,
         fee: u16,
+        protocol_fee: u16,
         authority: Option<Pubkey>,
+        pool_type: PoolType,
+        amp: u64,
+        weight_x: u16,
         bumps: &InitializeBumps,
-    ) -> Result<()> {
+    ) -> Result<PoolInitialized> {
+        require!(fee < 10_000, AmmError::InvalidFee);
+        require!(protocol_fee <= 10_000, AmmError::InvalidFee);
+
+        // StableSwap compares raw amounts, so both sides must share a unit
+        let amp = match pool_type {
+            PoolType::ConstantProduct | PoolType::Weighted => 0,
+            PoolType::StableSwap => {
+                require!((1..=MAX_AMP).contains(&amp), AmmError::InvalidAmp);
+                require!(
+                    self.mint_x.decimals == self.mint_y.decimals,
+                    AmmError::InvalidMint
+                );
+                amp
+            }
+        };
+        // Every other curve is the 50/50 case
+        let weight_x = match pool_type {
+            PoolType::Weighted => {
+                require!(
+                    (MIN_WEIGHT..=10_000 - MIN_WEIGHT).contains(&weight_x),
+                    AmmError::InvalidWeight
+                );
+                weight_x
+            }
+            _ => 5_000,
+        };
+        let weight_y = 10_000 - weight_x;
+        let now = Clock::get()?.unix_timestamp;
+
         self.config.set_inner(Config {
+            seed,
             authority,
             mint_x: self.mint_x.key(),
             mint_y: self.mint_y.key(),
             fee,
+            protocol_fee,
+            locked: false,
             config_bump: bumps.config,
             lp_bump: bumps.mint_lp,
+            lp_lock_bump: bumps.lp_lock,
+            protocol_x_bump: bumps.protocol_vault_x,
+            protocol_y_bump: bumps.protocol_vault_y,
+            price_x_cumulative: 0,
+            price_y_cumulative: 0,
+            last_update_ts: now,
+            last_reserve_x: 0,
+            last_reserve_y: 0,
+            pool_type,
+            initial_amp: amp,
+            target_amp: amp,
+            ramp_start_ts: now,
+            ramp_stop_ts: now,
+            flash_loan_amount: 0,
+            flash_loan_is_x: false,
+            dynamic_fee: false,
+            min_fee: fee,
+            max_fee: fee,
+            volatility: 0,
+            volatility_ts: now,
+            max_trade_bps: 0,
+            max_price_move_bps: 0,
+            breaker_window: 0,
+            breaker_slot: 0,
+            breaker_reserve_x: 0,
+            breaker_reserve_y: 0,
+            weight_x,
+            weight_y,
         });
 
-        Ok(())
+        Ok(PoolInitialized {
+            config: self.config.key(),
+            authority,
+            mint_x: self.mint_x.key(),
+            mint_y: self.mint_y.key(),
+            mint_lp: self.mint_lp.key(),
+            fee,
+            protocol_fee,
+            pool_type,
+            amp,
+            weight_x,
+            weight_y,
+        })
     }
-}
\ No newline at end of file
+}
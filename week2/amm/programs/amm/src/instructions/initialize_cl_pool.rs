@@ -0,0 +1,90 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::curve::concentrated::tick_at_sqrt_price;
+use crate::error::AmmError;
+use crate::state::{ClPool, Config};
+
+#[derive(Accounts)]
+pub struct InitializeClPool<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        has_one = mint_x,
+        has_one = mint_y,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"cl_pool", config.key().as_ref()],
+        bump,
+        space = ClPool::INIT_SPACE,
+    )]
+    pub cl_pool: Account<'info, ClPool>,
+
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = mint_x,
+        associated_token::authority = cl_pool,
+        associated_token::token_program = token_program_x,
+    )]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = payer,
+        associated_token::mint = mint_y,
+        associated_token::authority = cl_pool,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeClPool<'info> {
+    pub fn initialize_cl_pool(
+        &mut self,
+        tick_spacing: u16,
+        sqrt_price: u128,
+        bumps: &InitializeClPoolBumps,
+    ) -> Result<()> {
+        require!(tick_spacing > 0, AmmError::InvalidTick);
+
+        // Also rejects prices outside the tick range
+        let tick_current = tick_at_sqrt_price(sqrt_price)?;
+
+        self.cl_pool.set_inner(ClPool {
+            config: self.config.key(),
+            tick_spacing,
+            sqrt_price,
+            tick_current,
+            liquidity: 0,
+            fee_growth_global_x: 0,
+            fee_growth_global_y: 0,
+            breaker_slot: 0,
+            breaker_sqrt_price: 0,
+            bump: bumps.cl_pool,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{ClPool, Tick, TickArray};
+
+#[derive(Accounts)]
+#[instruction(start_tick_index: i32)]
+pub struct InitializeTickArray<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"cl_pool", cl_pool.config.as_ref()],
+        bump = cl_pool.bump,
+    )]
+    pub cl_pool: Account<'info, ClPool>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"tick_array", cl_pool.key().as_ref(), start_tick_index.to_le_bytes().as_ref()],
+        bump,
+        space = TickArray::INIT_SPACE,
+    )]
+    pub tick_array: Box<Account<'info, TickArray>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeTickArray<'info> {
+    pub fn initialize_tick_array(
+        &mut self,
+        start_tick_index: i32,
+        bumps: &InitializeTickArrayBumps,
+    ) -> Result<()> {
+        TickArray::check_start_tick_index(start_tick_index, self.cl_pool.tick_spacing)?;
+
+        self.tick_array.set_inner(TickArray {
+            cl_pool: self.cl_pool.key(),
+            start_tick_index,
+            ticks: [Tick::default(); TickArray::TICK_ARRAY_SIZE],
+            bump: bumps.tick_array,
+        });
+
+        Ok(())
+    }
+}
@@ -1,9 +1,53 @@
-pub mod initialize;
+pub mod cancel_limit_order;
+pub mod claim;
+pub mod close_position;
+pub mod collect_position_fees;
+pub mod collect_protocol_fees;
+pub mod create_farm;
 pub mod deposit;
-pub mod withdraw;
+pub mod deposit_single;
+pub mod fill_limit_order;
+pub mod flash_loan;
+pub mod fund_farm;
+pub mod initialize;
+pub mod initialize_cl_pool;
+pub mod initialize_tick_array;
+pub mod modify_liquidity;
+pub mod observe;
+pub mod open_position;
+pub mod place_limit_order;
+pub mod quote;
+pub mod stake_lp;
 pub mod swap;
+pub mod swap_cl;
+pub mod twap;
+pub mod update;
+pub mod withdraw;
+pub mod withdraw_single;
 
-pub use initialize::*;
+pub use cancel_limit_order::*;
+pub use claim::*;
+pub use close_position::*;
+pub use collect_position_fees::*;
+pub use collect_protocol_fees::*;
+pub use create_farm::*;
 pub use deposit::*;
+pub use deposit_single::*;
+pub use fill_limit_order::*;
+pub use flash_loan::*;
+pub use fund_farm::*;
+pub use initialize::*;
+pub use initialize_cl_pool::*;
+pub use initialize_tick_array::*;
+pub use modify_liquidity::*;
+pub use observe::*;
+pub use open_position::*;
+pub use place_limit_order::*;
+pub use quote::*;
+pub use stake_lp::*;
+pub use swap::*;
+pub use swap_cl::*;
+pub use twap::*;
+pub use update::*;
 pub use withdraw::*;
-pub use swap::*;
\ No newline at end of file
+pub use withdraw_single::*;
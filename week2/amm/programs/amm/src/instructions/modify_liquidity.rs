@@ -0,0 +1,308 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::curve::concentrated::{amounts_for_liquidity, sqrt_price_at_tick};
+use crate::error::AmmError;
+use crate::events::{DepositEvent, WithdrawEvent};
+use crate::helpers::{self, check_expiration, transfer_fee_on_top};
+use crate::state::{ClPool, Config, Position, TickArray};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct ModifyLiquidity<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        has_one = mint_x,
+        has_one = mint_y,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"cl_pool", config.key().as_ref()],
+        bump = cl_pool.bump,
+        has_one = config,
+    )]
+    pub cl_pool: Account<'info, ClPool>,
+
+    #[account(
+        mut,
+        seeds = [b"position", position.position_mint.as_ref()],
+        bump = position.bump,
+        has_one = cl_pool,
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(
+        token::mint = position.position_mint,
+        token::authority = owner,
+        constraint = position_token_account.amount == 1 @ AmmError::InvalidPositionOwner,
+    )]
+    pub position_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, has_one = cl_pool)]
+    pub tick_array_lower: Box<Account<'info, TickArray>>,
+
+    /// CHECK: may be the same account as `tick_array_lower`, so it is only
+    /// loaded and checked in the handler when it isn't
+    #[account(mut)]
+    pub tick_array_upper: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = cl_pool,
+        associated_token::token_program = token_program_x,
+    )]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = cl_pool,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program_x,
+    )]
+    pub user_token_account_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program_y,
+    )]
+    pub user_token_account_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ModifyLiquidity<'info> {
+    pub fn increase_liquidity(
+        &mut self,
+        liquidity: u128,
+        max_x: u64,
+        max_y: u64,
+        expiration: i64,
+    ) -> Result<DepositEvent> {
+        check_expiration(expiration)?;
+        require!(!self.config.locked, AmmError::PoolLocked);
+        require!(liquidity > 0, AmmError::InvalidAmount);
+
+        let delta = i128::try_from(liquidity).map_err(|_| AmmError::Overflow)?;
+        let (x, y) = self.modify_liquidity(delta, true)?;
+
+        require!(x <= max_x && y <= max_y, AmmError::SlippageExceeded);
+
+        let x = self.deposit_token(true, x)?;
+        let y = self.deposit_token(false, y)?;
+
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+
+        Ok(DepositEvent {
+            config: self.config.key(),
+            user: self.owner.key(),
+            amount_x: x,
+            amount_y: y,
+            lp_amount: 0,
+            fee: None,
+            reserve_x: self.vault_x.amount,
+            reserve_y: self.vault_y.amount,
+            lp_supply: None,
+            liquidity: Some(self.cl_pool.liquidity),
+        })
+    }
+
+    pub fn decrease_liquidity(
+        &mut self,
+        liquidity: u128,
+        min_x: u64,
+        min_y: u64,
+        expiration: i64,
+    ) -> Result<WithdrawEvent> {
+        check_expiration(expiration)?;
+        // Like a plain withdraw, LPs can always exit a locked pool
+        require!(
+            liquidity > 0 && liquidity <= self.position.liquidity,
+            AmmError::InvalidAmount
+        );
+
+        let delta = -i128::try_from(liquidity).map_err(|_| AmmError::Overflow)?;
+        let (x, y) = self.modify_liquidity(delta, false)?;
+
+        let x = self.withdraw_token(true, x)?;
+        let y = self.withdraw_token(false, y)?;
+
+        // Checked after the transfers so any transfer fee counts against the minimums
+        require!(x >= min_x && y >= min_y, AmmError::SlippageExceeded);
+
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+
+        Ok(WithdrawEvent {
+            config: self.config.key(),
+            user: self.owner.key(),
+            amount_x: x,
+            amount_y: y,
+            lp_amount: 0,
+            fee: None,
+            reserve_x: self.vault_x.amount,
+            reserve_y: self.vault_y.amount,
+            lp_supply: None,
+            liquidity: Some(self.cl_pool.liquidity),
+        })
+    }
+
+    /// Applies `liquidity_delta` to the position, its two ticks and, if the
+    /// range is active, the pool. Returns the token amounts it is worth,
+    /// rounded up when the owner pays and down when they receive.
+    fn modify_liquidity(&mut self, liquidity_delta: i128, round_up: bool) -> Result<(u64, u64)> {
+        let (tick_lower, tick_upper) = (self.position.tick_lower, self.position.tick_upper);
+        let pool = &self.cl_pool;
+        let (tick_current, spacing) = (pool.tick_current, pool.tick_spacing);
+        let (global_x, global_y) = (pool.fee_growth_global_x, pool.fee_growth_global_y);
+
+        let lower = self.tick_array_lower.tick_mut(tick_lower, spacing)?;
+        lower.update(
+            tick_lower,
+            tick_current,
+            liquidity_delta,
+            false,
+            global_x,
+            global_y,
+        )?;
+        let lower = *lower;
+
+        let upper = if self.tick_array_upper.key() == self.tick_array_lower.key() {
+            let upper = self.tick_array_lower.tick_mut(tick_upper, spacing)?;
+            upper.update(
+                tick_upper,
+                tick_current,
+                liquidity_delta,
+                true,
+                global_x,
+                global_y,
+            )?;
+            *upper
+        } else {
+            let info = self.tick_array_upper.to_account_info();
+            let mut tick_array = TickArray::load(&info, self.cl_pool.key())?;
+            let upper = tick_array.tick_mut(tick_upper, spacing)?;
+            upper.update(
+                tick_upper,
+                tick_current,
+                liquidity_delta,
+                true,
+                global_x,
+                global_y,
+            )?;
+            let upper = *upper;
+            tick_array.store(&info)?;
+            upper
+        };
+
+        let (inside_x, inside_y) = self
+            .cl_pool
+            .fee_growth_inside(tick_lower, &lower, tick_upper, &upper);
+        self.position.update(liquidity_delta, inside_x, inside_y)?;
+
+        if tick_lower <= tick_current && tick_current < tick_upper {
+            self.cl_pool.liquidity = self
+                .cl_pool
+                .liquidity
+                .checked_add_signed(liquidity_delta)
+                .ok_or(AmmError::Overflow)?;
+        }
+
+        let (x, y) = amounts_for_liquidity(
+            self.cl_pool.sqrt_price,
+            sqrt_price_at_tick(tick_lower)?,
+            sqrt_price_at_tick(tick_upper)?,
+            liquidity_delta.unsigned_abs(),
+            round_up,
+        )?;
+
+        Ok((
+            u64::try_from(x).map_err(|_| AmmError::Overflow)?,
+            u64::try_from(y).map_err(|_| AmmError::Overflow)?,
+        ))
+    }
+
+    /// Sends enough on top of `amount` that the vault receives all of it
+    /// after any transfer fee. Returns the amount that arrived.
+    fn deposit_token(&mut self, is_x: bool, amount: u64) -> Result<u64> {
+        if amount == 0 {
+            return Ok(0);
+        }
+
+        let (from, vault, mint, token_program) = if is_x {
+            (
+                self.user_token_account_x.to_account_info(),
+                &mut self.vault_x,
+                &self.mint_x,
+                self.token_program_x.to_account_info(),
+            )
+        } else {
+            (
+                self.user_token_account_y.to_account_info(),
+                &mut self.vault_y,
+                &self.mint_y,
+                self.token_program_y.to_account_info(),
+            )
+        };
+
+        let amount = amount + transfer_fee_on_top(&mint.to_account_info(), amount)?;
+        helpers::transfer_tokens(
+            from,
+            vault,
+            mint,
+            self.owner.to_account_info(),
+            token_program,
+            &[],
+            amount,
+        )
+    }
+
+    /// Returns the amount that arrived in the owner's token account.
+    fn withdraw_token(&mut self, is_x: bool, amount: u64) -> Result<u64> {
+        if amount == 0 {
+            return Ok(0);
+        }
+
+        let (vault, to, mint, token_program) = if is_x {
+            (
+                self.vault_x.to_account_info(),
+                &mut self.user_token_account_x,
+                &self.mint_x,
+                self.token_program_x.to_account_info(),
+            )
+        } else {
+            (
+                self.vault_y.to_account_info(),
+                &mut self.user_token_account_y,
+                &self.mint_y,
+                self.token_program_y.to_account_info(),
+            )
+        };
+
+        helpers::transfer_from_cl_pool(&self.cl_pool, vault, to, mint, token_program, amount)
+    }
+}
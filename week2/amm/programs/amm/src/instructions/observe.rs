@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::OBSERVATION_INTERVAL;
+use crate::state::{Config, Observation, Oracle};
+
+#[derive(Accounts)]
+pub struct Observe<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = Oracle::INIT_SPACE,
+        seeds = [b"oracle", config.key().as_ref()],
+        bump,
+    )]
+    pub oracle: Box<Account<'info, Oracle>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Observe<'info> {
+    /// Permissionless: stores a snapshot of the cumulative prices so `get_twap`
+    /// can look back over a window. Calls closer than `OBSERVATION_INTERVAL` to
+    /// the previous snapshot are a no-op, so the history can't be flushed by spam.
+    pub fn observe(&mut self, bumps: &ObserveBumps) -> Result<()> {
+        self.config.check_no_flash_loan()?;
+
+        if self.oracle.config == Pubkey::default() {
+            self.oracle.config = self.config.key();
+            self.oracle.bump = bumps.oracle;
+        }
+
+        self.config.update_oracle()?;
+
+        let now = self.config.last_update_ts;
+        if let Some(latest) = self.oracle.latest() {
+            if now - latest.timestamp < OBSERVATION_INTERVAL {
+                return Ok(());
+            }
+        }
+
+        self.oracle.record(Observation {
+            timestamp: now,
+            price_x_cumulative: self.config.price_x_cumulative,
+            price_y_cumulative: self.config.price_y_cumulative,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        mint_to, set_authority, spl_token_2022::instruction::AuthorityType, Mint, MintTo,
+        SetAuthority, TokenAccount, TokenInterface,
+    },
+};
+
+use crate::constants::{MAX_TICK, MIN_TICK};
+use crate::error::AmmError;
+use crate::state::{ClPool, Position};
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"cl_pool", cl_pool.config.as_ref()],
+        bump = cl_pool.bump,
+    )]
+    pub cl_pool: Account<'info, ClPool>,
+
+    #[account(
+        init,
+        payer = owner,
+        mint::decimals = 0,
+        mint::authority = cl_pool,
+        mint::token_program = token_program,
+    )]
+    pub position_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = position_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub position_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"position", position_mint.key().as_ref()],
+        bump,
+        space = Position::INIT_SPACE,
+    )]
+    pub position: Account<'info, Position>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> OpenPosition<'info> {
+    pub fn open_position(
+        &mut self,
+        tick_lower: i32,
+        tick_upper: i32,
+        bumps: &OpenPositionBumps,
+    ) -> Result<()> {
+        let spacing = self.cl_pool.tick_spacing as i32;
+        require!(tick_lower < tick_upper, AmmError::InvalidTick);
+        require!(
+            tick_lower >= MIN_TICK && tick_upper <= MAX_TICK,
+            AmmError::InvalidTick
+        );
+        require!(
+            tick_lower % spacing == 0 && tick_upper % spacing == 0,
+            AmmError::InvalidTick
+        );
+
+        self.position.set_inner(Position {
+            cl_pool: self.cl_pool.key(),
+            position_mint: self.position_mint.key(),
+            tick_lower,
+            tick_upper,
+            liquidity: 0,
+            fee_growth_inside_x_last: 0,
+            fee_growth_inside_y_last: 0,
+            fees_owed_x: 0,
+            fees_owed_y: 0,
+            bump: bumps.position,
+        });
+
+        self.mint_position_token()
+    }
+
+    /// Mints the one token that represents the position, then drops the mint
+    /// authority so no second one can ever exist.
+    fn mint_position_token(&mut self) -> Result<()> {
+        let signer_seeds: &[&[&[u8]]] = &[&self.cl_pool.signer_seeds()];
+
+        let cpi_accounts = MintTo {
+            mint: self.position_mint.to_account_info(),
+            to: self.position_token_account.to_account_info(),
+            authority: self.cl_pool.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        mint_to(cpi_ctx, 1)?;
+
+        let cpi_accounts = SetAuthority {
+            current_authority: self.cl_pool.to_account_info(),
+            account_or_mint: self.position_mint.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        set_authority(cpi_ctx, AuthorityType::MintTokens, None)
+    }
+}
@@ -0,0 +1,130 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::error::AmmError;
+use crate::helpers::{self, check_expiration};
+use crate::state::{Config, LimitOrder};
+
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct PlaceLimitOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mint::token_program = token_program,
+        constraint = mint_in.key() == config.mint_x || mint_in.key() == config.mint_y
+            @ AmmError::InvalidMint,
+    )]
+    pub mint_in: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = owner,
+        seeds = [
+            b"order",
+            config.key().as_ref(),
+            owner.key().as_ref(),
+            seed.to_le_bytes().as_ref(),
+        ],
+        bump,
+        space = LimitOrder::INIT_SPACE,
+    )]
+    pub order: Account<'info, LimitOrder>,
+
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = mint_in,
+        associated_token::authority = order,
+        associated_token::token_program = token_program,
+    )]
+    pub order_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_in,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub owner_token_account_in: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> PlaceLimitOrder<'info> {
+    /// Escrows `amount_in` of `mint_in` to be sold for at least
+    /// `limit_amount_out` of the pool's other token, and `tip` lamports for
+    /// whoever fills it.
+    pub fn place_limit_order(
+        &mut self,
+        seed: u64,
+        amount_in: u64,
+        limit_amount_out: u64,
+        tip: u64,
+        expiration: i64,
+        bumps: &PlaceLimitOrderBumps,
+    ) -> Result<()> {
+        check_expiration(expiration)?;
+        require!(amount_in > 0, AmmError::InvalidAmount);
+        // A zero limit is a market order anyone could fill at any price
+        require!(limit_amount_out > 0, AmmError::InvalidAmount);
+
+        let amount_in = self.deposit_tokens(amount_in)?;
+        require!(amount_in > 0, AmmError::InvalidAmount);
+        self.deposit_tip(tip)?;
+
+        self.order.set_inner(LimitOrder {
+            config: self.config.key(),
+            owner: self.owner.key(),
+            seed,
+            is_x: self.mint_in.key() == self.config.mint_x,
+            amount_in,
+            limit_amount_out,
+            tip,
+            expiration,
+            bump: bumps.order,
+        });
+
+        Ok(())
+    }
+
+    /// Returns the amount that arrived in the escrow.
+    fn deposit_tokens(&mut self, amount: u64) -> Result<u64> {
+        helpers::transfer_tokens(
+            self.owner_token_account_in.to_account_info(),
+            &mut self.order_vault,
+            &self.mint_in,
+            self.owner.to_account_info(),
+            self.token_program.to_account_info(),
+            &[],
+            amount,
+        )
+    }
+
+    fn deposit_tip(&mut self, tip: u64) -> Result<()> {
+        if tip == 0 {
+            return Ok(());
+        }
+
+        let cpi_accounts = Transfer {
+            from: self.owner.to_account_info(),
+            to: self.order.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), cpi_accounts);
+        transfer(cpi_ctx, tip)
+    }
+}
@@ -0,0 +1,190 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::error::AmmError;
+use crate::helpers::{check_expiration, epoch_transfer_fee, transfer_fee};
+use crate::instructions::{deposit_amounts, withdraw_quote};
+use crate::state::Config;
+
+/// What `swap` would do with the same arguments
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct SwapQuote {
+    /// Leaving the user's token account
+    pub amount_in: u64,
+    /// Arriving in the user's token account
+    pub amount_out: u64,
+    /// Whole swap fee, protocol share included
+    pub fee: u64,
+    pub protocol_fee: u64,
+    /// How far the swap moves the spot price, in basis points
+    pub price_impact: u64,
+    pub reserve_x: u64,
+    pub reserve_y: u64,
+}
+
+/// What `deposit` would do with the same arguments. Deposits are proportional,
+/// so there is no fee or price impact to report.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct DepositQuote {
+    /// Leaving the user's token accounts
+    pub amount_x: u64,
+    pub amount_y: u64,
+    pub lp_amount: u64,
+}
+
+/// What `withdraw` would do with the same arguments. Withdrawals are
+/// proportional, so there is no fee or price impact to report.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct WithdrawQuote {
+    /// Arriving in the user's token accounts
+    pub amount_x: u64,
+    pub amount_y: u64,
+    pub lp_amount: u64,
+}
+
+#[derive(Accounts)]
+pub struct Quote<'info> {
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        has_one = mint_x,
+        has_one = mint_y,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump
+    )]
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        associated_token::mint = mint_x,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_x,
+    )]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+}
+
+impl<'info> Quote<'info> {
+    /// Runs the same checks and curve math as `swap` without moving anything,
+    /// so it fails wherever `swap` would.
+    pub fn quote_swap(
+        &self,
+        is_x: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+        expiration: i64,
+    ) -> Result<SwapQuote> {
+        check_expiration(expiration)?;
+        require!(!self.config.locked, AmmError::PoolLocked);
+        self.config.check_no_flash_loan()?;
+        require!(amount_in > 0, AmmError::InvalidAmount);
+
+        let (reserve_x, reserve_y) = (self.vault_x.amount, self.vault_y.amount);
+        let (mint_in, mint_out) = if is_x {
+            (self.mint_x.to_account_info(), self.mint_y.to_account_info())
+        } else {
+            (self.mint_y.to_account_info(), self.mint_x.to_account_info())
+        };
+
+        let received = amount_in - transfer_fee(&mint_in, amount_in)?;
+        let swap =
+            self.config
+                .swap_amounts(reserve_x, reserve_y, self.mint_lp.supply, is_x, received)?;
+        let amount_out = swap.amount_out - transfer_fee(&mint_out, swap.amount_out)?;
+
+        require!(amount_out >= min_amount_out, AmmError::SlippageExceeded);
+
+        let (reserve_x_after, reserve_y_after) = if is_x {
+            (
+                reserve_x + received - swap.protocol_fee,
+                reserve_y - swap.amount_out,
+            )
+        } else {
+            (
+                reserve_x - swap.amount_out,
+                reserve_y + received - swap.protocol_fee,
+            )
+        };
+
+        // Checked on a copy, a quote doesn't open a breaker window
+        let mut config = (*self.config).clone();
+        config.check_price_move((reserve_x, reserve_y), (reserve_x_after, reserve_y_after))?;
+
+        Ok(SwapQuote {
+            amount_in,
+            amount_out,
+            fee: (received as u128 * swap.fee as u128 / 10_000) as u64,
+            protocol_fee: swap.protocol_fee,
+            price_impact: self
+                .config
+                .price_move_bps((reserve_x, reserve_y), (reserve_x_after, reserve_y_after))?,
+            reserve_x: reserve_x_after,
+            reserve_y: reserve_y_after,
+        })
+    }
+
+    /// Runs the same checks and math as `deposit` without moving anything.
+    pub fn quote_deposit(
+        &self,
+        amount: u64,
+        max_x: u64,
+        max_y: u64,
+        expiration: i64,
+    ) -> Result<DepositQuote> {
+        check_expiration(expiration)?;
+        require!(!self.config.locked, AmmError::PoolLocked);
+        self.config.check_no_flash_loan()?;
+
+        deposit_amounts(
+            self.vault_x.amount,
+            self.vault_y.amount,
+            self.mint_lp.supply,
+            amount,
+            max_x,
+            max_y,
+            epoch_transfer_fee(&self.mint_x.to_account_info())?.as_ref(),
+            epoch_transfer_fee(&self.mint_y.to_account_info())?.as_ref(),
+        )
+    }
+
+    /// Runs the same checks and math as `withdraw` without moving anything.
+    pub fn quote_withdraw(
+        &self,
+        amount: u64,
+        min_x: u64,
+        min_y: u64,
+        expiration: i64,
+    ) -> Result<WithdrawQuote> {
+        check_expiration(expiration)?;
+        self.config.check_no_flash_loan()?;
+
+        withdraw_quote(
+            self.vault_x.amount,
+            self.vault_y.amount,
+            self.mint_lp.supply,
+            amount,
+            min_x,
+            min_y,
+            epoch_transfer_fee(&self.mint_x.to_account_info())?.as_ref(),
+            epoch_transfer_fee(&self.mint_y.to_account_info())?.as_ref(),
+        )
+    }
+}
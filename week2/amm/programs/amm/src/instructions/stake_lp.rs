@@ -0,0 +1,132 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::error::AmmError;
+use crate::helpers;
+use crate::state::{Config, Farm, Stake};
+
+#[derive(Accounts)]
+pub struct StakeLp<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"farm", config.key().as_ref()],
+        bump = farm.bump,
+        has_one = config,
+    )]
+    pub farm: Account<'info, Farm>,
+
+    #[account(
+        init_if_needed,
+        payer = owner,
+        seeds = [b"stake", farm.key().as_ref(), owner.key().as_ref()],
+        bump,
+        space = Stake::INIT_SPACE,
+    )]
+    pub stake: Account<'info, Stake>,
+
+    #[account(
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump
+    )]
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = farm,
+        associated_token::token_program = token_program,
+    )]
+    pub lp_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token_account_lp: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> StakeLp<'info> {
+    pub fn stake_lp(&mut self, amount: u64, bumps: &StakeLpBumps) -> Result<()> {
+        require!(amount > 0, AmmError::InvalidAmount);
+
+        // First stake in this farm
+        if self.stake.farm == Pubkey::default() {
+            self.stake.farm = self.farm.key();
+            self.stake.owner = self.owner.key();
+            self.stake.bump = bumps.stake;
+        }
+
+        // Only what reached the vault is staked
+        let amount = self.deposit_lp(amount)?;
+
+        self.farm.update(Clock::get()?.unix_timestamp)?;
+        let delta = i64::try_from(amount).map_err(|_| AmmError::Overflow)?;
+        self.stake.update(delta, self.farm.reward_per_share)?;
+        self.farm.total_staked = self
+            .farm
+            .total_staked
+            .checked_add(amount)
+            .ok_or(AmmError::Overflow)?;
+
+        Ok(())
+    }
+
+    /// Rewards earned so far stay owed until `claim`.
+    pub fn unstake_lp(&mut self, amount: u64) -> Result<()> {
+        require!(amount > 0, AmmError::InvalidAmount);
+        require!(amount <= self.stake.amount, AmmError::InvalidAmount);
+
+        self.farm.update(Clock::get()?.unix_timestamp)?;
+        let delta = i64::try_from(amount).map_err(|_| AmmError::Overflow)?;
+        self.stake.update(-delta, self.farm.reward_per_share)?;
+        self.farm.total_staked -= amount;
+
+        self.withdraw_lp(amount)?;
+
+        Ok(())
+    }
+
+    /// Returns the amount that arrived in the vault.
+    fn deposit_lp(&mut self, amount: u64) -> Result<u64> {
+        helpers::transfer_tokens(
+            self.user_token_account_lp.to_account_info(),
+            &mut self.lp_vault,
+            &self.mint_lp,
+            self.owner.to_account_info(),
+            self.token_program.to_account_info(),
+            &[],
+            amount,
+        )
+    }
+
+    /// Returns the amount that arrived in the owner's token account.
+    fn withdraw_lp(&mut self, amount: u64) -> Result<u64> {
+        helpers::transfer_tokens(
+            self.lp_vault.to_account_info(),
+            &mut self.user_token_account_lp,
+            &self.mint_lp,
+            self.farm.to_account_info(),
+            self.token_program.to_account_info(),
+            &[&self.farm.signer_seeds()],
+            amount,
+        )
+    }
+}
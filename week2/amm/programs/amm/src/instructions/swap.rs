@@ -1,26 +1,34 @@
+use std::collections::BTreeSet;
+
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{transfer_checked, Mint, Token, TokenAccount, TransferChecked},
+    token_interface::{Mint, TokenAccount, TokenInterface},
 };
-use constant_product_curve::{ConstantProduct, LiquidityPair};
 
 use crate::error::AmmError;
+use crate::events::SwapEvent;
+use crate::helpers::{
+    self, check_expiration, is_native_mint, is_transient_native, transfer_fee_on_top, unwrap_sol,
+    wrap_sol,
+};
 use crate::state::Config;
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct Swap<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
 
-    #[account(mint::token_program = token_program)]
-    pub mint_x: Account<'info, Mint>,
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
 
-    #[account(mint::token_program = token_program)]
-    pub mint_y: Account<'info, Mint>,
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
-        seeds = [b"config"],
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
         bump = config.config_bump,
         has_one = mint_x,
         has_one = mint_y,
@@ -31,139 +39,452 @@ pub struct Swap<'info> {
         seeds = [b"lp", config.key().as_ref()],
         bump = config.lp_bump
     )]
-    pub mint_lp: Account<'info, Mint>,
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
         associated_token::mint = mint_x,
         associated_token::authority = config,
-        associated_token::token_program = token_program,
+        associated_token::token_program = token_program_x,
     )]
-    pub vault_x: Account<'info, TokenAccount>,
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
         associated_token::mint = mint_y,
         associated_token::authority = config,
-        associated_token::token_program = token_program,
+        associated_token::token_program = token_program_y,
     )]
-    pub vault_y: Account<'info, TokenAccount>,
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         init_if_needed,
         payer = user,
         associated_token::mint = mint_x,
         associated_token::authority = user,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_x
     )]
-    pub user_token_account_x: Account<'info, TokenAccount>,
+    pub user_token_account_x: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         init_if_needed,
         payer = user,
         associated_token::mint = mint_y,
         associated_token::authority = user,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_y
+    )]
+    pub user_token_account_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol", config.key().as_ref(), mint_x.key().as_ref()],
+        bump = config.protocol_x_bump,
     )]
-    pub user_token_account_y: Account<'info, TokenAccount>,
+    pub protocol_vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    pub token_program: Program<'info, Token>,
+    #[account(
+        mut,
+        seeds = [b"protocol", config.key().as_ref(), mint_y.key().as_ref()],
+        bump = config.protocol_y_bump,
+    )]
+    pub protocol_vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> Swap<'info> {
-    pub fn swap(&mut self, is_x: bool, amount_in: u64, min_amount_out: u64) -> Result<()> {
+    pub fn swap(
+        &mut self,
+        is_x: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+        expiration: i64,
+    ) -> Result<SwapEvent> {
+        check_expiration(expiration)?;
+        let transient = self.transient_native();
+
+        let event = self.swap_exact_in(is_x, amount_in)?;
+
+        require!(
+            event.amount_out >= min_amount_out,
+            AmmError::SlippageExceeded
+        );
+
+        self.unwrap_native(transient)?;
+
+        Ok(event)
+    }
+
+    /// Swaps `amount_in` through every pool in `remaining_accounts`, each given as
+    /// a full set of `Swap` accounts, after the pool in `self`. The output of one
+    /// hop is the input of the next, and only the final output is checked.
+    /// Returns one event per hop.
+    pub fn swap_route(
+        &mut self,
+        remaining_accounts: &'info [AccountInfo<'info>],
+        is_x: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+        expiration: i64,
+    ) -> Result<Vec<SwapEvent>> {
+        check_expiration(expiration)?;
+        let transient = self.transient_native();
+
+        let mut events = vec![self.swap_exact_in(is_x, amount_in)?];
+        let mut amount = events[0].amount_out;
+        let mut mint = if is_x {
+            self.mint_y.key()
+        } else {
+            self.mint_x.key()
+        };
+        let mut pools = vec![self.config.key()];
+
+        let mut accounts = remaining_accounts;
+        while !accounts.is_empty() {
+            // Parsed one hop at a time so each sees the balances the previous hop left
+            let mut hop = Swap::try_accounts(
+                &crate::ID,
+                &mut accounts,
+                &[],
+                &mut SwapBumps::default(),
+                &mut BTreeSet::new(),
+            )?;
+            let hop_transient = hop.transient_native();
+
+            require_keys_eq!(hop.user.key(), self.user.key(), AmmError::InvalidRoute);
+            // A pool's config is only written back once, so it can't appear twice
+            require!(!pools.contains(&hop.config.key()), AmmError::InvalidRoute);
+            pools.push(hop.config.key());
+
+            let hop_is_x = if hop.mint_x.key() == mint {
+                true
+            } else if hop.mint_y.key() == mint {
+                false
+            } else {
+                return err!(AmmError::InvalidRoute);
+            };
+
+            let event = hop.swap_exact_in(hop_is_x, amount)?;
+            amount = event.amount_out;
+            events.push(event);
+            mint = if hop_is_x {
+                hop.mint_y.key()
+            } else {
+                hop.mint_x.key()
+            };
+
+            // The last pool is the one holding the route's output
+            if accounts.is_empty() {
+                hop.unwrap_native(hop_transient)?;
+            }
+
+            hop.exit(&crate::ID)?;
+        }
+
+        require!(amount >= min_amount_out, AmmError::SlippageExceeded);
+
+        self.unwrap_native(transient)?;
+
+        Ok(events)
+    }
+
+    /// A single exact-input swap without a slippage check. The event's
+    /// `amount_out` is what arrived in the user's token account.
+    pub fn swap_exact_in(&mut self, is_x: bool, amount_in: u64) -> Result<SwapEvent> {
+        require!(!self.config.locked, AmmError::PoolLocked);
+        self.config.check_no_flash_loan()?;
         require!(amount_in > 0, AmmError::InvalidAmount);
 
-        let mut curve = ConstantProduct::init(
-            self.vault_x.amount,
-            self.vault_y.amount,
+        self.config.update_oracle()?;
+
+        let (reserve_x, reserve_y) = (self.vault_x.amount, self.vault_y.amount);
+
+        // Price only what actually reached the vault after any transfer fee
+        self.wrap_native(is_x, amount_in)?;
+        let received = self.deposit_token(is_x, amount_in)?;
+
+        let (vault_in, vault_out, protocol_vault_in, mint_in, mint_out, token_program_in) = if is_x
+        {
+            (
+                &mut self.vault_x,
+                &mut self.vault_y,
+                &mut self.protocol_vault_x,
+                &self.mint_x,
+                &self.mint_y,
+                &self.token_program_x,
+            )
+        } else {
+            (
+                &mut self.vault_y,
+                &mut self.vault_x,
+                &mut self.protocol_vault_y,
+                &self.mint_y,
+                &self.mint_x,
+                &self.token_program_y,
+            )
+        };
+        let (token_program_out, to) = if is_x {
+            (&self.token_program_y, &mut self.user_token_account_y)
+        } else {
+            (&self.token_program_x, &mut self.user_token_account_x)
+        };
+        let (swap, amount_out) = helpers::settle_swap(
+            &mut self.config,
             self.mint_lp.supply,
-            self.config.fee,
-            None,
-        )
-        .map_err(AmmError::from)?;
+            is_x,
+            (reserve_x, reserve_y),
+            received,
+            vault_in,
+            vault_out,
+            protocol_vault_in,
+            mint_in,
+            mint_out,
+            token_program_in.to_account_info(),
+            token_program_out.to_account_info(),
+            to,
+        )?;
+
+        Ok(self.swap_event(is_x, received, amount_out, swap.fee, swap.protocol_fee))
+    }
 
-        let p = if is_x {
-            LiquidityPair::X
+    pub fn swap_exact_out(
+        &mut self,
+        is_x: bool,
+        amount_out: u64,
+        max_amount_in: u64,
+        expiration: i64,
+    ) -> Result<SwapEvent> {
+        check_expiration(expiration)?;
+        require!(!self.config.locked, AmmError::PoolLocked);
+        self.config.check_no_flash_loan()?;
+        require!(amount_out > 0, AmmError::InvalidAmount);
+        let transient = self.transient_native();
+
+        self.config.update_oracle()?;
+
+        let (reserve_x, reserve_y) = (self.vault_x.amount, self.vault_y.amount);
+        let fee = self.config.swap_fee()?;
+        let (reserve_in, reserve_out, mint_in, mint_out) = if is_x {
+            (
+                self.vault_x.amount,
+                self.vault_y.amount,
+                self.mint_x.to_account_info(),
+                self.mint_y.to_account_info(),
+            )
         } else {
-            LiquidityPair::Y
+            (
+                self.vault_y.amount,
+                self.vault_x.amount,
+                self.mint_y.to_account_info(),
+                self.mint_x.to_account_info(),
+            )
         };
 
-        let swap_result = curve
-            .swap(p, amount_in, min_amount_out)
-            .map_err(AmmError::from)?;
+        // The vault has to send extra so the user still gets exactly amount_out,
+        // and the user has to send extra so the vault still gets what the curve needs
+        let vault_out = amount_out + transfer_fee_on_top(&mint_out, amount_out)?;
+        let vault_in =
+            self.config
+                .curve()?
+                .swap_amount_in(reserve_in, reserve_out, fee, is_x, vault_out)?;
+        let amount_in = vault_in + transfer_fee_on_top(&mint_in, vault_in)?;
 
-        require!(swap_result.deposit != 0, AmmError::InvalidAmount);
-        require!(swap_result.withdraw != 0, AmmError::InvalidAmount);
+        require!(amount_in <= max_amount_in, AmmError::SlippageExceeded);
+        self.config.check_trade_size(vault_in, reserve_in)?;
 
-        self.deposit_token(is_x, swap_result.deposit)?;
-        self.withdraw_token(!is_x, swap_result.withdraw)?;
+        self.wrap_native(is_x, amount_in)?;
+        let received = self.deposit_token(is_x, amount_in)?;
+        require!(received >= vault_in, AmmError::InvalidAmount);
 
-        Ok(())
+        let protocol_fee = self.transfer_protocol_fee(is_x, received, fee)?;
+        let amount_out = self.withdraw_token(!is_x, vault_out)?;
+
+        self.record_price_move(reserve_x, reserve_y)?;
+        self.config
+            .sync_reserves(self.vault_x.amount, self.vault_y.amount);
+        self.unwrap_native(transient)?;
+
+        Ok(self.swap_event(is_x, received, amount_out, fee, protocol_fee))
     }
 
-    pub fn deposit_token(&mut self, is_x: bool, amount: u64) -> Result<()> {
-        let (from, to, mint, decimals) = if is_x {
+    /// Wraps the user's native SOL into their token account when the input
+    /// side is the native mint.
+    fn wrap_native(&mut self, is_x: bool, amount: u64) -> Result<()> {
+        let (mint, token_account, token_program) = if is_x {
+            (
+                self.mint_x.key(),
+                &mut self.user_token_account_x,
+                self.token_program_x.to_account_info(),
+            )
+        } else {
+            (
+                self.mint_y.key(),
+                &mut self.user_token_account_y,
+                self.token_program_y.to_account_info(),
+            )
+        };
+
+        if !is_native_mint(&mint) {
+            return Ok(());
+        }
+
+        wrap_sol(
+            self.user.to_account_info(),
+            token_account,
+            amount,
+            token_program,
+            self.system_program.to_account_info(),
+        )
+    }
+
+    /// Which of the user's token accounts, x then y, only carry native SOL
+    /// through this instruction. Read before anything moves.
+    pub fn transient_native(&self) -> [bool; 2] {
+        [
+            is_transient_native(&self.mint_x.key(), &self.user_token_account_x),
+            is_transient_native(&self.mint_y.key(), &self.user_token_account_y),
+        ]
+    }
+
+    /// Closes the `transient` wrapped SOL accounts so the wallet ends up
+    /// holding plain SOL.
+    pub fn unwrap_native(&mut self, transient: [bool; 2]) -> Result<()> {
+        let sides = [
             (
                 self.user_token_account_x.to_account_info(),
+                self.token_program_x.to_account_info(),
+            ),
+            (
+                self.user_token_account_y.to_account_info(),
+                self.token_program_y.to_account_info(),
+            ),
+        ];
+
+        for ((token_account, token_program), transient) in sides.into_iter().zip(transient) {
+            if transient {
+                unwrap_sol(self.user.to_account_info(), token_account, token_program)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Describes a finished swap, with the reserves `record_price_move` reloaded.
+    fn swap_event(
+        &self,
+        is_x: bool,
+        amount_in: u64,
+        amount_out: u64,
+        fee: u16,
+        protocol_fee: u64,
+    ) -> SwapEvent {
+        SwapEvent {
+            config: self.config.key(),
+            user: self.user.key(),
+            is_x,
+            amount_in,
+            amount_out,
+            fee: (amount_in as u128 * fee as u128 / 10_000) as u64,
+            protocol_fee,
+            reserve_x: self.vault_x.amount,
+            reserve_y: self.vault_y.amount,
+            sqrt_price: None,
+            liquidity: None,
+        }
+    }
+
+    /// Feeds how far the swap just moved the price into the dynamic fee, and
+    /// trips the circuit breaker if that was too far.
+    fn record_price_move(&mut self, reserve_x: u64, reserve_y: u64) -> Result<()> {
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+
+        let after = (self.vault_x.amount, self.vault_y.amount);
+        self.config
+            .check_price_move((reserve_x, reserve_y), after)?;
+        self.config.record_price_move((reserve_x, reserve_y), after)
+    }
+
+    /// Moves the protocol's cut of the fee on `amount_in` out of the vault,
+    /// the rest of the fee stays with LPs. Returns the amount moved.
+    pub fn transfer_protocol_fee(&mut self, is_x: bool, amount_in: u64, fee: u16) -> Result<u64> {
+        let (vault, protocol_vault, mint, token_program) = if is_x {
+            (
                 self.vault_x.to_account_info(),
-                self.mint_x.to_account_info(),
-                self.mint_x.decimals,
+                &mut self.protocol_vault_x,
+                &self.mint_x,
+                self.token_program_x.to_account_info(),
             )
         } else {
             (
-                self.user_token_account_y.to_account_info(),
                 self.vault_y.to_account_info(),
-                self.mint_y.to_account_info(),
-                self.mint_y.decimals,
+                &mut self.protocol_vault_y,
+                &self.mint_y,
+                self.token_program_y.to_account_info(),
             )
         };
 
-        let cpi_program = self.token_program.to_account_info();
-        let cpi_accounts = TransferChecked {
-            from,
-            to,
-            authority: self.user.to_account_info(),
+        helpers::transfer_protocol_fee(
+            &self.config,
+            vault,
+            protocol_vault,
             mint,
-        };
-
-        let cpi_context = CpiContext::new(cpi_program, cpi_accounts);
-        transfer_checked(cpi_context, amount, decimals)
+            token_program,
+            amount_in,
+            fee,
+        )
     }
 
-    pub fn withdraw_token(&mut self, is_x: bool, amount: u64) -> Result<()> {
-        let (from, to, mint, decimals) = if is_x {
+    /// Returns the amount that arrived in the vault.
+    pub fn deposit_token(&mut self, is_x: bool, amount: u64) -> Result<u64> {
+        let (from, to, mint, token_program) = if is_x {
             (
-                self.vault_x.to_account_info(),
                 self.user_token_account_x.to_account_info(),
-                self.mint_x.to_account_info(),
-                self.mint_x.decimals,
+                &mut self.vault_x,
+                &self.mint_x,
+                self.token_program_x.to_account_info(),
             )
         } else {
             (
-                self.vault_y.to_account_info(),
                 self.user_token_account_y.to_account_info(),
-                self.mint_y.to_account_info(),
-                self.mint_y.decimals,
+                &mut self.vault_y,
+                &self.mint_y,
+                self.token_program_y.to_account_info(),
             )
         };
 
-        let cpi_program = self.token_program.to_account_info();
-        let cpi_accounts = TransferChecked {
+        helpers::transfer_tokens(
             from,
             to,
             mint,
-            authority: self.config.to_account_info(),
-        };
+            self.user.to_account_info(),
+            token_program,
+            &[],
+            amount,
+        )
+    }
 
-        let signer_seeds: &[&[&[u8]]] = &[&[
-            b"config",
-            &[self.config.config_bump],
-        ]];
+    /// Returns the amount that arrived in the user's token account.
+    pub fn withdraw_token(&mut self, is_x: bool, amount: u64) -> Result<u64> {
+        let (from, to, mint, token_program) = if is_x {
+            (
+                self.vault_x.to_account_info(),
+                &mut self.user_token_account_x,
+                &self.mint_x,
+                self.token_program_x.to_account_info(),
+            )
+        } else {
+            (
+                self.vault_y.to_account_info(),
+                &mut self.user_token_account_y,
+                &self.mint_y,
+                self.token_program_y.to_account_info(),
+            )
+        };
 
-        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
-        transfer_checked(cpi_context, amount, decimals)
+        helpers::transfer_from_pool(&self.config, from, to, mint, token_program, amount)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,361 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::constants::{MAX_TICK, MIN_TICK};
+use crate::curve::concentrated::{compute_swap_step, sqrt_price_at_tick, tick_at_sqrt_price};
+use crate::error::AmmError;
+use crate::events::SwapEvent;
+use crate::helpers::{self, check_expiration};
+use crate::state::{sqrt_price_move_bps, ClPool, Config, TickArray};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SwapCl<'info> {
+    pub user: Signer<'info>,
+
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        has_one = mint_x,
+        has_one = mint_y,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"cl_pool", config.key().as_ref()],
+        bump = cl_pool.bump,
+        has_one = config,
+    )]
+    pub cl_pool: Account<'info, ClPool>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = cl_pool,
+        associated_token::token_program = token_program_x,
+    )]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = cl_pool,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = user,
+        associated_token::token_program = token_program_x,
+    )]
+    pub user_token_account_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = user,
+        associated_token::token_program = token_program_y,
+    )]
+    pub user_token_account_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol", config.key().as_ref(), mint_x.key().as_ref()],
+        bump = config.protocol_x_bump,
+    )]
+    pub protocol_vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol", config.key().as_ref(), mint_y.key().as_ref()],
+        bump = config.protocol_y_bump,
+    )]
+    pub protocol_vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+}
+
+impl<'info> SwapCl<'info> {
+    /// Exact-input swap through the ticks in `tick_arrays`, which have to
+    /// start at the array holding the current tick and follow each other in
+    /// the swap direction. Whatever the arrays can't absorb is refunded. Runs
+    /// the same protocol fee, circuit breaker and dynamic fee hooks as `swap`.
+    pub fn swap_cl(
+        &mut self,
+        tick_arrays: &[AccountInfo],
+        is_x: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+        expiration: i64,
+    ) -> Result<SwapEvent> {
+        check_expiration(expiration)?;
+        require!(!self.config.locked, AmmError::PoolLocked);
+        require!(amount_in > 0, AmmError::InvalidAmount);
+        require!(!tick_arrays.is_empty(), AmmError::InvalidTickArray);
+
+        // The oracle follows the full-range pool, this only brings it up to now
+        self.config.update_oracle()?;
+
+        let reserve_in = if is_x {
+            self.vault_x.amount
+        } else {
+            self.vault_y.amount
+        };
+
+        // Price only what actually reached the vault after any transfer fee
+        let received = self.deposit_token(is_x, amount_in)?;
+        self.config.check_trade_size(received, reserve_in)?;
+
+        let sqrt_price_before = self.cl_pool.sqrt_price;
+        let (amount_used, amount_out, fee, protocol_fee) =
+            self.swap_through_ticks(tick_arrays, is_x, received)?;
+        require!(amount_out > 0, AmmError::InvalidAmount);
+
+        let clock = Clock::get()?;
+        self.cl_pool
+            .check_price_move_at(&self.config, sqrt_price_before, clock.slot)?;
+        // `record_price_move`, measured on this pool's own price
+        let price_move = sqrt_price_move_bps(sqrt_price_before, self.cl_pool.sqrt_price);
+        self.config
+            .add_volatility_at(price_move, clock.unix_timestamp);
+
+        if protocol_fee > 0 {
+            self.transfer_protocol_fee(is_x, protocol_fee)?;
+        }
+        if amount_used < received {
+            self.withdraw_token(is_x, received - amount_used)?;
+        }
+        let amount_out = self.withdraw_token(!is_x, amount_out)?;
+
+        require!(amount_out >= min_amount_out, AmmError::SlippageExceeded);
+
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+
+        Ok(SwapEvent {
+            config: self.config.key(),
+            user: self.user.key(),
+            is_x,
+            amount_in: amount_used,
+            amount_out,
+            fee,
+            protocol_fee,
+            reserve_x: self.vault_x.amount,
+            reserve_y: self.vault_y.amount,
+            sqrt_price: Some(self.cl_pool.sqrt_price),
+            liquidity: Some(self.cl_pool.liquidity),
+        })
+    }
+
+    /// Moves the pool price, crossing initialized ticks on the way. Selling x
+    /// pushes the price (y per x) down. Returns the input used, the output,
+    /// the fee and the protocol's cut of it, which positions don't earn.
+    fn swap_through_ticks(
+        &mut self,
+        tick_arrays: &[AccountInfo],
+        x_to_y: bool,
+        amount_in: u64,
+    ) -> Result<(u64, u64, u64, u64)> {
+        let pool_key = self.cl_pool.key();
+        let spacing = self.cl_pool.tick_spacing;
+        let fee = self.config.swap_fee()?;
+        let protocol_fee_bps = self.config.protocol_fee as u128;
+
+        let mut arrays = tick_arrays
+            .iter()
+            .map(|info| TickArray::load(info, pool_key))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Skipping an array would skip its liquidity, so they must be contiguous
+        let step = if x_to_y {
+            -TickArray::span(spacing)
+        } else {
+            TickArray::span(spacing)
+        };
+        require!(
+            arrays[0].contains(self.cl_pool.tick_current, spacing),
+            AmmError::InvalidTickArray
+        );
+        for pair in arrays.windows(2) {
+            require!(
+                pair[1].start_tick_index == pair[0].start_tick_index + step,
+                AmmError::InvalidTickArray
+            );
+        }
+
+        let pool = &mut self.cl_pool;
+        let mut amount_remaining = amount_in as u128;
+        let mut amount_out = 0u128;
+        let mut total_fee = 0u128;
+        let mut protocol_fee = 0u128;
+        let mut index = 0;
+
+        while amount_remaining > 0 {
+            let (next_tick, initialized) = loop {
+                match arrays[index].next_initialized_tick(pool.tick_current, spacing, x_to_y) {
+                    Some(tick) => break (tick, true),
+                    None if index + 1 < arrays.len() => index += 1,
+                    None => break (arrays[index].last_tick(spacing, x_to_y), false),
+                }
+            };
+            let next_tick = next_tick.clamp(MIN_TICK, MAX_TICK);
+            let sqrt_price_target = sqrt_price_at_tick(next_tick)?;
+
+            // Ran past the last array passed in
+            let exhausted = if x_to_y {
+                sqrt_price_target >= pool.sqrt_price
+            } else {
+                sqrt_price_target <= pool.sqrt_price
+            };
+            if exhausted && !initialized {
+                break;
+            }
+
+            let swap_step = compute_swap_step(
+                pool.sqrt_price,
+                sqrt_price_target,
+                pool.liquidity,
+                amount_remaining,
+                fee,
+            )?;
+
+            amount_remaining -= swap_step.amount_in + swap_step.fee_amount;
+            amount_out += swap_step.amount_out;
+
+            total_fee += swap_step.fee_amount;
+            let step_protocol_fee = swap_step.fee_amount * protocol_fee_bps / 10_000;
+            protocol_fee += step_protocol_fee;
+
+            let lp_fee = swap_step.fee_amount - step_protocol_fee;
+            if let Some(fee_growth) = (lp_fee << 64).checked_div(pool.liquidity) {
+                if x_to_y {
+                    pool.fee_growth_global_x = pool.fee_growth_global_x.wrapping_add(fee_growth);
+                } else {
+                    pool.fee_growth_global_y = pool.fee_growth_global_y.wrapping_add(fee_growth);
+                }
+            }
+
+            pool.sqrt_price = swap_step.sqrt_price_next;
+            if swap_step.sqrt_price_next == sqrt_price_target {
+                if initialized {
+                    let liquidity_net = arrays[index]
+                        .tick_mut(next_tick, spacing)?
+                        .cross(pool.fee_growth_global_x, pool.fee_growth_global_y);
+                    let liquidity_net = if x_to_y {
+                        -liquidity_net
+                    } else {
+                        liquidity_net
+                    };
+                    pool.liquidity = pool
+                        .liquidity
+                        .checked_add_signed(liquidity_net)
+                        .ok_or(AmmError::Overflow)?;
+                }
+                pool.tick_current = if x_to_y { next_tick - 1 } else { next_tick };
+            } else {
+                pool.tick_current = tick_at_sqrt_price(pool.sqrt_price)?;
+            }
+        }
+
+        for (info, tick_array) in tick_arrays.iter().zip(arrays.iter()) {
+            tick_array.store(info)?;
+        }
+
+        Ok((
+            amount_in - amount_remaining as u64,
+            u64::try_from(amount_out).map_err(|_| AmmError::Overflow)?,
+            total_fee as u64,
+            protocol_fee as u64,
+        ))
+    }
+
+    /// Moves the protocol's cut of the fee out of the input vault, to the same
+    /// protocol vaults the full-range pool pays into.
+    fn transfer_protocol_fee(&mut self, is_x: bool, amount: u64) -> Result<u64> {
+        let (vault, protocol_vault, mint, token_program) = if is_x {
+            (
+                self.vault_x.to_account_info(),
+                &mut self.protocol_vault_x,
+                &self.mint_x,
+                self.token_program_x.to_account_info(),
+            )
+        } else {
+            (
+                self.vault_y.to_account_info(),
+                &mut self.protocol_vault_y,
+                &self.mint_y,
+                self.token_program_y.to_account_info(),
+            )
+        };
+
+        helpers::transfer_from_cl_pool(
+            &self.cl_pool,
+            vault,
+            protocol_vault,
+            mint,
+            token_program,
+            amount,
+        )
+    }
+
+    /// Returns the amount that arrived in the vault.
+    fn deposit_token(&mut self, is_x: bool, amount: u64) -> Result<u64> {
+        let (from, vault, mint, token_program) = if is_x {
+            (
+                self.user_token_account_x.to_account_info(),
+                &mut self.vault_x,
+                &self.mint_x,
+                self.token_program_x.to_account_info(),
+            )
+        } else {
+            (
+                self.user_token_account_y.to_account_info(),
+                &mut self.vault_y,
+                &self.mint_y,
+                self.token_program_y.to_account_info(),
+            )
+        };
+
+        helpers::transfer_tokens(
+            from,
+            vault,
+            mint,
+            self.user.to_account_info(),
+            token_program,
+            &[],
+            amount,
+        )
+    }
+
+    /// Returns the amount that arrived in the user's token account.
+    fn withdraw_token(&mut self, is_x: bool, amount: u64) -> Result<u64> {
+        let (vault, to, mint, token_program) = if is_x {
+            (
+                self.vault_x.to_account_info(),
+                &mut self.user_token_account_x,
+                &self.mint_x,
+                self.token_program_x.to_account_info(),
+            )
+        } else {
+            (
+                self.vault_y.to_account_info(),
+                &mut self.user_token_account_y,
+                &self.mint_y,
+                self.token_program_y.to_account_info(),
+            )
+        };
+
+        helpers::transfer_from_cl_pool(&self.cl_pool, vault, to, mint, token_program, amount)
+    }
+}
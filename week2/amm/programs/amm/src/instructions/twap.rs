@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+use crate::error::AmmError;
+use crate::state::{Config, Observation, Oracle, Twap};
+
+#[derive(Accounts)]
+pub struct GetTwap<'info> {
+    #[account(
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"oracle", config.key().as_ref()],
+        bump = oracle.bump,
+    )]
+    pub oracle: Box<Account<'info, Oracle>>,
+}
+
+impl<'info> GetTwap<'info> {
+    /// Average prices over the last `window` seconds, read-only.
+    pub fn get_twap(&self, window: i64) -> Result<Twap> {
+        require!(window > 0, AmmError::InvalidAmount);
+        self.config.check_no_flash_loan()?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let (price_x_cumulative, price_y_cumulative) = self.config.cumulative_prices(now);
+
+        let current = Observation {
+            timestamp: now,
+            price_x_cumulative,
+            price_y_cumulative,
+        };
+
+        let (price_x_start, price_y_start) = self
+            .oracle
+            .cumulative_prices_at(now - window, current)
+            .ok_or(AmmError::OracleWindowTooLong)?;
+
+        Ok(Twap {
+            price_x: price_x_cumulative.wrapping_sub(price_x_start) / window as u128,
+            price_y: price_y_cumulative.wrapping_sub(price_y_start) / window as u128,
+        })
+    }
+}
@@ -0,0 +1,133 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::{MAX_AMP, MAX_AMP_CHANGE, MIN_RAMP_DURATION};
+use crate::error::AmmError;
+use crate::state::{Config, PoolType};
+
+#[derive(Accounts)]
+pub struct Update<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> Update<'info> {
+    pub fn lock(&mut self) -> Result<()> {
+        self.config.check_authority(self.authority.key())?;
+        self.config.locked = true;
+
+        Ok(())
+    }
+
+    pub fn unlock(&mut self) -> Result<()> {
+        self.config.check_authority(self.authority.key())?;
+        self.config.locked = false;
+
+        Ok(())
+    }
+
+    /// Starts moving a StableSwap pool's amplification towards `target_amp`,
+    /// reaching it at `stop_ts`. Ramps are gradual so nobody can shift the
+    /// curve under the pool in one transaction.
+    pub fn ramp_amp(&mut self, target_amp: u64, stop_ts: i64) -> Result<()> {
+        self.config.check_authority(self.authority.key())?;
+        require!(
+            self.config.pool_type == PoolType::StableSwap,
+            AmmError::InvalidPoolType
+        );
+        require!((1..=MAX_AMP).contains(&target_amp), AmmError::InvalidAmp);
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            stop_ts >= now.saturating_add(MIN_RAMP_DURATION),
+            AmmError::RampTooShort
+        );
+
+        let current_amp = self.config.amp(now);
+        require!(
+            target_amp <= current_amp.saturating_mul(MAX_AMP_CHANGE)
+                && target_amp.saturating_mul(MAX_AMP_CHANGE) >= current_amp,
+            AmmError::InvalidAmp
+        );
+
+        self.config.initial_amp = current_amp;
+        self.config.target_amp = target_amp;
+        self.config.ramp_start_ts = now;
+        self.config.ramp_stop_ts = stop_ts;
+
+        Ok(())
+    }
+
+    /// Freezes the amplification wherever a running ramp has got to.
+    pub fn stop_ramp_amp(&mut self) -> Result<()> {
+        self.config.check_authority(self.authority.key())?;
+        require!(
+            self.config.pool_type == PoolType::StableSwap,
+            AmmError::InvalidPoolType
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let current_amp = self.config.amp(now);
+
+        self.config.initial_amp = current_amp;
+        self.config.target_amp = current_amp;
+        self.config.ramp_start_ts = now;
+        self.config.ramp_stop_ts = now;
+
+        Ok(())
+    }
+
+    pub fn update_fee(&mut self, fee: u16) -> Result<()> {
+        self.config.check_authority(self.authority.key())?;
+        require!(fee < 10_000, AmmError::InvalidFee);
+
+        self.config.fee = fee;
+
+        Ok(())
+    }
+
+    /// Switches the volatility-scaled swap fee on or off. While on, the
+    /// effective fee never leaves `min_fee..=max_fee`.
+    pub fn update_dynamic_fee(&mut self, enabled: bool, min_fee: u16, max_fee: u16) -> Result<()> {
+        self.config.check_authority(self.authority.key())?;
+        require!(min_fee <= max_fee && max_fee < 10_000, AmmError::InvalidFee);
+
+        self.config.dynamic_fee = enabled;
+        self.config.min_fee = min_fee;
+        self.config.max_fee = max_fee;
+
+        Ok(())
+    }
+
+    /// Sets the circuit breaker. Swaps may pay in at most `max_trade_bps` of
+    /// the input reserve, and may not take the price more than
+    /// `max_price_move_bps` from where it was at the start of each
+    /// `window`-slot window. Zero turns a limit off.
+    pub fn update_circuit_breaker(
+        &mut self,
+        max_trade_bps: u16,
+        max_price_move_bps: u16,
+        window: u64,
+    ) -> Result<()> {
+        self.config.check_authority(self.authority.key())?;
+        require!(max_trade_bps <= 10_000, AmmError::InvalidCircuitBreaker);
+        require!(
+            max_price_move_bps == 0 || window > 0,
+            AmmError::InvalidCircuitBreaker
+        );
+
+        self.config.max_trade_bps = max_trade_bps;
+        self.config.max_price_move_bps = max_price_move_bps;
+        self.config.breaker_window = window;
+        // The next swap opens a fresh window under the new limits
+        self.config.breaker_reserve_x = 0;
+        self.config.breaker_reserve_y = 0;
+
+        Ok(())
+    }
+}
@@ -1,26 +1,32 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{burn, transfer_checked, Burn, Mint, Token, TokenAccount, TransferChecked},
+    token_interface::{burn, Burn, Mint, TokenAccount, TokenInterface},
 };
-use constant_product_curve::ConstantProduct;
 
 use crate::error::AmmError;
+use crate::events::WithdrawEvent;
+use crate::helpers::{
+    self, check_expiration, fee_of, is_transient_native, lp_share, unwrap_sol, TransferFee,
+};
+use crate::instructions::WithdrawQuote;
 use crate::state::Config;
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct Withdraw<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
 
-    #[account(mint::token_program = token_program)]
-    pub mint_x: Account<'info, Mint>,
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
 
-    #[account(mint::token_program = token_program)]
-    pub mint_y: Account<'info, Mint>,
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
-        seeds = [b"config"],
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
         bump = config.config_bump,
         has_one = mint_x,
         has_one = mint_y,
@@ -32,39 +38,41 @@ pub struct Withdraw<'info> {
         seeds = [b"lp", config.key().as_ref()],
         bump = config.lp_bump
     )]
-    pub mint_lp: Account<'info, Mint>,
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         mut,
         associated_token::mint = mint_x,
         associated_token::authority = config,
-        associated_token::token_program = token_program,
+        associated_token::token_program = token_program_x,
     )]
-    pub vault_x: Account<'info, TokenAccount>,
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
         associated_token::mint = mint_y,
         associated_token::authority = config,
-        associated_token::token_program = token_program,
+        associated_token::token_program = token_program_y,
     )]
-    pub vault_y: Account<'info, TokenAccount>,
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
-        mut,
+        init_if_needed,
+        payer = user,
         associated_token::mint = mint_x,
         associated_token::authority = user,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_x
     )]
-    pub user_token_account_x: Account<'info, TokenAccount>,
+    pub user_token_account_x: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
-        mut,
+        init_if_needed,
+        payer = user,
         associated_token::mint = mint_y,
         associated_token::authority = user,
-        associated_token::token_program = token_program
+        associated_token::token_program = token_program_y
     )]
-    pub user_token_account_y: Account<'info, TokenAccount>,
+    pub user_token_account_y: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
@@ -72,39 +80,114 @@ pub struct Withdraw<'info> {
         associated_token::authority = user,
         associated_token::token_program = token_program
     )]
-    pub user_token_account_lp: Account<'info, TokenAccount>,
+    pub user_token_account_lp: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    pub token_program: Program<'info, Token>,
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
+/// What leaves the vaults for `amount` LP: that share of each reserve, rounded
+/// down. Shared by `withdraw`, `withdraw_single`, `quote_withdraw` and
+/// off-chain simulators.
+pub fn withdraw_amounts(
+    reserve_x: u64,
+    reserve_y: u64,
+    supply: u64,
+    amount: u64,
+    min_x: u64,
+    min_y: u64,
+) -> Result<(u64, u64)> {
+    if supply == 0 && reserve_x == 0 && reserve_y == 0 {
+        return Ok((min_x, min_y));
+    }
+
+    Ok((
+        lp_share(reserve_x, amount, supply, false)?,
+        lp_share(reserve_y, amount, supply, false)?,
+    ))
+}
+
+/// `withdraw_amounts` as they arrive with the user after transfer fees,
+/// checked against the minimums the way `withdraw` checks them.
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw_quote(
+    reserve_x: u64,
+    reserve_y: u64,
+    supply: u64,
+    amount: u64,
+    min_x: u64,
+    min_y: u64,
+    transfer_fee_x: Option<&TransferFee>,
+    transfer_fee_y: Option<&TransferFee>,
+) -> Result<WithdrawQuote> {
+    require!(amount != 0, AmmError::InvalidAmount);
+
+    let (x, y) = withdraw_amounts(reserve_x, reserve_y, supply, amount, min_x, min_y)?;
+    let x = x - fee_of(transfer_fee_x, x)?;
+    let y = y - fee_of(transfer_fee_y, y)?;
+
+    require!(x >= min_x && y >= min_y, AmmError::SlippageExceeded);
+
+    Ok(WithdrawQuote {
+        amount_x: x,
+        amount_y: y,
+        lp_amount: amount,
+    })
+}
+
 impl<'info> Withdraw<'info> {
-    pub fn withdraw(&mut self, amount: u64, min_x: u64, min_y: u64) -> Result<()> {
+    pub fn withdraw(
+        &mut self,
+        amount: u64,
+        min_x: u64,
+        min_y: u64,
+        expiration: i64,
+    ) -> Result<WithdrawEvent> {
+        check_expiration(expiration)?;
+        self.config.check_no_flash_loan()?;
         require!(amount != 0, AmmError::InvalidAmount);
 
-        let (x, y) = match self.mint_lp.supply == 0
-            && self.vault_x.amount == 0
-            && self.vault_y.amount == 0
-        {
-            true => (min_x, min_y),
-            false => {
-                let amounts = ConstantProduct::xy_withdraw_amounts_from_l(
-                    self.vault_x.amount,
-                    self.vault_y.amount,
-                    self.mint_lp.supply,
-                    amount,
-                    6,
-                ).unwrap();
-                (amounts.x, amounts.y)
-            }
-        };
+        self.config.update_oracle()?;
+        let transient = self.transient_native();
 
-        require!(x >= min_x && y >= min_y, AmmError::SlippageExceeded);
+        let (x, y) = withdraw_amounts(
+            self.vault_x.amount,
+            self.vault_y.amount,
+            self.mint_lp.supply,
+            amount,
+            min_x,
+            min_y,
+        )?;
 
         self.burn_lp_tokens(amount)?;
-        self.withdraw_tokens(x, true)?;
-        self.withdraw_tokens(y, false)
+        let x = self.withdraw_tokens(x, true)?;
+        let y = self.withdraw_tokens(y, false)?;
+
+        // Checked after the transfers so any transfer fee counts against the minimums
+        require!(x >= min_x && y >= min_y, AmmError::SlippageExceeded);
+
+        self.unwrap_native(transient)?;
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+        self.mint_lp.reload()?;
+        self.config
+            .sync_reserves(self.vault_x.amount, self.vault_y.amount);
+
+        Ok(WithdrawEvent {
+            config: self.config.key(),
+            user: self.user.key(),
+            amount_x: x,
+            amount_y: y,
+            lp_amount: amount,
+            fee: None,
+            reserve_x: self.vault_x.amount,
+            reserve_y: self.vault_y.amount,
+            lp_supply: Some(self.mint_lp.supply),
+            liquidity: None,
+        })
     }
 
     pub fn burn_lp_tokens(&mut self, amount: u64) -> Result<()> {
@@ -115,45 +198,58 @@ impl<'info> Withdraw<'info> {
             authority: self.user.to_account_info(),
         };
 
-        let signer_seeds: &[&[&[u8]]] = &[&[
-            b"config",
-            &[self.config.config_bump],
-        ]];
-
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
         burn(cpi_ctx, amount)
     }
 
-    pub fn withdraw_tokens(&mut self, amount: u64, is_x: bool) -> Result<()> {
-        let (from, to, mint, decimals) = match is_x {
+    /// Which of the user's token accounts, x then y, only carry native SOL
+    /// through this instruction. Read before anything moves.
+    fn transient_native(&self) -> [bool; 2] {
+        [
+            is_transient_native(&self.mint_x.key(), &self.user_token_account_x),
+            is_transient_native(&self.mint_y.key(), &self.user_token_account_y),
+        ]
+    }
+
+    /// Closes the `transient` wrapped SOL accounts so the wallet ends up
+    /// holding plain SOL.
+    fn unwrap_native(&mut self, [x, y]: [bool; 2]) -> Result<()> {
+        if x {
+            unwrap_sol(
+                self.user.to_account_info(),
+                self.user_token_account_x.to_account_info(),
+                self.token_program_x.to_account_info(),
+            )?;
+        }
+
+        if y {
+            unwrap_sol(
+                self.user.to_account_info(),
+                self.user_token_account_y.to_account_info(),
+                self.token_program_y.to_account_info(),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the amount that arrived in the user's token account.
+    pub fn withdraw_tokens(&mut self, amount: u64, is_x: bool) -> Result<u64> {
+        let (from, to, mint, token_program) = match is_x {
             true => (
                 self.vault_x.to_account_info(),
-                self.user_token_account_x.to_account_info(),
-                self.mint_x.to_account_info(),
-                self.mint_x.decimals,
+                &mut self.user_token_account_x,
+                &self.mint_x,
+                self.token_program_x.to_account_info(),
             ),
             false => (
                 self.vault_y.to_account_info(),
-                self.user_token_account_y.to_account_info(),
-                self.mint_y.to_account_info(),
-                self.mint_y.decimals,
+                &mut self.user_token_account_y,
+                &self.mint_y,
+                self.token_program_y.to_account_info(),
             ),
         };
 
-        let cpi_program = self.token_program.to_account_info();
-        let cpi_accounts = TransferChecked {
-            from,
-            to,
-            mint,
-            authority: self.config.to_account_info(),
-        };
-
-        let signer_seeds: &[&[&[u8]]] = &[&[
-            b"config",
-            &[self.config.config_bump],
-        ]];
-
-        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
-        transfer_checked(cpi_context, amount, decimals)
+        helpers::transfer_from_pool(&self.config, from, to, mint, token_program, amount)
     }
-}
\ No newline at end of file
+}
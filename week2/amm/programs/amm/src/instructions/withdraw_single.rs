@@ -0,0 +1,250 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{burn, Burn, Mint, TokenAccount, TokenInterface},
+};
+
+use crate::error::AmmError;
+use crate::events::WithdrawEvent;
+use crate::helpers::{self, check_expiration};
+use crate::instructions::withdraw_amounts;
+use crate::state::Config;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct WithdrawSingle<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(mint::token_program = token_program_x)]
+    pub mint_x: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mint::token_program = token_program_y)]
+    pub mint_y: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"config", config.seed.to_le_bytes().as_ref()],
+        bump = config.config_bump,
+        has_one = mint_x,
+        has_one = mint_y,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"lp", config.key().as_ref()],
+        bump = config.lp_bump
+    )]
+    pub mint_lp: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_x,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_x,
+    )]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_y,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_y,
+    )]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol", config.key().as_ref(), mint_x.key().as_ref()],
+        bump = config.protocol_x_bump,
+    )]
+    pub protocol_vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol", config.key().as_ref(), mint_y.key().as_ref()],
+        bump = config.protocol_y_bump,
+    )]
+    pub protocol_vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    // Only the side being withdrawn, so users don't need an account for the other mint
+    #[account(
+        mut,
+        token::authority = user,
+    )]
+    pub user_token_account_out: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint_lp,
+        associated_token::authority = user,
+        associated_token::token_program = token_program
+    )]
+    pub user_token_account_lp: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_x: Interface<'info, TokenInterface>,
+    pub token_program_y: Interface<'info, TokenInterface>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> WithdrawSingle<'info> {
+    pub fn withdraw_single(
+        &mut self,
+        is_x: bool,
+        lp_amount: u64,
+        min_out: u64,
+        expiration: i64,
+    ) -> Result<WithdrawEvent> {
+        check_expiration(expiration)?;
+        // Unlike a plain withdraw this swaps, so it stops with the rest of the pool
+        require!(!self.config.locked, AmmError::PoolLocked);
+        self.config.check_no_flash_loan()?;
+        require!(lp_amount != 0, AmmError::InvalidAmount);
+
+        self.config.update_oracle()?;
+
+        let mint_out = if is_x {
+            self.mint_x.key()
+        } else {
+            self.mint_y.key()
+        };
+        require_keys_eq!(
+            self.user_token_account_out.mint,
+            mint_out,
+            AmmError::InvalidMint
+        );
+
+        let (reserve_x, reserve_y) = (self.vault_x.amount, self.vault_y.amount);
+        let supply = self.mint_lp.supply;
+        let remaining_supply = supply
+            .checked_sub(lp_amount)
+            .ok_or(AmmError::InvalidAmount)?;
+        let (x, y) = withdraw_amounts(reserve_x, reserve_y, supply, lp_amount, 0, 0)?;
+
+        // The unwanted side never leaves the vault, it is sold back into the
+        // pool that remains after the withdrawal. A sale too small to buy
+        // anything is left there as dust, `min_out` still has the last word.
+        let (keep, sell) = if is_x { (x, y) } else { (y, x) };
+        let swap = if sell > 0 {
+            self.config.swap_amounts_unless_dust(
+                reserve_x - x,
+                reserve_y - y,
+                remaining_supply,
+                !is_x,
+                sell,
+            )?
+        } else {
+            None
+        };
+        let (sold, bought, fee) = match swap {
+            Some(swap) => (sell, swap.amount_out, swap.fee),
+            None => (0, 0, 0),
+        };
+
+        self.burn_lp_tokens(lp_amount)?;
+        self.transfer_protocol_fee(!is_x, sold, fee)?;
+        let amount_out = self.withdraw_token(is_x, keep + bought)?;
+
+        require!(amount_out >= min_out, AmmError::SlippageExceeded);
+
+        self.vault_x.reload()?;
+        self.vault_y.reload()?;
+        self.mint_lp.reload()?;
+
+        // Only the internal swap moves the price, the rest is taken at the old ratio
+        let after = (self.vault_x.amount, self.vault_y.amount);
+        self.config
+            .check_price_move((reserve_x, reserve_y), after)?;
+        self.config
+            .record_price_move((reserve_x, reserve_y), after)?;
+        self.config.sync_reserves(after.0, after.1);
+
+        let (amount_x, amount_y) = if is_x {
+            (amount_out, 0)
+        } else {
+            (0, amount_out)
+        };
+        Ok(WithdrawEvent {
+            config: self.config.key(),
+            user: self.user.key(),
+            amount_x,
+            amount_y,
+            lp_amount,
+            fee: Some((sold as u128 * fee as u128 / 10_000) as u64),
+            reserve_x: self.vault_x.amount,
+            reserve_y: self.vault_y.amount,
+            lp_supply: Some(self.mint_lp.supply),
+            liquidity: None,
+        })
+    }
+
+    fn burn_lp_tokens(&mut self, amount: u64) -> Result<()> {
+        let cpi_program = self.token_program.to_account_info();
+        let cpi_accounts = Burn {
+            mint: self.mint_lp.to_account_info(),
+            from: self.user_token_account_lp.to_account_info(),
+            authority: self.user.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        burn(cpi_ctx, amount)
+    }
+
+    /// Moves the protocol's cut of the internal swap's fee out of the vault.
+    fn transfer_protocol_fee(&mut self, is_x: bool, amount_in: u64, fee: u16) -> Result<u64> {
+        let (vault, protocol_vault, mint, token_program) = if is_x {
+            (
+                self.vault_x.to_account_info(),
+                &mut self.protocol_vault_x,
+                &self.mint_x,
+                self.token_program_x.to_account_info(),
+            )
+        } else {
+            (
+                self.vault_y.to_account_info(),
+                &mut self.protocol_vault_y,
+                &self.mint_y,
+                self.token_program_y.to_account_info(),
+            )
+        };
+
+        helpers::transfer_protocol_fee(
+            &self.config,
+            vault,
+            protocol_vault,
+            mint,
+            token_program,
+            amount_in,
+            fee,
+        )
+    }
+
+    /// Returns the amount that arrived in the user's token account.
+    fn withdraw_token(&mut self, is_x: bool, amount: u64) -> Result<u64> {
+        let (from, mint, token_program) = if is_x {
+            (
+                self.vault_x.to_account_info(),
+                &self.mint_x,
+                self.token_program_x.to_account_info(),
+            )
+        } else {
+            (
+                self.vault_y.to_account_info(),
+                &self.mint_y,
+                self.token_program_y.to_account_info(),
+            )
+        };
+
+        helpers::transfer_from_pool(
+            &self.config,
+            from,
+            &mut self.user_token_account_out,
+            mint,
+            token_program,
+            amount,
+        )
+    }
+}
@@ -1,13 +1,18 @@
 #![allow(deprecated)]
 #![allow(unexpected_cfgs)]
 pub(crate) mod constants;
+pub mod curve;
 pub mod error;
+pub mod events;
+pub(crate) mod helpers;
 pub mod instructions;
 pub mod state;
 
 use anchor_lang::prelude::*;
 
 pub use constants::*;
+pub use events::*;
+pub use helpers::{fee_of, fee_on_top, TransferFee};
 pub use instructions::*;
 pub use state::*;
 
@@ -16,39 +21,357 @@ declare_id!("6PBEiekfAzLKRFcCxXPygyKRsYfjdZuoHVNEM8hNBwxR");
 #[program]
 pub mod anchor_amm {
     use super::*;
-  
-      pub fn initialize(
-          ctx: Context<Initialize>,
-          fee: u16,
-          authority: Option<Pubkey>,
-      ) -> Result<()> {
-          ctx.accounts.initialize(fee, authority, &ctx.bumps)
-      }
-  
-      pub fn deposit(
-          ctx: Context<Deposit>,
-          amount: u64,
-          max_x: u64,
-          max_y: u64
-      ) -> Result<()> {
-          ctx.accounts.deposit(amount, max_x, max_y)
-      }
-  
-      pub fn withdraw(
-          ctx: Context<Withdraw>,
-          amount: u64,
-          min_x: u64,
-          min_y: u64
-      ) -> Result<()> {
-          ctx.accounts.withdraw(amount, min_x, min_y)
-      }
-  
-      pub fn swap(
-          ctx: Context<Swap>,
-          is_x: bool,
-          amount_in: u64,
-          min_amount_out: u64
-      ) -> Result<()> {
-          ctx.accounts.swap(is_x, amount_in, min_amount_out)
-      }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize(
+        ctx: Context<Initialize>,
+        seed: u64,
+        fee: u16,
+        protocol_fee: u16,
+        authority: Option<Pubkey>,
+        pool_type: PoolType,
+        amp: u64,
+        weight_x: u16,
+    ) -> Result<()> {
+        let event = ctx.accounts.initialize(
+            seed,
+            fee,
+            protocol_fee,
+            authority,
+            pool_type,
+            amp,
+            weight_x,
+            &ctx.bumps,
+        )?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn deposit(
+        ctx: Context<Deposit>,
+        amount: u64,
+        max_x: u64,
+        max_y: u64,
+        expiration: i64,
+    ) -> Result<()> {
+        let event = ctx.accounts.deposit(amount, max_x, max_y, expiration)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn deposit_single(
+        ctx: Context<DepositSingle>,
+        is_x: bool,
+        amount_in: u64,
+        min_lp_out: u64,
+        expiration: i64,
+    ) -> Result<()> {
+        let event = ctx
+            .accounts
+            .deposit_single(is_x, amount_in, min_lp_out, expiration)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn withdraw(
+        ctx: Context<Withdraw>,
+        amount: u64,
+        min_x: u64,
+        min_y: u64,
+        expiration: i64,
+    ) -> Result<()> {
+        let event = ctx.accounts.withdraw(amount, min_x, min_y, expiration)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn withdraw_single(
+        ctx: Context<WithdrawSingle>,
+        is_x: bool,
+        lp_amount: u64,
+        min_out: u64,
+        expiration: i64,
+    ) -> Result<()> {
+        let event = ctx
+            .accounts
+            .withdraw_single(is_x, lp_amount, min_out, expiration)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn swap(
+        ctx: Context<Swap>,
+        is_x: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+        expiration: i64,
+    ) -> Result<()> {
+        let event = ctx
+            .accounts
+            .swap(is_x, amount_in, min_amount_out, expiration)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn swap_exact_out(
+        ctx: Context<Swap>,
+        is_x: bool,
+        amount_out: u64,
+        max_amount_in: u64,
+        expiration: i64,
+    ) -> Result<()> {
+        let event = ctx
+            .accounts
+            .swap_exact_out(is_x, amount_out, max_amount_in, expiration)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn swap_route<'info>(
+        ctx: Context<'_, '_, 'info, 'info, Swap<'info>>,
+        is_x: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+        expiration: i64,
+    ) -> Result<()> {
+        let events = ctx.accounts.swap_route(
+            ctx.remaining_accounts,
+            is_x,
+            amount_in,
+            min_amount_out,
+            expiration,
+        )?;
+        // Every hop is emitted through this pool's event authority
+        for event in events {
+            emit_cpi!(event);
+        }
+        Ok(())
+    }
+
+    pub fn lock(ctx: Context<Update>) -> Result<()> {
+        ctx.accounts.lock()
+    }
+
+    pub fn unlock(ctx: Context<Update>) -> Result<()> {
+        ctx.accounts.unlock()
+    }
+
+    pub fn ramp_amp(ctx: Context<Update>, target_amp: u64, stop_ts: i64) -> Result<()> {
+        ctx.accounts.ramp_amp(target_amp, stop_ts)
+    }
+
+    pub fn stop_ramp_amp(ctx: Context<Update>) -> Result<()> {
+        ctx.accounts.stop_ramp_amp()
+    }
+
+    pub fn update_fee(ctx: Context<Update>, fee: u16) -> Result<()> {
+        ctx.accounts.update_fee(fee)
+    }
+
+    pub fn update_dynamic_fee(
+        ctx: Context<Update>,
+        enabled: bool,
+        min_fee: u16,
+        max_fee: u16,
+    ) -> Result<()> {
+        ctx.accounts.update_dynamic_fee(enabled, min_fee, max_fee)
+    }
+
+    pub fn update_circuit_breaker(
+        ctx: Context<Update>,
+        max_trade_bps: u16,
+        max_price_move_bps: u16,
+        window: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .update_circuit_breaker(max_trade_bps, max_price_move_bps, window)
+    }
+
+    pub fn collect_protocol_fees(ctx: Context<CollectProtocolFees>) -> Result<()> {
+        let event = ctx.accounts.collect_protocol_fees()?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn observe(ctx: Context<Observe>) -> Result<()> {
+        ctx.accounts.observe(&ctx.bumps)
+    }
+
+    pub fn get_twap(ctx: Context<GetTwap>, window: i64) -> Result<Twap> {
+        ctx.accounts.get_twap(window)
+    }
+
+    pub fn initialize_cl_pool(
+        ctx: Context<InitializeClPool>,
+        tick_spacing: u16,
+        sqrt_price: u128,
+    ) -> Result<()> {
+        ctx.accounts
+            .initialize_cl_pool(tick_spacing, sqrt_price, &ctx.bumps)
+    }
+
+    pub fn initialize_tick_array(
+        ctx: Context<InitializeTickArray>,
+        start_tick_index: i32,
+    ) -> Result<()> {
+        ctx.accounts
+            .initialize_tick_array(start_tick_index, &ctx.bumps)
+    }
+
+    pub fn open_position(
+        ctx: Context<OpenPosition>,
+        tick_lower: i32,
+        tick_upper: i32,
+    ) -> Result<()> {
+        ctx.accounts
+            .open_position(tick_lower, tick_upper, &ctx.bumps)
+    }
+
+    pub fn increase_liquidity(
+        ctx: Context<ModifyLiquidity>,
+        liquidity: u128,
+        max_x: u64,
+        max_y: u64,
+        expiration: i64,
+    ) -> Result<()> {
+        let event = ctx
+            .accounts
+            .increase_liquidity(liquidity, max_x, max_y, expiration)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn decrease_liquidity(
+        ctx: Context<ModifyLiquidity>,
+        liquidity: u128,
+        min_x: u64,
+        min_y: u64,
+        expiration: i64,
+    ) -> Result<()> {
+        let event = ctx
+            .accounts
+            .decrease_liquidity(liquidity, min_x, min_y, expiration)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn collect_position_fees(ctx: Context<CollectPositionFees>) -> Result<()> {
+        let event = ctx.accounts.collect_position_fees()?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn close_position(ctx: Context<ClosePosition>) -> Result<()> {
+        ctx.accounts.close_position()
+    }
+
+    pub fn swap_cl(
+        ctx: Context<SwapCl>,
+        is_x: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+        expiration: i64,
+    ) -> Result<()> {
+        let event = ctx.accounts.swap_cl(
+            ctx.remaining_accounts,
+            is_x,
+            amount_in,
+            min_amount_out,
+            expiration,
+        )?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn flash_borrow(ctx: Context<FlashLoan>, is_x: bool, amount: u64) -> Result<()> {
+        let event = ctx.accounts.flash_borrow(is_x, amount)?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn flash_repay(ctx: Context<FlashLoan>) -> Result<()> {
+        let event = ctx.accounts.flash_repay()?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn quote_swap(
+        ctx: Context<Quote>,
+        is_x: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+        expiration: i64,
+    ) -> Result<SwapQuote> {
+        ctx.accounts
+            .quote_swap(is_x, amount_in, min_amount_out, expiration)
+    }
+
+    pub fn quote_deposit(
+        ctx: Context<Quote>,
+        amount: u64,
+        max_x: u64,
+        max_y: u64,
+        expiration: i64,
+    ) -> Result<DepositQuote> {
+        ctx.accounts.quote_deposit(amount, max_x, max_y, expiration)
+    }
+
+    pub fn quote_withdraw(
+        ctx: Context<Quote>,
+        amount: u64,
+        min_x: u64,
+        min_y: u64,
+        expiration: i64,
+    ) -> Result<WithdrawQuote> {
+        ctx.accounts
+            .quote_withdraw(amount, min_x, min_y, expiration)
+    }
+
+    pub fn create_farm(ctx: Context<CreateFarm>, reward_per_second: u64) -> Result<()> {
+        ctx.accounts.create_farm(reward_per_second, &ctx.bumps)
+    }
+
+    pub fn fund_farm(ctx: Context<FundFarm>, amount: u64) -> Result<()> {
+        ctx.accounts.fund_farm(amount)
+    }
+
+    pub fn stake_lp(ctx: Context<StakeLp>, amount: u64) -> Result<()> {
+        ctx.accounts.stake_lp(amount, &ctx.bumps)
+    }
+
+    pub fn unstake_lp(ctx: Context<StakeLp>, amount: u64) -> Result<()> {
+        ctx.accounts.unstake_lp(amount)
+    }
+
+    pub fn claim(ctx: Context<Claim>) -> Result<()> {
+        ctx.accounts.claim()
+    }
+
+    pub fn place_limit_order(
+        ctx: Context<PlaceLimitOrder>,
+        seed: u64,
+        amount_in: u64,
+        limit_amount_out: u64,
+        tip: u64,
+        expiration: i64,
+    ) -> Result<()> {
+        ctx.accounts.place_limit_order(
+            seed,
+            amount_in,
+            limit_amount_out,
+            tip,
+            expiration,
+            &ctx.bumps,
+        )
+    }
+
+    pub fn fill_limit_order(ctx: Context<FillLimitOrder>) -> Result<()> {
+        let event = ctx.accounts.fill_limit_order()?;
+        emit_cpi!(event);
+        Ok(())
+    }
+
+    pub fn cancel_limit_order(ctx: Context<CancelLimitOrder>) -> Result<()> {
+        ctx.accounts.cancel_limit_order()
+    }
 }
@@ -0,0 +1,137 @@
+use anchor_lang::prelude::*;
+
+use crate::curve::U256;
+use crate::error::AmmError;
+use crate::state::{Config, Tick};
+
+/// Concentrated-liquidity pool for a `Config`'s pair. Shares the mints, fee,
+/// protocol fee, circuit breaker limits and lock with the full-range pool but
+/// keeps its own vaults, liquidity and breaker window.
+#[account]
+pub struct ClPool {
+    pub config: Pubkey,
+    pub tick_spacing: u16,
+    pub sqrt_price: u128,
+    pub tick_current: i32,
+    pub liquidity: u128,
+    pub fee_growth_global_x: u128,
+    pub fee_growth_global_y: u128,
+    pub breaker_slot: u64,
+    pub breaker_sqrt_price: u128,
+    pub bump: u8,
+}
+
+impl ClPool {
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        32 + // config
+        2 + // tick_spacing (u16)
+        16 + // sqrt_price (u128, Q64.64)
+        4 + // tick_current (i32)
+        16 + // liquidity (u128)
+        16 + // fee_growth_global_x (u128, Q64.64)
+        16 + // fee_growth_global_y (u128, Q64.64)
+        8 + // breaker_slot, when the current breaker window opened
+        16 + // breaker_sqrt_price (u128, Q64.64), price when it opened, 0 = none
+        1; // bump
+
+    /// Seeds the pool signs with as authority of its vaults and position mints
+    pub fn signer_seeds(&self) -> [&[u8]; 3] {
+        [
+            b"cl_pool",
+            self.config.as_ref(),
+            std::slice::from_ref(&self.bump),
+        ]
+    }
+
+    /// Fees earned per unit of liquidity between two ticks since they were
+    /// initialized, Uniswap v3 style. Wrapping on purpose, only differences
+    /// between two readings mean anything.
+    pub fn fee_growth_inside(
+        &self,
+        tick_lower: i32,
+        lower: &Tick,
+        tick_upper: i32,
+        upper: &Tick,
+    ) -> (u128, u128) {
+        let (below_x, below_y) = if self.tick_current >= tick_lower {
+            (lower.fee_growth_outside_x, lower.fee_growth_outside_y)
+        } else {
+            (
+                self.fee_growth_global_x
+                    .wrapping_sub(lower.fee_growth_outside_x),
+                self.fee_growth_global_y
+                    .wrapping_sub(lower.fee_growth_outside_y),
+            )
+        };
+
+        let (above_x, above_y) = if self.tick_current < tick_upper {
+            (upper.fee_growth_outside_x, upper.fee_growth_outside_y)
+        } else {
+            (
+                self.fee_growth_global_x
+                    .wrapping_sub(upper.fee_growth_outside_x),
+                self.fee_growth_global_y
+                    .wrapping_sub(upper.fee_growth_outside_y),
+            )
+        };
+
+        (
+            self.fee_growth_global_x
+                .wrapping_sub(below_x)
+                .wrapping_sub(above_x),
+            self.fee_growth_global_y
+                .wrapping_sub(below_y)
+                .wrapping_sub(above_y),
+        )
+    }
+
+    /// `Config::check_price_move` for this pool: fails if a swap leaves the
+    /// price more than `max_price_move_bps` away from where it stood when the
+    /// current breaker window opened. Call it once `sqrt_price` has moved.
+    pub fn check_price_move_at(
+        &mut self,
+        config: &Config,
+        sqrt_price_before: u128,
+        slot: u64,
+    ) -> Result<()> {
+        if config.max_price_move_bps == 0 {
+            return Ok(());
+        }
+
+        let has_window = self.breaker_sqrt_price != 0;
+        if !has_window || slot >= self.breaker_slot.saturating_add(config.breaker_window) {
+            self.breaker_slot = slot;
+            self.breaker_sqrt_price = sqrt_price_before;
+        }
+
+        let price_move = sqrt_price_move_bps(self.breaker_sqrt_price, self.sqrt_price);
+        require!(
+            price_move <= config.max_price_move_bps as u64,
+            AmmError::PriceMoveTooLarge
+        );
+
+        Ok(())
+    }
+}
+
+/// How far the price moved between two Q64.64 square-root prices, in basis
+/// points of the price before.
+pub fn sqrt_price_move_bps(sqrt_price_before: u128, sqrt_price_after: u128) -> u64 {
+    if sqrt_price_before == 0 {
+        return 0;
+    }
+
+    // |a² - b²| = |a - b|·(a + b)
+    let (before, after) = (U256::from(sqrt_price_before), U256::from(sqrt_price_after));
+    let diff = if after > before {
+        after - before
+    } else {
+        before - after
+    };
+    let bps = diff * (before + after) * U256::from(10_000u64) / (before * before);
+    if bps > U256::from(u64::MAX) {
+        u64::MAX
+    } else {
+        bps.as_u64()
+    }
+}
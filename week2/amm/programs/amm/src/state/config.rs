@@ -1,21 +1,492 @@
 use anchor_lang::prelude::*;
 
+use crate::constants::{VOLATILITY_FEE_DIVISOR, VOLATILITY_HALF_LIFE};
+use crate::curve::{stable_swap, Curve, U256};
+use crate::error::AmmError;
+
+/// Which invariant the pool prices swaps against, fixed at initialize
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PoolType {
+    ConstantProduct,
+    StableSwap,
+    Weighted,
+}
+
+/// The vault side of an exact-input swap, see `Config::swap_amounts`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapAmounts {
+    /// Leaving the output vault
+    pub amount_out: u64,
+    /// Fee rate charged, in basis points
+    pub fee: u16,
+    /// The protocol's cut of the fee, leaving the input vault
+    pub protocol_fee: u64,
+}
+
+/// The config PDA's signer seeds, owned so they can outlive the `Config`
+/// borrow they were read from. See `Config::signer_seeds`.
+pub struct ConfigSeeds {
+    seed: [u8; 8],
+    bump: [u8; 1],
+}
+
+impl ConfigSeeds {
+    pub fn as_seeds(&self) -> [&[u8]; 3] {
+        [b"config", &self.seed, &self.bump]
+    }
+}
+
 #[account]
 pub struct Config {
+    pub seed: u64,
     pub authority: Option<Pubkey>,
     pub mint_x: Pubkey,
     pub mint_y: Pubkey,
     pub fee: u16,
+    pub protocol_fee: u16,
+    pub locked: bool,
     pub config_bump: u8,
     pub lp_bump: u8,
+    pub lp_lock_bump: u8,
+    pub protocol_x_bump: u8,
+    pub protocol_y_bump: u8,
+    pub price_x_cumulative: u128,
+    pub price_y_cumulative: u128,
+    pub last_update_ts: i64,
+    pub last_reserve_x: u64,
+    pub last_reserve_y: u64,
+    pub pool_type: PoolType,
+    pub initial_amp: u64,
+    pub target_amp: u64,
+    pub ramp_start_ts: i64,
+    pub ramp_stop_ts: i64,
+    pub flash_loan_amount: u64,
+    pub flash_loan_is_x: bool,
+    pub dynamic_fee: bool,
+    pub min_fee: u16,
+    pub max_fee: u16,
+    pub volatility: u64,
+    pub volatility_ts: i64,
+    pub max_trade_bps: u16,
+    pub max_price_move_bps: u16,
+    pub breaker_window: u64,
+    pub breaker_slot: u64,
+    pub breaker_reserve_x: u64,
+    pub breaker_reserve_y: u64,
+    pub weight_x: u16,
+    pub weight_y: u16,
 }
 
 impl Config {
     pub const INIT_SPACE: usize = 8 + // discriminator
+        8 + // seed
         (1 + 32) + // Option<Pubkey> for authority (1 byte for Some/None + 32 bytes for Pubkey)
         32 + // mint_x
         32 + // mint_y
         2 + // fee (u16)
+        2 + // protocol_fee (u16)
+        1 + // locked
         1 + // config_bump
-        1; // lp_bump
+        1 + // lp_bump
+        1 + // lp_lock_bump
+        1 + // protocol_x_bump
+        1 + // protocol_y_bump
+        16 + // price_x_cumulative (u128)
+        16 + // price_y_cumulative (u128)
+        8 + // last_update_ts (i64)
+        8 + // last_reserve_x
+        8 + // last_reserve_y
+        1 + // pool_type (enum tag)
+        8 + // initial_amp
+        8 + // target_amp
+        8 + // ramp_start_ts (i64)
+        8 + // ramp_stop_ts (i64)
+        8 + // flash_loan_amount
+        1 + // flash_loan_is_x
+        1 + // dynamic_fee
+        2 + // min_fee (u16)
+        2 + // max_fee (u16)
+        8 + // volatility (bps of price movement)
+        8 + // volatility_ts (i64)
+        2 + // max_trade_bps (u16, 0 = no limit)
+        2 + // max_price_move_bps (u16, 0 = no limit)
+        8 + // breaker_window (slots)
+        8 + // breaker_slot
+        8 + // breaker_reserve_x
+        8 + // breaker_reserve_y
+        2 + // weight_x (bps)
+        2; // weight_y (bps)
+
+    /// Seeds the pool signs with as authority of its vaults and LP mint
+    pub fn signer_seeds(&self) -> ConfigSeeds {
+        ConfigSeeds {
+            seed: self.seed.to_le_bytes(),
+            bump: [self.config_bump],
+        }
+    }
+
+    // A pool initialized without an authority is immutable
+    pub fn check_authority(&self, signer: Pubkey) -> Result<()> {
+        match self.authority {
+            Some(authority) => {
+                require_keys_eq!(authority, signer, AmmError::InvalidAuthority);
+                Ok(())
+            }
+            None => err!(AmmError::NoAuthority),
+        }
+    }
+
+    /// Vault balances are short by the loan until it's repaid, so nothing that
+    /// prices off them may run in between.
+    pub fn check_no_flash_loan(&self) -> Result<()> {
+        require!(self.flash_loan_amount == 0, AmmError::FlashLoanActive);
+        Ok(())
+    }
+
+    /// Fee charged on swaps right now. In dynamic mode recent volatility is
+    /// added on top of `fee`, and the result kept within `min_fee..=max_fee`.
+    pub fn swap_fee(&self) -> Result<u16> {
+        if !self.dynamic_fee {
+            return Ok(self.fee);
+        }
+
+        Ok(self.swap_fee_at(Clock::get()?.unix_timestamp))
+    }
+
+    /// `swap_fee` as of `now`, for callers without the clock sysvar.
+    pub fn swap_fee_at(&self, now: i64) -> u16 {
+        if !self.dynamic_fee {
+            return self.fee;
+        }
+
+        let surcharge = self.volatility_at(now) / VOLATILITY_FEE_DIVISOR;
+        let fee = (self.fee as u64).saturating_add(surcharge);
+
+        fee.clamp(self.min_fee as u64, self.max_fee as u64) as u16
+    }
+
+    /// The volatility accumulator decayed to `now`, halving every
+    /// `VOLATILITY_HALF_LIFE` seconds without swaps.
+    pub fn volatility_at(&self, now: i64) -> u64 {
+        let halvings = now.saturating_sub(self.volatility_ts).max(0) / VOLATILITY_HALF_LIFE;
+        if halvings >= 64 {
+            0
+        } else {
+            self.volatility >> halvings
+        }
+    }
+
+    /// Adds how far a swap moved the spot price, in basis points, to the
+    /// volatility accumulator.
+    pub fn record_price_move(&mut self, before: (u64, u64), after: (u64, u64)) -> Result<()> {
+        self.record_price_move_at(before, after, Clock::get()?.unix_timestamp);
+        Ok(())
+    }
+
+    /// `record_price_move` as of `now`, for callers without the clock sysvar.
+    pub fn record_price_move_at(&mut self, before: (u64, u64), after: (u64, u64), now: i64) {
+        let price_move = self.price_move_bps_at(before, after, now);
+        self.add_volatility_at(price_move, now);
+    }
+
+    /// Adds a price move measured elsewhere, in basis points, to the
+    /// volatility accumulator as of `now`. The concentrated pool feeds its
+    /// moves in through here, it prices on its own curve.
+    pub fn add_volatility_at(&mut self, price_move: u64, now: i64) {
+        self.volatility = self.volatility_at(now).saturating_add(price_move);
+        self.volatility_ts = now;
+    }
+
+    /// Fails if `amount_in` is more than `max_trade_bps` of the reserve it is
+    /// paid into.
+    pub fn check_trade_size(&self, amount_in: u64, reserve_in: u64) -> Result<()> {
+        if self.max_trade_bps == 0 {
+            return Ok(());
+        }
+
+        require!(
+            amount_in as u128 * 10_000 <= reserve_in as u128 * self.max_trade_bps as u128,
+            AmmError::TradeTooLarge
+        );
+
+        Ok(())
+    }
+
+    /// Fails if a swap from `before` to `after` leaves the price more than
+    /// `max_price_move_bps` away from where it stood when the current breaker
+    /// window opened, so splitting a trade within the window doesn't help.
+    pub fn check_price_move(&mut self, before: (u64, u64), after: (u64, u64)) -> Result<()> {
+        let clock = Clock::get()?;
+        self.check_price_move_at(before, after, clock.slot, clock.unix_timestamp)
+    }
+
+    /// `check_price_move` as of `slot` and `now`, for callers without the
+    /// clock sysvar.
+    pub fn check_price_move_at(
+        &mut self,
+        before: (u64, u64),
+        after: (u64, u64),
+        slot: u64,
+        now: i64,
+    ) -> Result<()> {
+        if self.max_price_move_bps == 0 {
+            return Ok(());
+        }
+
+        // The first swap after the window elapses, or since the limits were
+        // set, opens the next one
+        let has_window = self.breaker_reserve_x != 0 && self.breaker_reserve_y != 0;
+        if !has_window || slot >= self.breaker_slot.saturating_add(self.breaker_window) {
+            self.breaker_slot = slot;
+            (self.breaker_reserve_x, self.breaker_reserve_y) = before;
+        }
+
+        let window_start = (self.breaker_reserve_x, self.breaker_reserve_y);
+        let price_move = self.price_move_bps_at(window_start, after, now);
+        require!(
+            price_move <= self.max_price_move_bps as u64,
+            AmmError::PriceMoveTooLarge
+        );
+
+        Ok(())
+    }
+
+    /// Prices an exact-input swap of `received`, the amount that reached the
+    /// input vault, against the reserves it found there. Checks the trade size
+    /// and rejects swaps that would pay nothing out. Every exact-input swap,
+    /// quote and off-chain simulation goes through here.
+    pub fn swap_amounts(
+        &self,
+        reserve_x: u64,
+        reserve_y: u64,
+        supply: u64,
+        is_x: bool,
+        received: u64,
+    ) -> Result<SwapAmounts> {
+        let now = Clock::get()?.unix_timestamp;
+        self.swap_amounts_at(reserve_x, reserve_y, supply, is_x, received, now)
+    }
+
+    /// `swap_amounts` as of `now`, for callers without the clock sysvar.
+    pub fn swap_amounts_at(
+        &self,
+        reserve_x: u64,
+        reserve_y: u64,
+        supply: u64,
+        is_x: bool,
+        received: u64,
+        now: i64,
+    ) -> Result<SwapAmounts> {
+        self.check_trade_size(received, if is_x { reserve_x } else { reserve_y })?;
+        let swap = self.price_swap_at(reserve_x, reserve_y, supply, is_x, received, now)?;
+        require!(swap.amount_out != 0, AmmError::InvalidAmount);
+
+        Ok(swap)
+    }
+
+    /// `swap_amounts` for a sale the caller can skip: `None` when `received`
+    /// is too small to pay anything out, instead of failing.
+    pub fn swap_amounts_unless_dust(
+        &self,
+        reserve_x: u64,
+        reserve_y: u64,
+        supply: u64,
+        is_x: bool,
+        received: u64,
+    ) -> Result<Option<SwapAmounts>> {
+        let now = Clock::get()?.unix_timestamp;
+        self.check_trade_size(received, if is_x { reserve_x } else { reserve_y })?;
+        let swap = self.price_swap_at(reserve_x, reserve_y, supply, is_x, received, now)?;
+
+        Ok((swap.amount_out != 0).then_some(swap))
+    }
+
+    /// `swap_amounts_at` without the trade size check or rejecting a zero
+    /// output, for searches over candidate amounts. Whatever amount they
+    /// settle on still has to go through `swap_amounts_at`.
+    pub fn price_swap_at(
+        &self,
+        reserve_x: u64,
+        reserve_y: u64,
+        supply: u64,
+        is_x: bool,
+        received: u64,
+        now: i64,
+    ) -> Result<SwapAmounts> {
+        require!(received != 0, AmmError::InvalidAmount);
+
+        let fee = self.swap_fee_at(now);
+        let amount_out = self
+            .curve_at(now)
+            .swap_amount_out(reserve_x, reserve_y, supply, fee, is_x, received)?;
+
+        Ok(SwapAmounts {
+            amount_out,
+            fee,
+            protocol_fee: self.protocol_fee_amount(received, fee)?,
+        })
+    }
+
+    /// The protocol's cut of the fee on `amount_in`.
+    pub fn protocol_fee_amount(&self, amount_in: u64, fee: u16) -> Result<u64> {
+        let fee = (amount_in as u128)
+            .checked_mul(fee as u128)
+            .ok_or(AmmError::Overflow)?
+            / 10_000;
+
+        Ok((fee * self.protocol_fee as u128 / 10_000) as u64)
+    }
+
+    /// Amplification in effect at `now`, moving linearly from `initial_amp` to
+    /// `target_amp` while a ramp is running.
+    pub fn amp(&self, now: i64) -> u64 {
+        if now >= self.ramp_stop_ts || self.ramp_stop_ts <= self.ramp_start_ts {
+            return self.target_amp;
+        }
+
+        let elapsed = now.saturating_sub(self.ramp_start_ts).max(0) as u128;
+        let duration = (self.ramp_stop_ts - self.ramp_start_ts) as u128;
+
+        if self.target_amp > self.initial_amp {
+            let delta = (self.target_amp - self.initial_amp) as u128 * elapsed / duration;
+            self.initial_amp + delta as u64
+        } else {
+            let delta = (self.initial_amp - self.target_amp) as u128 * elapsed / duration;
+            self.initial_amp - delta as u64
+        }
+    }
+
+    /// The curve swaps are priced on right now.
+    pub fn curve(&self) -> Result<Curve> {
+        Ok(match self.pool_type {
+            PoolType::StableSwap => self.curve_at(Clock::get()?.unix_timestamp),
+            _ => self.curve_at(0),
+        })
+    }
+
+    /// `curve` as of `now`, for callers without the clock sysvar.
+    pub fn curve_at(&self, now: i64) -> Curve {
+        match self.pool_type {
+            PoolType::ConstantProduct => Curve::ConstantProduct,
+            PoolType::StableSwap => Curve::StableSwap { amp: self.amp(now) },
+            PoolType::Weighted => Curve::Weighted {
+                weight_x: self.weight_x,
+                weight_y: self.weight_y,
+            },
+        }
+    }
+
+    /// Adds the spot price times the time elapsed since the last update to the
+    /// cumulative prices, Uniswap v2 style. Prices off the reserves the last
+    /// operation left behind rather than the vaults, so tokens sent straight to
+    /// a vault can't reprice the interval before them. Has to run before the
+    /// reserves change.
+    pub fn update_oracle(&mut self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let (price_x_cumulative, price_y_cumulative) = self.cumulative_prices(now);
+
+        self.price_x_cumulative = price_x_cumulative;
+        self.price_y_cumulative = price_y_cumulative;
+        self.last_update_ts = now;
+
+        Ok(())
+    }
+
+    /// Records the reserves an operation left in the vaults, the price the
+    /// oracle accrues until the next one. Has to run after the vaults change.
+    pub fn sync_reserves(&mut self, reserve_x: u64, reserve_y: u64) {
+        self.last_reserve_x = reserve_x;
+        self.last_reserve_y = reserve_y;
+    }
+
+    /// Cumulative prices as of `now`, without writing them back. Accumulators
+    /// wrap on overflow, consumers only ever look at differences.
+    pub fn cumulative_prices(&self, now: i64) -> (u128, u128) {
+        let elapsed = now.saturating_sub(self.last_update_ts);
+        let (reserve_x, reserve_y) = (self.last_reserve_x, self.last_reserve_y);
+        if elapsed <= 0 || reserve_x == 0 || reserve_y == 0 {
+            return (self.price_x_cumulative, self.price_y_cumulative);
+        }
+
+        let (price_x, price_y) = self.spot_prices_at(reserve_x, reserve_y, now);
+
+        (
+            self.price_x_cumulative
+                .wrapping_add(price_x.wrapping_mul(elapsed as u128)),
+            self.price_y_cumulative
+                .wrapping_add(price_y.wrapping_mul(elapsed as u128)),
+        )
+    }
+
+    /// How far the spot price of x on the pool's curve moved between two sets
+    /// of reserves, in basis points of the price before.
+    pub fn price_move_bps(&self, before: (u64, u64), after: (u64, u64)) -> Result<u64> {
+        Ok(self.price_move_bps_at(before, after, Clock::get()?.unix_timestamp))
+    }
+
+    /// `price_move_bps` as of `now`, for callers without the clock sysvar.
+    pub fn price_move_bps_at(&self, before: (u64, u64), after: (u64, u64), now: i64) -> u64 {
+        let (price_before, _) = self.spot_prices_at(before.0, before.1, now);
+        if price_before == 0 || after.0 == 0 {
+            return 0;
+        }
+
+        let (price_after, _) = self.spot_prices_at(after.0, after.1, now);
+        let bps = price_before.abs_diff(price_after).saturating_mul(10_000) / price_before;
+        u64::try_from(bps).unwrap_or(u64::MAX)
+    }
+
+    /// Marginal prices of x in y and of y in x on the pool's curve at `now`,
+    /// as Q64.64. Both are zero for an empty side.
+    pub fn spot_prices_at(&self, reserve_x: u64, reserve_y: u64, now: i64) -> (u128, u128) {
+        if reserve_x == 0 || reserve_y == 0 {
+            return (0, 0);
+        }
+
+        match self.pool_type {
+            // `(y / w_y) / (x / w_x)`
+            PoolType::Weighted => (
+                q64_weighted_ratio(reserve_y, self.weight_x, reserve_x, self.weight_y),
+                q64_weighted_ratio(reserve_x, self.weight_y, reserve_y, self.weight_x),
+            ),
+            // Stays near 1 until the pool is far off balance, unlike y / x.
+            // The invariant only fails to converge on reserves no swap can
+            // reach, those fall back to the constant-product price.
+            PoolType::StableSwap => {
+                let amp = self.amp(now);
+                (
+                    stable_swap::spot_price(amp, reserve_x, reserve_y)
+                        .unwrap_or_else(|| q64_ratio(reserve_y, reserve_x)),
+                    stable_swap::spot_price(amp, reserve_y, reserve_x)
+                        .unwrap_or_else(|| q64_ratio(reserve_x, reserve_y)),
+                )
+            }
+            PoolType::ConstantProduct => (
+                q64_ratio(reserve_y, reserve_x),
+                q64_ratio(reserve_x, reserve_y),
+            ),
+        }
+    }
+}
+
+/// `numerator / denominator` as a Q64.64 fixed-point number
+pub fn q64_ratio(numerator: u64, denominator: u64) -> u128 {
+    ((numerator as u128) << 64) / denominator as u128
+}
+
+/// `(numerator · numerator_weight) / (denominator · denominator_weight)` as a
+/// Q64.64 fixed-point number, saturating
+pub fn q64_weighted_ratio(
+    numerator: u64,
+    numerator_weight: u16,
+    denominator: u64,
+    denominator_weight: u16,
+) -> u128 {
+    let ratio = ((U256::from(numerator) * U256::from(numerator_weight)) << 64)
+        / (U256::from(denominator) * U256::from(denominator_weight));
+    if ratio > U256::from(u128::MAX) {
+        u128::MAX
+    } else {
+        ratio.as_u128()
+    }
 }
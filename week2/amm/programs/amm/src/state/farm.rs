@@ -0,0 +1,81 @@
+use anchor_lang::prelude::*;
+
+use crate::curve::U256;
+use crate::error::AmmError;
+
+/// Emits `reward_mint` to whoever stakes a `Config`'s LP tokens, pro rata to
+/// their stake and the time it was staked.
+#[account]
+pub struct Farm {
+    pub config: Pubkey,
+    pub reward_mint: Pubkey,
+    pub reward_per_second: u64,
+    pub reward_per_share: u128,
+    pub total_staked: u64,
+    pub reward_funded: u64,
+    pub reward_emitted: u64,
+    pub last_update_ts: i64,
+    pub bump: u8,
+}
+
+impl Farm {
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        32 + // config
+        32 + // reward_mint
+        8 + // reward_per_second
+        16 + // reward_per_share (u128, Q64.64)
+        8 + // total_staked
+        8 + // reward_funded, everything fund_farm put into the vault
+        8 + // reward_emitted, never more than reward_funded
+        8 + // last_update_ts (i64)
+        1; // bump
+
+    /// Seeds the farm signs with as authority of its LP and reward vaults
+    pub fn signer_seeds(&self) -> [&[u8]; 3] {
+        [
+            b"farm",
+            self.config.as_ref(),
+            std::slice::from_ref(&self.bump),
+        ]
+    }
+
+    /// Adds the rewards emitted since the last update to the reward per staked
+    /// LP token. Nothing is emitted while nothing is staked, and emissions stop
+    /// once everything funded has gone out until the farm is topped up again.
+    /// Wrapping on purpose, only differences between two readings mean
+    /// anything.
+    pub fn update(&mut self, now: i64) -> Result<()> {
+        let elapsed = now.saturating_sub(self.last_update_ts).max(0) as u128;
+        let unemitted = self
+            .reward_funded
+            .checked_sub(self.reward_emitted)
+            .ok_or(AmmError::Overflow)?;
+
+        if self.total_staked > 0 && unemitted > 0 {
+            let rewards = (elapsed * self.reward_per_second as u128).min(unemitted as u128);
+            self.reward_emitted = self
+                .reward_emitted
+                .checked_add(rewards as u64)
+                .ok_or(AmmError::Overflow)?;
+
+            let growth = (U256::from(rewards) << 64) / U256::from(self.total_staked);
+            let growth = u128::try_from(growth).map_err(|_| AmmError::Overflow)?;
+            self.reward_per_share = self.reward_per_share.wrapping_add(growth);
+        }
+        self.last_update_ts = now;
+
+        Ok(())
+    }
+
+    /// Adds `amount` to what can be emitted. Settles the time before the top
+    /// up first, so it doesn't pay for a stretch the farm had run dry.
+    pub fn fund(&mut self, amount: u64, now: i64) -> Result<()> {
+        self.update(now)?;
+        self.reward_funded = self
+            .reward_funded
+            .checked_add(amount)
+            .ok_or(AmmError::Overflow)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Config;
+
+/// The order PDA's signer seeds, owned so they can outlive the `LimitOrder`
+/// borrow they were read from. See `LimitOrder::signer_seeds`.
+pub struct OrderSeeds {
+    config: Pubkey,
+    owner: Pubkey,
+    seed: [u8; 8],
+    bump: [u8; 1],
+}
+
+impl OrderSeeds {
+    pub fn as_seeds(&self) -> [&[u8]; 5] {
+        [
+            b"order",
+            self.config.as_ref(),
+            self.owner.as_ref(),
+            &self.seed,
+            &self.bump,
+        ]
+    }
+}
+
+/// Tokens `owner` escrowed to sell into a `Config`'s pool once the pool pays
+/// at least `limit_amount_out` for all of them. Anyone can fill it then, and
+/// earns the `tip` lamports the order holds on top of its rent.
+///
+/// The limit is an average execution price over the whole escrow, price
+/// impact and fees included, not a spot price trigger: the order fills once
+/// selling all of `amount_in` in one swap would pay `limit_amount_out`.
+#[account]
+pub struct LimitOrder {
+    pub config: Pubkey,
+    pub owner: Pubkey,
+    pub seed: u64,
+    pub is_x: bool,
+    pub amount_in: u64,
+    pub limit_amount_out: u64,
+    pub tip: u64,
+    pub expiration: i64,
+    pub bump: u8,
+}
+
+impl LimitOrder {
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        32 + // config
+        32 + // owner
+        8 + // seed
+        1 + // is_x
+        8 + // amount_in (what reached the escrow)
+        8 + // limit_amount_out (what the whole escrow must fetch)
+        8 + // tip (lamports)
+        8 + // expiration (i64)
+        1; // bump
+
+    pub fn signer_seeds(&self) -> OrderSeeds {
+        OrderSeeds {
+            config: self.config,
+            owner: self.owner,
+            seed: self.seed.to_le_bytes(),
+            bump: [self.bump],
+        }
+    }
+
+    /// The mints the order sells and buys
+    pub fn mints(&self, config: &Config) -> (Pubkey, Pubkey) {
+        if self.is_x {
+            (config.mint_x, config.mint_y)
+        } else {
+            (config.mint_y, config.mint_x)
+        }
+    }
+}
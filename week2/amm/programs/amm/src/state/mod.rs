@@ -1,3 +1,17 @@
+mod cl_pool;
 mod config;
+mod farm;
+mod limit_order;
+mod oracle;
+mod position;
+mod stake;
+mod tick_array;
 
+pub use cl_pool::*;
 pub use config::*;
+pub use farm::*;
+pub use limit_order::*;
+pub use oracle::*;
+pub use position::*;
+pub use stake::*;
+pub use tick_array::*;
@@ -0,0 +1,91 @@
+use anchor_lang::prelude::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct Observation {
+    pub timestamp: i64,
+    pub price_x_cumulative: u128,
+    pub price_y_cumulative: u128,
+}
+
+impl Observation {
+    pub const SIZE: usize = 8 + 16 + 16;
+}
+
+/// Time-weighted average prices as Q64.64 fixed-point numbers
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct Twap {
+    pub price_x: u128,
+    pub price_y: u128,
+}
+
+#[account]
+pub struct Oracle {
+    pub config: Pubkey,
+    pub index: u16,
+    pub observations: Vec<Observation>,
+    pub bump: u8,
+}
+
+impl Oracle {
+    pub const MAX_OBSERVATIONS: usize = 64;
+
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        32 + // config
+        2 + // index (u16)
+        (4 + Self::MAX_OBSERVATIONS * Observation::SIZE) + // observations
+        1; // bump
+
+    pub fn latest(&self) -> Option<&Observation> {
+        match self.index {
+            0 => self.observations.last(),
+            index => self.observations.get(index as usize - 1),
+        }
+    }
+
+    // Fills the buffer first, then overwrites the oldest observation at `index`
+    pub fn record(&mut self, observation: Observation) {
+        if self.observations.len() < Self::MAX_OBSERVATIONS {
+            self.observations.push(observation);
+        } else {
+            self.observations[self.index as usize] = observation;
+            self.index = ((self.index as usize + 1) % Self::MAX_OBSERVATIONS) as u16;
+        }
+    }
+
+    /// Cumulative prices at `target`, interpolated between the stored observations
+    /// and `current`. `None` if `target` is older than the oldest observation.
+    pub fn cumulative_prices_at(&self, target: i64, current: Observation) -> Option<(u128, u128)> {
+        let (newer, older) = self.observations.split_at(self.index as usize);
+        let mut history = older
+            .iter()
+            .chain(newer.iter())
+            .chain(std::iter::once(&current));
+
+        let mut before = history.next()?;
+        if target < before.timestamp {
+            return None;
+        }
+
+        for after in history {
+            if target <= after.timestamp {
+                let span = after.timestamp - before.timestamp;
+                if span == 0 {
+                    return Some((after.price_x_cumulative, after.price_y_cumulative));
+                }
+                let elapsed = (target - before.timestamp) as u128;
+
+                let interpolate = |from: u128, to: u128| {
+                    from.wrapping_add(to.wrapping_sub(from) / span as u128 * elapsed)
+                };
+
+                return Some((
+                    interpolate(before.price_x_cumulative, after.price_x_cumulative),
+                    interpolate(before.price_y_cumulative, after.price_y_cumulative),
+                ));
+            }
+            before = after;
+        }
+
+        None
+    }
+}
@@ -0,0 +1,74 @@
+use anchor_lang::prelude::*;
+
+use crate::curve::U256;
+use crate::error::AmmError;
+
+/// Liquidity in `[tick_lower, tick_upper)` of a `ClPool`, owned by whoever
+/// holds the single token of `position_mint`.
+#[account]
+pub struct Position {
+    pub cl_pool: Pubkey,
+    pub position_mint: Pubkey,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: u128,
+    pub fee_growth_inside_x_last: u128,
+    pub fee_growth_inside_y_last: u128,
+    pub fees_owed_x: u64,
+    pub fees_owed_y: u64,
+    pub bump: u8,
+}
+
+impl Position {
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        32 + // cl_pool
+        32 + // position_mint
+        4 + // tick_lower (i32)
+        4 + // tick_upper (i32)
+        16 + // liquidity (u128)
+        16 + // fee_growth_inside_x_last (u128, Q64.64)
+        16 + // fee_growth_inside_y_last (u128, Q64.64)
+        8 + // fees_owed_x
+        8 + // fees_owed_y
+        1; // bump
+
+    /// Credits the fees earned since the last update to the position, then
+    /// applies `liquidity_delta`.
+    pub fn update(
+        &mut self,
+        liquidity_delta: i128,
+        fee_growth_inside_x: u128,
+        fee_growth_inside_y: u128,
+    ) -> Result<()> {
+        let owed_x = fees_earned(
+            self.liquidity,
+            fee_growth_inside_x.wrapping_sub(self.fee_growth_inside_x_last),
+        )?;
+        let owed_y = fees_earned(
+            self.liquidity,
+            fee_growth_inside_y.wrapping_sub(self.fee_growth_inside_y_last),
+        )?;
+
+        self.fees_owed_x = self
+            .fees_owed_x
+            .checked_add(owed_x)
+            .ok_or(AmmError::Overflow)?;
+        self.fees_owed_y = self
+            .fees_owed_y
+            .checked_add(owed_y)
+            .ok_or(AmmError::Overflow)?;
+        self.fee_growth_inside_x_last = fee_growth_inside_x;
+        self.fee_growth_inside_y_last = fee_growth_inside_y;
+        self.liquidity = self
+            .liquidity
+            .checked_add_signed(liquidity_delta)
+            .ok_or(AmmError::Overflow)?;
+
+        Ok(())
+    }
+}
+
+fn fees_earned(liquidity: u128, fee_growth: u128) -> Result<u64> {
+    let fees = (U256::from(liquidity) * U256::from(fee_growth)) >> 64;
+    u64::try_from(fees).map_err(|_| AmmError::Overflow.into())
+}
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+use crate::curve::U256;
+use crate::error::AmmError;
+
+/// LP tokens `owner` has staked in a `Farm`, and the rewards they are owed.
+#[account]
+pub struct Stake {
+    pub farm: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub reward_per_share_last: u128,
+    pub rewards_owed: u64,
+    pub bump: u8,
+}
+
+impl Stake {
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        32 + // farm
+        32 + // owner
+        8 + // amount
+        16 + // reward_per_share_last (u128, Q64.64)
+        8 + // rewards_owed
+        1; // bump
+
+    /// Credits the rewards earned since the last update, then applies
+    /// `amount_delta`. The farm has to be updated first.
+    pub fn update(&mut self, amount_delta: i64, reward_per_share: u128) -> Result<()> {
+        let earned = (U256::from(self.amount)
+            * U256::from(reward_per_share.wrapping_sub(self.reward_per_share_last)))
+            >> 64;
+        let earned = u64::try_from(earned).map_err(|_| AmmError::Overflow)?;
+
+        self.rewards_owed = self
+            .rewards_owed
+            .checked_add(earned)
+            .ok_or(AmmError::Overflow)?;
+        self.reward_per_share_last = reward_per_share;
+        self.amount = self
+            .amount
+            .checked_add_signed(amount_delta)
+            .ok_or(AmmError::InvalidAmount)?;
+
+        Ok(())
+    }
+}
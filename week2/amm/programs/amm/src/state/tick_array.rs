@@ -0,0 +1,195 @@
+use anchor_lang::prelude::*;
+
+use crate::constants::{MAX_TICK, MIN_TICK};
+use crate::error::AmmError;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct Tick {
+    pub liquidity_net: i128,
+    pub liquidity_gross: u128,
+    pub fee_growth_outside_x: u128,
+    pub fee_growth_outside_y: u128,
+}
+
+impl Tick {
+    pub const SIZE: usize = 16 + 16 + 16 + 16;
+
+    pub fn is_initialized(&self) -> bool {
+        self.liquidity_gross > 0
+    }
+
+    /// Adds a position's liquidity change at this tick, as its lower or upper
+    /// bound. Fee growth below a fresh tick is assumed to have all happened
+    /// below it, which only shifts every reading by the same constant.
+    pub fn update(
+        &mut self,
+        tick: i32,
+        tick_current: i32,
+        liquidity_delta: i128,
+        is_upper: bool,
+        fee_growth_global_x: u128,
+        fee_growth_global_y: u128,
+    ) -> Result<()> {
+        let liquidity_gross = self
+            .liquidity_gross
+            .checked_add_signed(liquidity_delta)
+            .ok_or(AmmError::Overflow)?;
+
+        if !self.is_initialized() && tick <= tick_current {
+            self.fee_growth_outside_x = fee_growth_global_x;
+            self.fee_growth_outside_y = fee_growth_global_y;
+        }
+
+        self.liquidity_gross = liquidity_gross;
+        self.liquidity_net = if is_upper {
+            self.liquidity_net.checked_sub(liquidity_delta)
+        } else {
+            self.liquidity_net.checked_add(liquidity_delta)
+        }
+        .ok_or(AmmError::Overflow)?;
+
+        if liquidity_gross == 0 {
+            *self = Tick::default();
+        }
+
+        Ok(())
+    }
+
+    /// Flips which side of the tick the fee growth is recorded for as the
+    /// price moves across it. Returns the liquidity to add going up.
+    pub fn cross(&mut self, fee_growth_global_x: u128, fee_growth_global_y: u128) -> i128 {
+        self.fee_growth_outside_x = fee_growth_global_x.wrapping_sub(self.fee_growth_outside_x);
+        self.fee_growth_outside_y = fee_growth_global_y.wrapping_sub(self.fee_growth_outside_y);
+        self.liquidity_net
+    }
+}
+
+/// A run of `TICK_ARRAY_SIZE` ticks, `tick_spacing` apart, starting at
+/// `start_tick_index`.
+#[account]
+pub struct TickArray {
+    pub cl_pool: Pubkey,
+    pub start_tick_index: i32,
+    pub ticks: [Tick; TickArray::TICK_ARRAY_SIZE],
+    pub bump: u8,
+}
+
+impl TickArray {
+    pub const TICK_ARRAY_SIZE: usize = 32;
+
+    pub const INIT_SPACE: usize = 8 + // discriminator
+        32 + // cl_pool
+        4 + // start_tick_index (i32)
+        Self::TICK_ARRAY_SIZE * Tick::SIZE + // ticks
+        1; // bump
+
+    /// Reads a tick array passed outside the typed accounts, for when it may
+    /// alias another account or the count is only known at runtime.
+    pub fn load(info: &AccountInfo, cl_pool: Pubkey) -> Result<Box<TickArray>> {
+        require_keys_eq!(*info.owner, crate::ID, AmmError::InvalidTickArray);
+        require!(info.is_writable, AmmError::InvalidTickArray);
+
+        let data = info.try_borrow_data()?;
+        let tick_array = Box::new(TickArray::try_deserialize(&mut &data[..])?);
+        require_keys_eq!(tick_array.cl_pool, cl_pool, AmmError::InvalidTickArray);
+
+        Ok(tick_array)
+    }
+
+    /// Writes back a tick array read with `load`.
+    pub fn store(&self, info: &AccountInfo) -> Result<()> {
+        let mut data = info.try_borrow_mut_data()?;
+        let mut writer: &mut [u8] = &mut data[..];
+        self.try_serialize(&mut writer)
+    }
+
+    /// Ticks covered by one array
+    pub fn span(tick_spacing: u16) -> i32 {
+        tick_spacing as i32 * Self::TICK_ARRAY_SIZE as i32
+    }
+
+    /// Start of the array holding `tick`.
+    pub fn start_tick_index_for(tick: i32, tick_spacing: u16) -> i32 {
+        tick.div_euclid(Self::span(tick_spacing)) * Self::span(tick_spacing)
+    }
+
+    pub fn check_start_tick_index(start_tick_index: i32, tick_spacing: u16) -> Result<()> {
+        require!(
+            tick_spacing > 0
+                && start_tick_index == Self::start_tick_index_for(start_tick_index, tick_spacing)
+                && start_tick_index + Self::span(tick_spacing) > MIN_TICK
+                && start_tick_index <= MAX_TICK,
+            AmmError::InvalidTickArray
+        );
+        Ok(())
+    }
+
+    pub fn contains(&self, tick: i32, tick_spacing: u16) -> bool {
+        tick >= self.start_tick_index && tick < self.start_tick_index + Self::span(tick_spacing)
+    }
+
+    fn offset(&self, tick: i32, tick_spacing: u16) -> Result<usize> {
+        require!(tick % tick_spacing as i32 == 0, AmmError::InvalidTick);
+        require!(
+            self.contains(tick, tick_spacing),
+            AmmError::InvalidTickArray
+        );
+
+        Ok(((tick - self.start_tick_index) / tick_spacing as i32) as usize)
+    }
+
+    pub fn tick(&self, tick: i32, tick_spacing: u16) -> Result<&Tick> {
+        Ok(&self.ticks[self.offset(tick, tick_spacing)?])
+    }
+
+    pub fn tick_mut(&mut self, tick: i32, tick_spacing: u16) -> Result<&mut Tick> {
+        let offset = self.offset(tick, tick_spacing)?;
+        Ok(&mut self.ticks[offset])
+    }
+
+    /// The closest initialized tick in this array in the swap direction:
+    /// at or below `tick_current` when the price falls, above it when it rises.
+    pub fn next_initialized_tick(
+        &self,
+        tick_current: i32,
+        tick_spacing: u16,
+        x_to_y: bool,
+    ) -> Option<i32> {
+        let spacing = tick_spacing as i32;
+        let end = self.start_tick_index + Self::span(tick_spacing);
+        let tick_at = |offset: usize| self.start_tick_index + offset as i32 * spacing;
+
+        if x_to_y {
+            if tick_current < self.start_tick_index {
+                return None;
+            }
+            let from = ((tick_current - self.start_tick_index) / spacing)
+                .min(Self::TICK_ARRAY_SIZE as i32 - 1) as usize;
+            (0..=from)
+                .rev()
+                .find(|&offset| self.ticks[offset].is_initialized())
+                .map(tick_at)
+        } else {
+            if tick_current >= end {
+                return None;
+            }
+            let from = if tick_current < self.start_tick_index {
+                0
+            } else {
+                ((tick_current - self.start_tick_index) / spacing + 1) as usize
+            };
+            (from..Self::TICK_ARRAY_SIZE)
+                .find(|&offset| self.ticks[offset].is_initialized())
+                .map(tick_at)
+        }
+    }
+
+    /// Furthest tick of this array in the swap direction.
+    pub fn last_tick(&self, tick_spacing: u16, x_to_y: bool) -> i32 {
+        if x_to_y {
+            self.start_tick_index
+        } else {
+            self.start_tick_index + Self::span(tick_spacing) - tick_spacing as i32
+        }
+    }
+}
@@ -0,0 +1,968 @@
+//! In-process harness for the compiled program. Build it first with
+//! `anchor build`, the tests load `target/deploy/anchor_amm.so`.
+#![allow(dead_code, deprecated)]
+
+use anchor_amm::{
+    accounts, error::AmmError, instruction, Config, DepositQuote, PoolType, SwapQuote,
+    WithdrawQuote, NO_EXPIRATION,
+};
+use anchor_lang::{
+    prelude::{AccountMeta, Pubkey},
+    solana_program::{
+        instruction::{Instruction, InstructionError},
+        program_pack::Pack,
+        system_instruction, system_program, sysvar,
+    },
+    AccountDeserialize, AnchorDeserialize, InstructionData, ToAccountMetas,
+};
+use anchor_spl::{
+    associated_token::{
+        self, get_associated_token_address_with_program_id,
+        spl_associated_token_account::instruction::create_associated_token_account,
+    },
+    token::spl_token,
+    token_interface::spl_token_2022::{
+        self,
+        extension::{
+            transfer_fee::instruction::initialize_transfer_fee_config, ExtensionType,
+            StateWithExtensions,
+        },
+    },
+};
+use litesvm::{types::TransactionResult, LiteSVM};
+use solana_sdk::{
+    account::Account,
+    compute_budget::ComputeBudgetInstruction,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+const PROGRAM_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../../target/deploy/anchor_amm.so"
+);
+
+pub const DECIMALS: u8 = 6;
+pub const FEE: u16 = 30;
+pub const PROTOCOL_FEE: u16 = 1_000;
+pub const LIQUIDITY: u64 = 1_000_000_000;
+
+/// What the pool's x mint is, y is always a plain SPL mint
+enum MintX {
+    Spl,
+    Native,
+    /// A Token-2022 mint charging this many basis points on every transfer
+    TransferFee(u16),
+}
+
+/// A freshly initialized constant-product pool between two new SPL mints
+pub struct Pool {
+    pub svm: LiteSVM,
+    pub admin: Keypair,
+    pub seed: u64,
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub token_program_x: Pubkey,
+    pub token_program_y: Pubkey,
+    pub config: Pubkey,
+    pub mint_lp: Pubkey,
+    pub lp_lock: Pubkey,
+    pub vault_x: Pubkey,
+    pub vault_y: Pubkey,
+    pub protocol_vault_x: Pubkey,
+    pub protocol_vault_y: Pubkey,
+}
+
+impl Pool {
+    pub fn new() -> Self {
+        Self::with_curve(PoolType::ConstantProduct, 0)
+    }
+
+    /// A pool priced on `pool_type`, `amp` only matters for StableSwap
+    pub fn with_curve(pool_type: PoolType, amp: u64) -> Self {
+        Self::build(pool_type, amp, MintX::Spl)
+    }
+
+    /// A constant-product pool with wrapped SOL as x, which users pay in and
+    /// get out as native SOL
+    pub fn with_native_x() -> Self {
+        Self::build(PoolType::ConstantProduct, 0, MintX::Native)
+    }
+
+    /// A constant-product pool with a Token-2022 x mint that withholds
+    /// `basis_points` of every transfer
+    pub fn with_transfer_fee_x(basis_points: u16) -> Self {
+        Self::build(
+            PoolType::ConstantProduct,
+            0,
+            MintX::TransferFee(basis_points),
+        )
+    }
+
+    fn build(pool_type: PoolType, amp: u64, mint_x: MintX) -> Self {
+        let mut svm = LiteSVM::new();
+        svm.add_program_from_file(anchor_amm::ID, PROGRAM_PATH)
+            .expect("run `anchor build` before the Rust tests");
+
+        let admin = Keypair::new();
+        svm.airdrop(&admin.pubkey(), 100_000_000_000).unwrap();
+
+        let seed = 42u64;
+        let config = Pubkey::find_program_address(
+            &[b"config", seed.to_le_bytes().as_ref()],
+            &anchor_amm::ID,
+        )
+        .0;
+        let pda = |prefix: &[u8], key: &Pubkey| {
+            Pubkey::find_program_address(&[prefix, config.as_ref(), key.as_ref()], &anchor_amm::ID)
+                .0
+        };
+
+        let mut pool = Pool {
+            svm,
+            admin,
+            seed,
+            mint_x: Pubkey::default(),
+            mint_y: Pubkey::default(),
+            token_program_x: spl_token::ID,
+            token_program_y: spl_token::ID,
+            config,
+            mint_lp: Pubkey::find_program_address(&[b"lp", config.as_ref()], &anchor_amm::ID).0,
+            lp_lock: Pubkey::find_program_address(&[b"lp_lock", config.as_ref()], &anchor_amm::ID)
+                .0,
+            vault_x: Pubkey::default(),
+            vault_y: Pubkey::default(),
+            protocol_vault_x: Pubkey::default(),
+            protocol_vault_y: Pubkey::default(),
+        };
+
+        pool.mint_x = match mint_x {
+            MintX::Spl => pool.create_mint(),
+            MintX::Native => pool.native_mint(),
+            MintX::TransferFee(basis_points) => {
+                pool.token_program_x = spl_token_2022::ID;
+                pool.create_transfer_fee_mint(basis_points)
+            }
+        };
+        pool.mint_y = pool.create_mint();
+        pool.vault_x = pool.token_account(&config, &pool.mint_x);
+        pool.vault_y = pool.token_account(&config, &pool.mint_y);
+        pool.protocol_vault_x = pda(b"protocol", &pool.mint_x);
+        pool.protocol_vault_y = pda(b"protocol", &pool.mint_y);
+
+        pool.initialize(FEE, PROTOCOL_FEE, pool_type, amp).unwrap();
+        pool
+    }
+
+    pub fn initialize(
+        &mut self,
+        fee: u16,
+        protocol_fee: u16,
+        pool_type: PoolType,
+        amp: u64,
+    ) -> TransactionResult {
+        let accounts = accounts::Initialize {
+            admin: self.admin.pubkey(),
+            mint_x: self.mint_x,
+            mint_y: self.mint_y,
+            config: self.config,
+            mint_lp: self.mint_lp,
+            lp_lock: self.lp_lock,
+            vault_x: self.vault_x,
+            vault_y: self.vault_y,
+            protocol_vault_x: self.protocol_vault_x,
+            protocol_vault_y: self.protocol_vault_y,
+            token_program_x: self.token_program_x,
+            token_program_y: self.token_program_y,
+            token_program: spl_token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: anchor_amm::ID,
+        };
+        let data = instruction::Initialize {
+            seed: self.seed,
+            fee,
+            protocol_fee,
+            authority: Some(self.admin.pubkey()),
+            pool_type,
+            amp,
+            weight_x: 0,
+        };
+
+        let admin = self.admin.insecure_clone();
+        self.send(accounts.to_account_metas(None), data.data(), &admin)
+    }
+
+    /// A funded wallet holding `amount_x` and `amount_y` of the pool's tokens.
+    /// A native side is left unwrapped, the wallet's SOL covers it.
+    pub fn user(&mut self, amount_x: u64, amount_y: u64) -> Keypair {
+        let user = Keypair::new();
+        self.svm.airdrop(&user.pubkey(), 10_000_000_000).unwrap();
+
+        for (mint, amount) in [(self.mint_x, amount_x), (self.mint_y, amount_y)] {
+            if mint == spl_token::native_mint::ID {
+                continue;
+            }
+
+            let token_program = self.token_program(&mint);
+            let create = create_associated_token_account(
+                &self.admin.pubkey(),
+                &user.pubkey(),
+                &mint,
+                &token_program,
+            );
+            let mint_to = spl_token_2022::instruction::mint_to(
+                &token_program,
+                &mint,
+                &self.token_account(&user.pubkey(), &mint),
+                &self.admin.pubkey(),
+                &[],
+                amount,
+            )
+            .unwrap();
+
+            let admin = self.admin.insecure_clone();
+            self.send_instructions(&[create, mint_to], &[&admin])
+                .unwrap();
+        }
+
+        user
+    }
+
+    pub fn deposit(
+        &mut self,
+        user: &Keypair,
+        amount: u64,
+        max_x: u64,
+        max_y: u64,
+    ) -> TransactionResult {
+        let ix = self.deposit_instruction(&user.pubkey(), amount, max_x, max_y);
+        self.send_instructions(&[ix], &[user])
+    }
+
+    pub fn deposit_instruction(
+        &self,
+        user: &Pubkey,
+        amount: u64,
+        max_x: u64,
+        max_y: u64,
+    ) -> Instruction {
+        let accounts = accounts::Deposit {
+            user: *user,
+            mint_x: self.mint_x,
+            mint_y: self.mint_y,
+            config: self.config,
+            mint_lp: self.mint_lp,
+            vault_x: self.vault_x,
+            vault_y: self.vault_y,
+            user_token_account_x: self.token_account(user, &self.mint_x),
+            user_token_account_y: self.token_account(user, &self.mint_y),
+            user_token_account_lp: self.token_account(user, &self.mint_lp),
+            lp_lock: self.lp_lock,
+            token_program_x: self.token_program_x,
+            token_program_y: self.token_program_y,
+            token_program: spl_token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: anchor_amm::ID,
+        };
+        let data = instruction::Deposit {
+            amount,
+            max_x,
+            max_y,
+            expiration: NO_EXPIRATION,
+        };
+
+        program_instruction(accounts.to_account_metas(None), data.data())
+    }
+
+    pub fn withdraw(
+        &mut self,
+        user: &Keypair,
+        amount: u64,
+        min_x: u64,
+        min_y: u64,
+    ) -> TransactionResult {
+        let accounts = accounts::Withdraw {
+            user: user.pubkey(),
+            mint_x: self.mint_x,
+            mint_y: self.mint_y,
+            config: self.config,
+            mint_lp: self.mint_lp,
+            vault_x: self.vault_x,
+            vault_y: self.vault_y,
+            user_token_account_x: self.token_account(&user.pubkey(), &self.mint_x),
+            user_token_account_y: self.token_account(&user.pubkey(), &self.mint_y),
+            user_token_account_lp: self.token_account(&user.pubkey(), &self.mint_lp),
+            token_program_x: self.token_program_x,
+            token_program_y: self.token_program_y,
+            token_program: spl_token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: anchor_amm::ID,
+        };
+        let data = instruction::Withdraw {
+            amount,
+            min_x,
+            min_y,
+            expiration: NO_EXPIRATION,
+        };
+
+        self.send(accounts.to_account_metas(None), data.data(), user)
+    }
+
+    pub fn swap(
+        &mut self,
+        user: &Keypair,
+        is_x: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> TransactionResult {
+        let ix = self.swap_instruction(&user.pubkey(), is_x, amount_in, min_amount_out);
+        self.send_instructions(&[ix], &[user])
+    }
+
+    pub fn swap_instruction(
+        &self,
+        user: &Pubkey,
+        is_x: bool,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> Instruction {
+        let accounts = accounts::Swap {
+            user: *user,
+            mint_x: self.mint_x,
+            mint_y: self.mint_y,
+            config: self.config,
+            mint_lp: self.mint_lp,
+            vault_x: self.vault_x,
+            vault_y: self.vault_y,
+            user_token_account_x: self.token_account(user, &self.mint_x),
+            user_token_account_y: self.token_account(user, &self.mint_y),
+            protocol_vault_x: self.protocol_vault_x,
+            protocol_vault_y: self.protocol_vault_y,
+            token_program_x: self.token_program_x,
+            token_program_y: self.token_program_y,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: anchor_amm::ID,
+        };
+        let data = instruction::Swap {
+            is_x,
+            amount_in,
+            min_amount_out,
+            expiration: NO_EXPIRATION,
+        };
+
+        program_instruction(accounts.to_account_metas(None), data.data())
+    }
+
+    fn flash_loan_accounts(&self, user: &Pubkey) -> Vec<AccountMeta> {
+        accounts::FlashLoan {
+            user: *user,
+            mint_x: self.mint_x,
+            mint_y: self.mint_y,
+            config: self.config,
+            vault_x: self.vault_x,
+            vault_y: self.vault_y,
+            user_token_account_x: self.token_account(user, &self.mint_x),
+            user_token_account_y: self.token_account(user, &self.mint_y),
+            instruction_sysvar: sysvar::instructions::ID,
+            token_program_x: self.token_program_x,
+            token_program_y: self.token_program_y,
+            event_authority: event_authority(),
+            program: anchor_amm::ID,
+        }
+        .to_account_metas(None)
+    }
+
+    /// Only goes through in a transaction that also carries `flash_repay`
+    pub fn flash_borrow_instruction(&self, user: &Pubkey, is_x: bool, amount: u64) -> Instruction {
+        let data = instruction::FlashBorrow { is_x, amount };
+        program_instruction(self.flash_loan_accounts(user), data.data())
+    }
+
+    pub fn flash_repay_instruction(&self, user: &Pubkey) -> Instruction {
+        program_instruction(
+            self.flash_loan_accounts(user),
+            instruction::FlashRepay {}.data(),
+        )
+    }
+
+    pub fn quote_swap(&mut self, is_x: bool, amount_in: u64, min_amount_out: u64) -> SwapQuote {
+        let data = instruction::QuoteSwap {
+            is_x,
+            amount_in,
+            min_amount_out,
+            expiration: NO_EXPIRATION,
+        };
+        self.quote(data.data())
+    }
+
+    pub fn quote_deposit(&mut self, amount: u64, max_x: u64, max_y: u64) -> DepositQuote {
+        let data = instruction::QuoteDeposit {
+            amount,
+            max_x,
+            max_y,
+            expiration: NO_EXPIRATION,
+        };
+        self.quote(data.data())
+    }
+
+    pub fn quote_withdraw(&mut self, amount: u64, min_x: u64, min_y: u64) -> WithdrawQuote {
+        let data = instruction::QuoteWithdraw {
+            amount,
+            min_x,
+            min_y,
+            expiration: NO_EXPIRATION,
+        };
+        self.quote(data.data())
+    }
+
+    /// Runs a quote instruction and decodes what it returned. Panics if the
+    /// quote fails.
+    fn quote<T: AnchorDeserialize>(&mut self, data: Vec<u8>) -> T {
+        let accounts = accounts::Quote {
+            mint_x: self.mint_x,
+            mint_y: self.mint_y,
+            config: self.config,
+            mint_lp: self.mint_lp,
+            vault_x: self.vault_x,
+            vault_y: self.vault_y,
+            token_program_x: self.token_program_x,
+            token_program_y: self.token_program_y,
+        };
+
+        let admin = self.admin.insecure_clone();
+        let meta = self
+            .send(accounts.to_account_metas(None), data, &admin)
+            .unwrap_or_else(|failed| panic!("{}", failed.meta.pretty_logs()));
+        T::try_from_slice(&meta.return_data.data).unwrap()
+    }
+
+    /// Sends tokens straight to the vaults, outside of any instruction
+    pub fn donate(&mut self, user: &Keypair, amount_x: u64, amount_y: u64) -> TransactionResult {
+        let transfers = [
+            (self.mint_x, self.vault_x, amount_x),
+            (self.mint_y, self.vault_y, amount_y),
+        ]
+        .map(|(mint, vault, amount)| {
+            spl_token_2022::instruction::transfer_checked(
+                &self.token_program(&mint),
+                &self.token_account(&user.pubkey(), &mint),
+                &mint,
+                &vault,
+                &user.pubkey(),
+                &[],
+                amount,
+                DECIMALS,
+            )
+            .unwrap()
+        });
+
+        self.send_instructions(&transfers, &[user])
+    }
+
+    pub fn deposit_single(
+        &mut self,
+        user: &Keypair,
+        is_x: bool,
+        amount_in: u64,
+        min_lp_out: u64,
+    ) -> TransactionResult {
+        let (mint_in, _, _) = self.side(is_x);
+        let accounts = accounts::DepositSingle {
+            user: user.pubkey(),
+            mint_x: self.mint_x,
+            mint_y: self.mint_y,
+            config: self.config,
+            mint_lp: self.mint_lp,
+            vault_x: self.vault_x,
+            vault_y: self.vault_y,
+            protocol_vault_x: self.protocol_vault_x,
+            protocol_vault_y: self.protocol_vault_y,
+            user_token_account_in: self.token_account(&user.pubkey(), &mint_in),
+            user_token_account_lp: self.token_account(&user.pubkey(), &self.mint_lp),
+            token_program_x: self.token_program_x,
+            token_program_y: self.token_program_y,
+            token_program: spl_token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: anchor_amm::ID,
+        };
+        let data = instruction::DepositSingle {
+            is_x,
+            amount_in,
+            min_lp_out,
+            expiration: NO_EXPIRATION,
+        };
+
+        self.send(accounts.to_account_metas(None), data.data(), user)
+    }
+
+    pub fn withdraw_single(
+        &mut self,
+        user: &Keypair,
+        is_x: bool,
+        lp_amount: u64,
+        min_out: u64,
+    ) -> TransactionResult {
+        let (mint_out, _, _) = self.side(is_x);
+        let accounts = accounts::WithdrawSingle {
+            user: user.pubkey(),
+            mint_x: self.mint_x,
+            mint_y: self.mint_y,
+            config: self.config,
+            mint_lp: self.mint_lp,
+            vault_x: self.vault_x,
+            vault_y: self.vault_y,
+            protocol_vault_x: self.protocol_vault_x,
+            protocol_vault_y: self.protocol_vault_y,
+            user_token_account_out: self.token_account(&user.pubkey(), &mint_out),
+            user_token_account_lp: self.token_account(&user.pubkey(), &self.mint_lp),
+            token_program_x: self.token_program_x,
+            token_program_y: self.token_program_y,
+            token_program: spl_token::ID,
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: anchor_amm::ID,
+        };
+        let data = instruction::WithdrawSingle {
+            is_x,
+            lp_amount,
+            min_out,
+            expiration: NO_EXPIRATION,
+        };
+
+        self.send(accounts.to_account_metas(None), data.data(), user)
+    }
+
+    /// Snapshots the cumulative prices into the pool's oracle
+    pub fn observe(&mut self) -> TransactionResult {
+        let accounts = accounts::Observe {
+            payer: self.admin.pubkey(),
+            config: self.config,
+            oracle: Pubkey::find_program_address(
+                &[b"oracle", self.config.as_ref()],
+                &anchor_amm::ID,
+            )
+            .0,
+            system_program: system_program::ID,
+        };
+
+        let admin = self.admin.insecure_clone();
+        self.send(
+            accounts.to_account_metas(None),
+            instruction::Observe {}.data(),
+            &admin,
+        )
+    }
+
+    pub fn update_circuit_breaker(
+        &mut self,
+        max_trade_bps: u16,
+        max_price_move_bps: u16,
+        window: u64,
+    ) -> TransactionResult {
+        let accounts = accounts::Update {
+            authority: self.admin.pubkey(),
+            config: self.config,
+        };
+        let data = instruction::UpdateCircuitBreaker {
+            max_trade_bps,
+            max_price_move_bps,
+            window,
+        };
+
+        let admin = self.admin.insecure_clone();
+        self.send(accounts.to_account_metas(None), data.data(), &admin)
+    }
+
+    pub fn order(&self, owner: &Pubkey, seed: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                b"order",
+                self.config.as_ref(),
+                owner.as_ref(),
+                seed.to_le_bytes().as_ref(),
+            ],
+            &anchor_amm::ID,
+        )
+        .0
+    }
+
+    /// The mint, pool vault and protocol vault on the x side if `is_x`
+    fn side(&self, is_x: bool) -> (Pubkey, Pubkey, Pubkey) {
+        if is_x {
+            (self.mint_x, self.vault_x, self.protocol_vault_x)
+        } else {
+            (self.mint_y, self.vault_y, self.protocol_vault_y)
+        }
+    }
+
+    pub fn place_limit_order(
+        &mut self,
+        owner: &Keypair,
+        seed: u64,
+        is_x: bool,
+        amount_in: u64,
+        limit_amount_out: u64,
+        tip: u64,
+    ) -> TransactionResult {
+        let (mint_in, _, _) = self.side(is_x);
+        let order = self.order(&owner.pubkey(), seed);
+
+        let accounts = accounts::PlaceLimitOrder {
+            owner: owner.pubkey(),
+            mint_in,
+            config: self.config,
+            order,
+            order_vault: self.token_account(&order, &mint_in),
+            owner_token_account_in: self.token_account(&owner.pubkey(), &mint_in),
+            token_program: self.token_program(&mint_in),
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        };
+        let data = instruction::PlaceLimitOrder {
+            seed,
+            amount_in,
+            limit_amount_out,
+            tip,
+            expiration: NO_EXPIRATION,
+        };
+
+        self.send(accounts.to_account_metas(None), data.data(), owner)
+    }
+
+    pub fn fill_limit_order(
+        &mut self,
+        keeper: &Keypair,
+        owner: &Pubkey,
+        seed: u64,
+        is_x: bool,
+    ) -> TransactionResult {
+        let (mint_in, vault_in, protocol_vault_in) = self.side(is_x);
+        let (mint_out, vault_out, _) = self.side(!is_x);
+        let order = self.order(owner, seed);
+
+        let accounts = accounts::FillLimitOrder {
+            keeper: keeper.pubkey(),
+            owner: *owner,
+            mint_in,
+            mint_out,
+            config: self.config,
+            mint_lp: self.mint_lp,
+            order,
+            order_vault: self.token_account(&order, &mint_in),
+            vault_in,
+            vault_out,
+            protocol_vault_in,
+            owner_token_account_out: self.token_account(owner, &mint_out),
+            token_program_in: self.token_program(&mint_in),
+            token_program_out: self.token_program(&mint_out),
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+            event_authority: event_authority(),
+            program: anchor_amm::ID,
+        };
+
+        self.send(
+            accounts.to_account_metas(None),
+            instruction::FillLimitOrder {}.data(),
+            keeper,
+        )
+    }
+
+    pub fn cancel_limit_order(
+        &mut self,
+        owner: &Keypair,
+        seed: u64,
+        is_x: bool,
+    ) -> TransactionResult {
+        let (mint_in, _, _) = self.side(is_x);
+        let order = self.order(&owner.pubkey(), seed);
+
+        let accounts = accounts::CancelLimitOrder {
+            owner: owner.pubkey(),
+            mint_in,
+            config: self.config,
+            order,
+            order_vault: self.token_account(&order, &mint_in),
+            owner_token_account_in: self.token_account(&owner.pubkey(), &mint_in),
+            token_program: self.token_program(&mint_in),
+            associated_token_program: associated_token::ID,
+            system_program: system_program::ID,
+        };
+
+        self.send(
+            accounts.to_account_metas(None),
+            instruction::CancelLimitOrder {}.data(),
+            owner,
+        )
+    }
+
+    pub fn lamports(&self, account: &Pubkey) -> u64 {
+        self.svm
+            .get_account(account)
+            .map(|account| account.lamports)
+            .unwrap_or(0)
+    }
+
+    pub fn config(&self) -> Config {
+        let account = self.svm.get_account(&self.config).unwrap();
+        Config::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    /// The token program that owns `mint`
+    pub fn token_program(&self, mint: &Pubkey) -> Pubkey {
+        if *mint == self.mint_x {
+            self.token_program_x
+        } else if *mint == self.mint_y {
+            self.token_program_y
+        } else {
+            spl_token::ID
+        }
+    }
+
+    /// `owner`'s associated token account for `mint`, under whichever token
+    /// program owns it
+    pub fn token_account(&self, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, mint, &self.token_program(mint))
+    }
+
+    /// Token balance, zero for accounts that don't exist yet
+    pub fn balance(&self, token_account: &Pubkey) -> u64 {
+        self.svm
+            .get_account(token_account)
+            .filter(|account| !account.data.is_empty())
+            .map(|account| {
+                StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+                    .unwrap()
+                    .base
+                    .amount
+            })
+            .unwrap_or(0)
+    }
+
+    pub fn reserves(&self) -> (u64, u64) {
+        (self.balance(&self.vault_x), self.balance(&self.vault_y))
+    }
+
+    pub fn lp_supply(&self) -> u64 {
+        let account = self.svm.get_account(&self.mint_lp).unwrap();
+        spl_token::state::Mint::unpack(&account.data)
+            .unwrap()
+            .supply
+    }
+
+    pub fn lp_balance(&self, user: &Keypair) -> u64 {
+        self.balance(&self.token_account(&user.pubkey(), &self.mint_lp))
+    }
+
+    pub fn x_balance(&self, user: &Keypair) -> u64 {
+        self.balance(&self.token_account(&user.pubkey(), &self.mint_x))
+    }
+
+    pub fn y_balance(&self, user: &Keypair) -> u64 {
+        self.balance(&self.token_account(&user.pubkey(), &self.mint_y))
+    }
+
+    /// Wraps `amount` of `user`'s SOL by hand, the way a wallet would
+    pub fn wrap_sol(&mut self, user: &Keypair, amount: u64) -> TransactionResult {
+        let native_mint = spl_token::native_mint::ID;
+        let token_account = ata(&user.pubkey(), &native_mint);
+
+        let create = create_associated_token_account(
+            &user.pubkey(),
+            &user.pubkey(),
+            &native_mint,
+            &spl_token::ID,
+        );
+        let transfer = system_instruction::transfer(&user.pubkey(), &token_account, amount);
+        let sync = spl_token::instruction::sync_native(&spl_token::ID, &token_account).unwrap();
+
+        self.send_instructions(&[create, transfer, sync], &[user])
+    }
+
+    /// The wrapped SOL mint, added to the SVM if it doesn't ship with it
+    fn native_mint(&mut self) -> Pubkey {
+        let native_mint = spl_token::native_mint::ID;
+        if self.svm.get_account(&native_mint).is_none() {
+            let mut data = vec![0; spl_token::state::Mint::LEN];
+            spl_token::state::Mint {
+                decimals: spl_token::native_mint::DECIMALS,
+                is_initialized: true,
+                ..Default::default()
+            }
+            .pack_into_slice(&mut data);
+
+            let account = Account {
+                lamports: self
+                    .svm
+                    .minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN),
+                data,
+                owner: spl_token::ID,
+                executable: false,
+                rent_epoch: 0,
+            };
+            self.svm.set_account(native_mint, account).unwrap();
+        }
+
+        native_mint
+    }
+
+    fn create_mint(&mut self) -> Pubkey {
+        let mint = Keypair::new();
+        let rent = self
+            .svm
+            .minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN);
+
+        let create = system_instruction::create_account(
+            &self.admin.pubkey(),
+            &mint.pubkey(),
+            rent,
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::ID,
+        );
+        let initialize = spl_token::instruction::initialize_mint2(
+            &spl_token::ID,
+            &mint.pubkey(),
+            &self.admin.pubkey(),
+            None,
+            DECIMALS,
+        )
+        .unwrap();
+
+        let admin = self.admin.insecure_clone();
+        self.send_instructions(&[create, initialize], &[&admin, &mint])
+            .unwrap();
+
+        mint.pubkey()
+    }
+
+    /// A Token-2022 mint that withholds `basis_points` of every transfer in
+    /// the recipient's account, with no cap
+    fn create_transfer_fee_mint(&mut self, basis_points: u16) -> Pubkey {
+        let mint = Keypair::new();
+        let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+            ExtensionType::TransferFeeConfig,
+        ])
+        .unwrap();
+        let rent = self.svm.minimum_balance_for_rent_exemption(space);
+
+        let create = system_instruction::create_account(
+            &self.admin.pubkey(),
+            &mint.pubkey(),
+            rent,
+            space as u64,
+            &spl_token_2022::ID,
+        );
+        let transfer_fee = initialize_transfer_fee_config(
+            &spl_token_2022::ID,
+            &mint.pubkey(),
+            None,
+            None,
+            basis_points,
+            u64::MAX,
+        )
+        .unwrap();
+        let initialize = spl_token_2022::instruction::initialize_mint2(
+            &spl_token_2022::ID,
+            &mint.pubkey(),
+            &self.admin.pubkey(),
+            None,
+            DECIMALS,
+        )
+        .unwrap();
+
+        let admin = self.admin.insecure_clone();
+        self.send_instructions(&[create, transfer_fee, initialize], &[&admin, &mint])
+            .unwrap();
+
+        mint.pubkey()
+    }
+
+    fn send(
+        &mut self,
+        accounts: Vec<AccountMeta>,
+        data: Vec<u8>,
+        signer: &Keypair,
+    ) -> TransactionResult {
+        self.send_instructions(&[program_instruction(accounts, data)], &[signer])
+    }
+
+    /// Sends `ixs` in one transaction, after the compute budget instruction
+    pub fn send_instructions(
+        &mut self,
+        ixs: &[Instruction],
+        signers: &[&Keypair],
+    ) -> TransactionResult {
+        let mut all = vec![ComputeBudgetInstruction::set_compute_unit_limit(1_400_000)];
+        all.extend_from_slice(ixs);
+
+        let tx = Transaction::new_signed_with_payer(
+            &all,
+            Some(&signers[0].pubkey()),
+            signers,
+            self.svm.latest_blockhash(),
+        );
+        let result = self.svm.send_transaction(tx);
+
+        // Identical transactions are otherwise rejected as already processed
+        self.svm.expire_blockhash();
+        result
+    }
+}
+
+impl Default for Pool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A pool seeded 1:1 by a first LP, who is returned along with it
+pub fn seeded_pool() -> (Pool, Keypair) {
+    let mut pool = Pool::new();
+    let lp = pool.user(LIQUIDITY, LIQUIDITY);
+    pool.deposit(&lp, 1, LIQUIDITY, LIQUIDITY).unwrap();
+    (pool, lp)
+}
+
+fn program_instruction(accounts: Vec<AccountMeta>, data: Vec<u8>) -> Instruction {
+    Instruction {
+        program_id: anchor_amm::ID,
+        accounts,
+        data,
+    }
+}
+
+pub fn ata(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(owner, mint, &spl_token::ID)
+}
+
+pub fn event_authority() -> Pubkey {
+    Pubkey::find_program_address(&[b"__event_authority"], &anchor_amm::ID).0
+}
+
+/// Fails unless the transaction was rejected by the program with `error`
+pub fn assert_amm_error(result: TransactionResult, error: AmmError) {
+    // Right after the compute budget instruction
+    assert_amm_error_at(result, 1, error);
+}
+
+/// `assert_amm_error` for the instruction at `index` in the transaction
+pub fn assert_amm_error_at(result: TransactionResult, index: u8, error: AmmError) {
+    let failed = result.expect_err("transaction should have failed");
+    assert_eq!(
+        failed.err,
+        TransactionError::InstructionError(index, InstructionError::Custom(error.into())),
+        "{}",
+        failed.meta.pretty_logs()
+    );
+}
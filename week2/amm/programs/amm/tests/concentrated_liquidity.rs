@@ -0,0 +1,178 @@
+mod common;
+
+use anchor_amm::{
+    curve::concentrated::{
+        amounts_for_liquidity, compute_swap_step, sqrt_price_at_tick, tick_at_sqrt_price,
+    },
+    error::AmmError,
+    sqrt_price_move_bps, ClPool, Position, Tick, MAX_TICK, MIN_TICK,
+};
+use anchor_lang::prelude::Pubkey;
+use common::Pool;
+
+const Q64: u128 = 1 << 64;
+
+fn new_pool() -> ClPool {
+    ClPool {
+        config: Pubkey::default(),
+        tick_spacing: 10,
+        sqrt_price: Q64,
+        tick_current: 0,
+        liquidity: 0,
+        fee_growth_global_x: 0,
+        fee_growth_global_y: 0,
+        breaker_slot: 0,
+        breaker_sqrt_price: 0,
+        bump: 0,
+    }
+}
+
+#[test]
+fn tick_zero_is_price_one() {
+    assert_eq!(sqrt_price_at_tick(0).unwrap(), Q64);
+    assert_eq!(tick_at_sqrt_price(Q64).unwrap(), 0);
+}
+
+#[test]
+fn ticks_round_trip_through_sqrt_price() {
+    for tick in [MIN_TICK, -200_000, -7, -1, 1, 13, 60_000, MAX_TICK - 1] {
+        let sqrt_price = sqrt_price_at_tick(tick).unwrap();
+        assert!(sqrt_price_at_tick(tick + 1).unwrap() > sqrt_price);
+
+        assert_eq!(tick_at_sqrt_price(sqrt_price).unwrap(), tick);
+        // Anywhere short of the next tick still floors to this one
+        assert_eq!(tick_at_sqrt_price(sqrt_price + 1).unwrap(), tick);
+    }
+
+    assert!(sqrt_price_at_tick(MAX_TICK + 1).is_err());
+    assert!(sqrt_price_at_tick(MIN_TICK - 1).is_err());
+}
+
+#[test]
+fn range_below_price_is_all_y_and_above_is_all_x() {
+    let (lower, upper) = (
+        sqrt_price_at_tick(-600).unwrap(),
+        sqrt_price_at_tick(600).unwrap(),
+    );
+    let liquidity = 1_000_000_000;
+
+    let (x, y) = amounts_for_liquidity(Q64, lower, upper, liquidity, true).unwrap();
+    assert!(x > 0 && y > 0);
+
+    let (x, y) = amounts_for_liquidity(lower - 1, lower, upper, liquidity, true).unwrap();
+    assert!(x > 0 && y == 0);
+
+    let (x, y) = amounts_for_liquidity(upper, lower, upper, liquidity, true).unwrap();
+    assert!(x == 0 && y > 0);
+}
+
+#[test]
+fn swap_step_never_spends_more_than_it_has() {
+    let liquidity = 50_000_000_000;
+    let target = sqrt_price_at_tick(-1_000).unwrap();
+
+    for amount in [1u128, 999, 1_000_000, 10_000_000_000] {
+        let step = compute_swap_step(Q64, target, liquidity, amount, 30).unwrap();
+        assert!(step.amount_in + step.fee_amount <= amount);
+        assert!(step.sqrt_price_next >= target && step.sqrt_price_next <= Q64);
+        // Selling x at a price of ~1 returns less y than went in
+        assert!(step.amount_out <= step.amount_in);
+    }
+
+    // Enough input stops exactly at the target and keeps the rest
+    let step = compute_swap_step(Q64, target, liquidity, u64::MAX as u128, 30).unwrap();
+    assert_eq!(step.sqrt_price_next, target);
+    assert!(step.amount_in + step.fee_amount < u64::MAX as u128);
+}
+
+#[test]
+fn fees_go_to_positions_in_range() {
+    let mut pool = new_pool();
+    let new_position = |tick_lower, tick_upper| Position {
+        cl_pool: Pubkey::default(),
+        position_mint: Pubkey::default(),
+        tick_lower,
+        tick_upper,
+        liquidity: 0,
+        fee_growth_inside_x_last: 0,
+        fee_growth_inside_y_last: 0,
+        fees_owed_x: 0,
+        fees_owed_y: 0,
+        bump: 0,
+    };
+
+    let liquidity = 1_000_000i128;
+    let (mut t_lower, mut t_mid, mut t_upper) = (Tick::default(), Tick::default(), Tick::default());
+    let mut in_range = new_position(-100, 100);
+    let mut out_of_range = new_position(100, 200);
+
+    t_lower.update(-100, 0, liquidity, false, 0, 0).unwrap();
+    t_mid.update(100, 0, liquidity, true, 0, 0).unwrap();
+    in_range.update(liquidity, 0, 0).unwrap();
+    t_mid.update(100, 0, liquidity, false, 0, 0).unwrap();
+    t_upper.update(200, 0, liquidity, true, 0, 0).unwrap();
+    out_of_range.update(liquidity, 0, 0).unwrap();
+    pool.liquidity = liquidity as u128;
+
+    // A swap at the current price earns 5_000 of x for the active liquidity
+    pool.fee_growth_global_x += (5_000u128 << 64) / pool.liquidity;
+
+    let (x, y) = pool.fee_growth_inside(-100, &t_lower, 100, &t_mid);
+    in_range.update(0, x, y).unwrap();
+    let (x, y) = pool.fee_growth_inside(100, &t_mid, 200, &t_upper);
+    out_of_range.update(0, x, y).unwrap();
+
+    assert!(in_range.fees_owed_x >= 4_999 && in_range.fees_owed_x <= 5_000);
+    assert_eq!(out_of_range.fees_owed_x, 0);
+
+    // Moving the price into the upper range hands it the next fees
+    let net = t_mid.cross(pool.fee_growth_global_x, pool.fee_growth_global_y);
+    pool.liquidity = pool.liquidity.checked_add_signed(net).unwrap();
+    pool.tick_current = 150;
+    pool.fee_growth_global_x += (3_000u128 << 64) / pool.liquidity;
+
+    let (x, y) = pool.fee_growth_inside(-100, &t_lower, 100, &t_mid);
+    in_range.update(0, x, y).unwrap();
+    let (x, y) = pool.fee_growth_inside(100, &t_mid, 200, &t_upper);
+    out_of_range.update(0, x, y).unwrap();
+
+    assert!(in_range.fees_owed_x <= 5_000);
+    assert!(out_of_range.fees_owed_x >= 2_999 && out_of_range.fees_owed_x <= 3_000);
+}
+
+#[test]
+fn price_moves_are_measured_on_the_price_not_its_square_root() {
+    // Half again the square root is 2.25 times the price
+    assert_eq!(sqrt_price_move_bps(Q64, Q64 + Q64 / 2), 12_500);
+    assert_eq!(sqrt_price_move_bps(Q64, Q64 / 2), 7_500);
+    assert_eq!(sqrt_price_move_bps(Q64, Q64), 0);
+    assert_eq!(sqrt_price_move_bps(0, Q64), 0);
+}
+
+#[test]
+fn circuit_breaker_keeps_its_own_window() {
+    let mut config = Pool::new().config();
+    // 2% of price per 10 slots
+    config.max_price_move_bps = 200;
+    config.breaker_window = 10;
+    let mut pool = new_pool();
+
+    // Each of these moves the price about 1.6%, only one fits in a window
+    pool.sqrt_price = Q64 - Q64 / 125;
+    pool.check_price_move_at(&config, Q64, 100).unwrap();
+    let before = pool.sqrt_price;
+    pool.sqrt_price -= Q64 / 125;
+    let result = pool.check_price_move_at(&config, before, 105);
+    assert_eq!(result.unwrap_err(), AmmError::PriceMoveTooLarge.into());
+
+    // The next window opens where the swap found the price
+    pool.check_price_move_at(&config, before, 110).unwrap();
+    assert_eq!((pool.breaker_slot, pool.breaker_sqrt_price), (110, before));
+
+    // No limit, no window
+    config.max_price_move_bps = 0;
+    let mut pool = new_pool();
+    pool.sqrt_price = Q64 / 2;
+    pool.check_price_move_at(&config, Q64, 0).unwrap();
+    assert_eq!(pool.breaker_sqrt_price, 0);
+}
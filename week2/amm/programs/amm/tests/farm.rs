@@ -0,0 +1,102 @@
+use anchor_amm::{Farm, Stake};
+use anchor_lang::prelude::Pubkey;
+
+/// A farm funded well past anything the tests emit
+fn new_farm(reward_per_second: u64) -> Farm {
+    Farm {
+        config: Pubkey::default(),
+        reward_mint: Pubkey::default(),
+        reward_per_second,
+        reward_per_share: 0,
+        total_staked: 0,
+        reward_funded: u64::MAX,
+        reward_emitted: 0,
+        last_update_ts: 0,
+        bump: 0,
+    }
+}
+
+fn new_stake() -> Stake {
+    Stake {
+        farm: Pubkey::default(),
+        owner: Pubkey::default(),
+        amount: 0,
+        reward_per_share_last: 0,
+        rewards_owed: 0,
+        bump: 0,
+    }
+}
+
+fn stake(farm: &mut Farm, stake: &mut Stake, now: i64, amount: i64) {
+    farm.update(now).unwrap();
+    stake.update(amount, farm.reward_per_share).unwrap();
+    farm.total_staked = farm.total_staked.checked_add_signed(amount).unwrap();
+}
+
+#[test]
+fn nothing_is_emitted_while_nothing_is_staked() {
+    let mut farm = new_farm(1_000);
+    let mut alice = new_stake();
+
+    stake(&mut farm, &mut alice, 100, 500);
+    stake(&mut farm, &mut alice, 110, 0);
+
+    // Only the ten seconds alice was staked count
+    assert!(alice.rewards_owed >= 9_999 && alice.rewards_owed <= 10_000);
+}
+
+#[test]
+fn rewards_are_split_pro_rata_over_time() {
+    let mut farm = new_farm(1_000);
+    let (mut alice, mut bob) = (new_stake(), new_stake());
+
+    stake(&mut farm, &mut alice, 0, 100);
+    // Alice earns everything for the first 10 seconds
+    stake(&mut farm, &mut bob, 10, 300);
+    // Then a quarter of it for the next 20
+    stake(&mut farm, &mut alice, 30, -100);
+    // And bob the rest until he leaves
+    stake(&mut farm, &mut bob, 40, -300);
+
+    assert!(alice.rewards_owed >= 14_999 && alice.rewards_owed <= 15_000);
+    assert!(bob.rewards_owed >= 24_999 && bob.rewards_owed <= 25_000);
+    assert_eq!(farm.total_staked, 0);
+
+    // Emissions pause once everyone has left
+    stake(&mut farm, &mut alice, 100, 0);
+    assert!(alice.rewards_owed <= 15_000);
+}
+
+#[test]
+fn emissions_stop_when_the_funding_runs_out() {
+    let mut farm = new_farm(1_000);
+    farm.reward_funded = 15_000;
+    let mut alice = new_stake();
+
+    stake(&mut farm, &mut alice, 0, 100);
+    // Funding covers 15 of these 20 seconds
+    stake(&mut farm, &mut alice, 20, 0);
+    assert!(alice.rewards_owed >= 14_999 && alice.rewards_owed <= 15_000);
+    assert_eq!(farm.reward_emitted, 15_000);
+
+    // Topping up doesn't pay for the time the farm was dry
+    farm.fund(5_000, 30).unwrap();
+    stake(&mut farm, &mut alice, 30, 0);
+    assert!(alice.rewards_owed <= 15_000);
+
+    // But emissions pick up again from there, until it runs out again
+    stake(&mut farm, &mut alice, 33, 0);
+    assert!(alice.rewards_owed >= 17_999 && alice.rewards_owed <= 18_000);
+    stake(&mut farm, &mut alice, 100, 0);
+    assert!(alice.rewards_owed >= 19_999 && alice.rewards_owed <= 20_000);
+    assert_eq!(farm.reward_emitted, farm.reward_funded);
+}
+
+#[test]
+fn unstaking_more_than_staked_fails() {
+    let mut farm = new_farm(1_000);
+    let mut alice = new_stake();
+
+    stake(&mut farm, &mut alice, 0, 100);
+    assert!(alice.update(-101, farm.reward_per_share).is_err());
+}
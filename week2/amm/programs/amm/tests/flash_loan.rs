@@ -0,0 +1,80 @@
+mod common;
+
+use anchor_amm::error::AmmError;
+use common::{assert_amm_error, assert_amm_error_at, seeded_pool, Pool, FEE, LIQUIDITY};
+use solana_sdk::signature::{Keypair, Signer};
+
+const LOAN: u64 = LIQUIDITY / 10;
+
+/// A pool seeded 1:1 and a borrower holding enough x to pay the fee
+fn setup() -> (Pool, Keypair) {
+    let (mut pool, _) = seeded_pool();
+    let borrower = pool.user(LIQUIDITY, LIQUIDITY);
+    (pool, borrower)
+}
+
+#[test]
+fn borrow_and_repay_in_one_transaction_pays_the_fee_to_the_pool() {
+    let (mut pool, borrower) = setup();
+    let user = borrower.pubkey();
+
+    let borrow = pool.flash_borrow_instruction(&user, true, LOAN);
+    let repay = pool.flash_repay_instruction(&user);
+    pool.send_instructions(&[borrow, repay], &[&borrower])
+        .unwrap();
+
+    // The fee rounds up and stays in the vault for LPs
+    let fee = (LOAN * FEE as u64).div_ceil(10_000);
+    assert_eq!(pool.reserves(), (LIQUIDITY + fee, LIQUIDITY));
+    assert_eq!(pool.x_balance(&borrower), LIQUIDITY - fee);
+    assert_eq!(pool.config().flash_loan_amount, 0);
+
+    // The pool is usable again once the loan is repaid
+    pool.swap(&borrower, true, 1_000, 0).unwrap();
+}
+
+#[test]
+fn borrow_without_a_repay_fails() {
+    let (mut pool, borrower) = setup();
+    let user = borrower.pubkey();
+
+    let borrow = pool.flash_borrow_instruction(&user, true, LOAN);
+    let result = pool.send_instructions(&[borrow], &[&borrower]);
+    assert_amm_error(result, AmmError::MissingFlashRepay);
+
+    // A repay with nothing borrowed has nothing to pay back
+    let repay = pool.flash_repay_instruction(&user);
+    let result = pool.send_instructions(&[repay], &[&borrower]);
+    assert_amm_error(result, AmmError::NoFlashLoan);
+
+    assert_eq!(pool.reserves(), (LIQUIDITY, LIQUIDITY));
+}
+
+#[test]
+fn pool_refuses_swaps_and_deposits_while_a_loan_is_out() {
+    let (mut pool, borrower) = setup();
+    let user = borrower.pubkey();
+
+    // Pricing against a drained vault would be a free trade
+    let swap = pool.swap_instruction(&user, false, LOAN, 0);
+    let ixs = [
+        pool.flash_borrow_instruction(&user, true, LOAN),
+        swap,
+        pool.flash_repay_instruction(&user),
+    ];
+    let result = pool.send_instructions(&ixs, &[&borrower]);
+    assert_amm_error_at(result, 2, AmmError::FlashLoanActive);
+
+    let deposit = pool.deposit_instruction(&user, 1_000, LIQUIDITY, LIQUIDITY);
+    let ixs = [
+        pool.flash_borrow_instruction(&user, true, LOAN),
+        deposit,
+        pool.flash_repay_instruction(&user),
+    ];
+    let result = pool.send_instructions(&ixs, &[&borrower]);
+    assert_amm_error_at(result, 2, AmmError::FlashLoanActive);
+
+    // Nothing moved
+    assert_eq!(pool.reserves(), (LIQUIDITY, LIQUIDITY));
+    assert_eq!(pool.config().flash_loan_amount, 0);
+}
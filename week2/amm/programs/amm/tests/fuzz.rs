@@ -0,0 +1,84 @@
+mod common;
+
+use anchor_amm::curve::U256;
+use common::Pool;
+use proptest::prelude::*;
+
+const USERS: usize = 3;
+const BALANCE: u64 = 1_000_000_000_000;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Deposit {
+        user: usize,
+        amount: u64,
+    },
+    Withdraw {
+        user: usize,
+        share: u8,
+    },
+    Swap {
+        user: usize,
+        is_x: bool,
+        amount: u64,
+    },
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        (0..USERS, 1u64..1_000_000_000_000).prop_map(|(user, amount)| Op::Deposit { user, amount }),
+        (0..USERS, 1u8..=100).prop_map(|(user, share)| Op::Withdraw { user, share }),
+        (0..USERS, any::<bool>(), 1u64..100_000_000_000)
+            .prop_map(|(user, is_x, amount)| Op::Swap { user, is_x, amount }),
+    ]
+}
+
+/// `sqrt(k) / supply`, compared squared so it stays in integers
+fn value_per_lp_at_least(before: (u64, u64, u64), after: (u64, u64, u64)) -> bool {
+    let k = |(x, y, _): (u64, u64, u64)| U256::from(x) * U256::from(y);
+    let supply_squared = |(_, _, supply): (u64, u64, u64)| U256::from(supply) * U256::from(supply);
+
+    k(after) * supply_squared(before) >= k(before) * supply_squared(after)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    /// Whatever order users deposit, withdraw and swap in, swaps never shrink
+    /// `k` and nothing ever shrinks what one LP token is worth.
+    #[test]
+    fn random_operations_keep_the_invariant(ops in prop::collection::vec(op(), 1..40)) {
+        let mut pool = Pool::new();
+        let users: Vec<_> = (0..USERS).map(|_| pool.user(BALANCE, BALANCE)).collect();
+        pool.deposit(&users[0], 1, 1_000_000_000, 1_000_000_000).unwrap();
+
+        for op in ops {
+            let (x, y) = pool.reserves();
+            let before = (x, y, pool.lp_supply());
+
+            // Ops the program rejects must leave everything as it was
+            let ok = match op {
+                Op::Deposit { user, amount } => {
+                    pool.deposit(&users[user], amount, u64::MAX, u64::MAX).is_ok()
+                }
+                Op::Withdraw { user, share } => {
+                    let amount = pool.lp_balance(&users[user]) * share as u64 / 100;
+                    pool.withdraw(&users[user], amount, 0, 0).is_ok()
+                }
+                Op::Swap { user, is_x, amount } => {
+                    let ok = pool.swap(&users[user], is_x, amount, 0).is_ok();
+                    let (x_after, y_after) = pool.reserves();
+                    prop_assert!(x_after as u128 * y_after as u128 >= x as u128 * y as u128);
+                    ok
+                }
+            };
+
+            let (x, y) = pool.reserves();
+            let after = (x, y, pool.lp_supply());
+            if !ok {
+                prop_assert_eq!(before, after);
+            }
+            prop_assert!(value_per_lp_at_least(before, after), "{:?} -> {:?}", before, after);
+        }
+    }
+}
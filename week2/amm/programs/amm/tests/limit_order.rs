@@ -0,0 +1,122 @@
+mod common;
+
+use anchor_amm::error::AmmError;
+use common::{assert_amm_error, ata, seeded_pool, Pool, LIQUIDITY};
+use solana_sdk::signature::{Keypair, Signer};
+
+const ORDER: u64 = 1_000_000;
+const TIP: u64 = 1_000_000;
+/// 1%, so the fees on ORDER come out exact
+const TRANSFER_FEE_BPS: u64 = 100;
+
+/// A pool seeded 1:1, an order owner and a keeper
+fn setup() -> (Pool, Keypair, Keypair) {
+    let (mut pool, _) = seeded_pool();
+
+    let owner = pool.user(ORDER, ORDER);
+    let keeper = pool.user(0, 0);
+    (pool, owner, keeper)
+}
+
+#[test]
+fn fill_waits_for_the_limit_price() {
+    let (mut pool, owner, keeper) = setup();
+
+    // Sell y for 10% more x than the pool pays now
+    pool.place_limit_order(&owner, 0, false, ORDER, ORDER * 11 / 10, TIP)
+        .unwrap();
+    assert_eq!(pool.y_balance(&owner), 0);
+
+    let result = pool.fill_limit_order(&keeper, &owner.pubkey(), 0, false);
+    assert_amm_error(result, AmmError::LimitPriceNotReached);
+
+    // Someone dumps x until y is worth the limit
+    let whale = pool.user(LIQUIDITY, 0);
+    pool.swap(&whale, true, LIQUIDITY / 4, 0).unwrap();
+
+    let keeper_lamports = pool.lamports(&keeper.pubkey());
+    pool.fill_limit_order(&keeper, &owner.pubkey(), 0, false)
+        .unwrap();
+
+    assert!(pool.x_balance(&owner) >= ORDER + ORDER * 11 / 10);
+    // The tip covers the keeper's transaction fee
+    assert!(pool.lamports(&keeper.pubkey()) > keeper_lamports);
+    let order = pool.order(&owner.pubkey(), 0);
+    assert_eq!(pool.lamports(&order), 0);
+    assert_eq!(pool.lamports(&ata(&order, &pool.mint_y)), 0);
+}
+
+#[test]
+fn cancel_returns_the_escrow() {
+    let (mut pool, owner, keeper) = setup();
+
+    pool.place_limit_order(&owner, 7, true, ORDER, ORDER * 2, TIP)
+        .unwrap();
+    assert_eq!(pool.x_balance(&owner), 0);
+
+    // Only the owner's own order PDA can be cancelled
+    let thief = pool.user(0, 0);
+    assert!(pool.cancel_limit_order(&thief, 7, true).is_err());
+
+    let owner_lamports = pool.lamports(&owner.pubkey());
+    pool.cancel_limit_order(&owner, 7, true).unwrap();
+
+    assert_eq!(pool.x_balance(&owner), ORDER);
+    // Rent and the unused tip come back
+    assert!(pool.lamports(&owner.pubkey()) > owner_lamports + TIP);
+    assert!(pool
+        .fill_limit_order(&keeper, &owner.pubkey(), 7, true)
+        .is_err());
+}
+
+/// What's left of `amount` after one transfer of the fee mint
+fn after_transfer_fee(amount: u64) -> u64 {
+    amount - amount * TRANSFER_FEE_BPS / 10_000
+}
+
+#[test]
+fn transfer_fee_escrows_are_harvested_and_closed() {
+    let mut pool = Pool::with_transfer_fee_x(TRANSFER_FEE_BPS as u16);
+    let lp = pool.user(LIQUIDITY, LIQUIDITY);
+    pool.deposit(&lp, 1, LIQUIDITY, LIQUIDITY).unwrap();
+    let owner = pool.user(ORDER * 2, 0);
+    let keeper = pool.user(0, 0);
+
+    // Filled: the escrow keeps the fee withheld on its way in
+    pool.place_limit_order(&owner, 0, true, ORDER, ORDER / 2, TIP)
+        .unwrap();
+    let order = pool.order(&owner.pubkey(), 0);
+    let order_vault = pool.token_account(&order, &pool.mint_x);
+    assert_eq!(pool.balance(&order_vault), after_transfer_fee(ORDER));
+
+    pool.fill_limit_order(&keeper, &owner.pubkey(), 0, true)
+        .unwrap();
+    assert!(pool.y_balance(&owner) >= ORDER / 2);
+    assert_eq!(pool.lamports(&order), 0);
+    assert_eq!(pool.lamports(&order_vault), 0);
+
+    // Cancelled: the owner gets back the escrow less the fee going out
+    pool.place_limit_order(&owner, 1, true, ORDER, ORDER * 2, TIP)
+        .unwrap();
+    let order = pool.order(&owner.pubkey(), 1);
+    let order_vault = pool.token_account(&order, &pool.mint_x);
+
+    pool.cancel_limit_order(&owner, 1, true).unwrap();
+    assert_eq!(
+        pool.x_balance(&owner),
+        after_transfer_fee(after_transfer_fee(ORDER))
+    );
+    assert_eq!(pool.lamports(&order), 0);
+    assert_eq!(pool.lamports(&order_vault), 0);
+}
+
+#[test]
+fn orders_need_an_amount_and_a_limit() {
+    let (mut pool, owner, _) = setup();
+
+    let result = pool.place_limit_order(&owner, 0, true, 0, ORDER, TIP);
+    assert_amm_error(result, AmmError::InvalidAmount);
+
+    let result = pool.place_limit_order(&owner, 0, true, ORDER, 0, TIP);
+    assert_amm_error(result, AmmError::InvalidAmount);
+}
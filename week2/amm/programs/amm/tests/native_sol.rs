@@ -0,0 +1,56 @@
+mod common;
+
+use common::{ata, Pool, LIQUIDITY};
+use solana_sdk::signature::{Keypair, Signer};
+
+const WRAPPED: u64 = LIQUIDITY / 100;
+
+/// A pool of native SOL and a token, seeded 1:1 from a wallet holding no
+/// wrapped SOL
+fn native_pool() -> (Pool, Keypair) {
+    let mut pool = Pool::with_native_x();
+    let lp = pool.user(0, LIQUIDITY);
+    pool.deposit(&lp, 1, LIQUIDITY, LIQUIDITY).unwrap();
+    (pool, lp)
+}
+
+#[test]
+fn native_sol_goes_in_and_out_without_leaving_a_wrapped_account() {
+    let (mut pool, lp) = native_pool();
+    assert_eq!(pool.reserves(), (LIQUIDITY, LIQUIDITY));
+    assert_eq!(pool.lamports(&ata(&lp.pubkey(), &pool.mint_x)), 0);
+
+    let user = pool.user(0, LIQUIDITY);
+    let wrapped = ata(&user.pubkey(), &pool.mint_x);
+
+    let lamports = pool.lamports(&user.pubkey());
+    pool.swap(&user, false, LIQUIDITY / 10, 0).unwrap();
+    assert!(pool.lamports(&user.pubkey()) > lamports + LIQUIDITY / 20);
+    assert_eq!(pool.lamports(&wrapped), 0);
+
+    let lamports = pool.lamports(&user.pubkey());
+    let y = pool.y_balance(&user);
+    pool.swap(&user, true, LIQUIDITY / 20, 0).unwrap();
+    assert!(lamports - pool.lamports(&user.pubkey()) >= LIQUIDITY / 20);
+    assert!(pool.y_balance(&user) > y);
+    assert_eq!(pool.lamports(&wrapped), 0);
+}
+
+#[test]
+fn wrapped_sol_the_user_already_held_stays_wrapped() {
+    let (mut pool, _) = native_pool();
+    let user = pool.user(0, LIQUIDITY);
+    pool.wrap_sol(&user, WRAPPED).unwrap();
+    let wrapped = ata(&user.pubkey(), &pool.mint_x);
+
+    // Paid out into the account the user already had, which stays open
+    pool.swap(&user, false, LIQUIDITY / 10, 0).unwrap();
+    let out = pool.x_balance(&user) - WRAPPED;
+    assert!(out > 0);
+    assert!(pool.lamports(&wrapped) > 0);
+
+    // Spent from it like any other token account, nothing else is unwrapped
+    pool.swap(&user, true, WRAPPED / 2, 0).unwrap();
+    assert_eq!(pool.x_balance(&user), WRAPPED + out - WRAPPED / 2);
+    assert!(pool.lamports(&wrapped) > 0);
+}
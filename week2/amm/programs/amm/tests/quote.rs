@@ -0,0 +1,75 @@
+mod common;
+
+use anchor_amm::PoolType;
+use common::{seeded_pool, Pool, LIQUIDITY};
+
+#[test]
+fn price_impact_is_measured_against_the_price_before() {
+    let config = Pool::new().config();
+
+    // Selling x until the pool holds twice as much halves the price of x
+    assert_eq!(
+        config.price_move_bps_at((1_000, 1_000), (2_000, 500), 0),
+        7_500
+    );
+    assert_eq!(
+        config.price_move_bps_at((1_000, 1_000), (1_000, 1_000), 0),
+        0
+    );
+
+    // Both directions count as a move
+    assert!(config.price_move_bps_at((1_000_000, 1_000_000), (999_000, 1_001_001), 0) > 0);
+}
+
+#[test]
+fn stable_pools_measure_price_impact_on_their_curve() {
+    let config = Pool::with_curve(PoolType::StableSwap, 100).config();
+
+    // The same trade barely moves the price near the peg
+    let price_move = config.price_move_bps_at((1_000_000, 1_000_000), (1_100_000, 900_100), 0);
+    assert!(price_move > 0 && price_move < 100, "{price_move}");
+
+    // Far enough off balance the price does give way
+    let price_move = config.price_move_bps_at((1_000_000, 1_000_000), (4_000_000, 250_000), 0);
+    assert!(price_move > 1_000, "{price_move}");
+}
+
+#[test]
+fn empty_pool_has_no_price_impact() {
+    let config = Pool::new().config();
+    assert_eq!(config.price_move_bps_at((0, 0), (1_000, 1_000), 0), 0);
+    assert_eq!(config.price_move_bps_at((1_000, 1_000), (0, 2_000), 0), 0);
+}
+
+#[test]
+fn quotes_match_what_the_instructions_do() {
+    let (mut pool, _) = seeded_pool();
+    let user = pool.user(LIQUIDITY, LIQUIDITY);
+
+    let quote = pool.quote_swap(true, LIQUIDITY / 20, 0);
+    let (x, y) = (pool.x_balance(&user), pool.y_balance(&user));
+    pool.swap(&user, true, LIQUIDITY / 20, 0).unwrap();
+    assert_eq!(x - pool.x_balance(&user), quote.amount_in);
+    assert_eq!(pool.y_balance(&user) - y, quote.amount_out);
+    assert_eq!(pool.reserves(), (quote.reserve_x, quote.reserve_y));
+
+    // The swap left the pool uneven, so the deposit isn't 1:1 either
+    let quote = pool.quote_deposit(LIQUIDITY / 10, LIQUIDITY, LIQUIDITY);
+    let (x, y) = (pool.x_balance(&user), pool.y_balance(&user));
+    pool.deposit(&user, LIQUIDITY / 10, LIQUIDITY, LIQUIDITY)
+        .unwrap();
+    assert_eq!(x - pool.x_balance(&user), quote.amount_x);
+    assert_eq!(y - pool.y_balance(&user), quote.amount_y);
+    assert_eq!(pool.lp_balance(&user), quote.lp_amount);
+
+    let quote = pool.quote_withdraw(LIQUIDITY / 30, 0, 0);
+    let (x, y, lp) = (
+        pool.x_balance(&user),
+        pool.y_balance(&user),
+        pool.lp_balance(&user),
+    );
+    pool.withdraw(&user, LIQUIDITY / 30, 0, 0).unwrap();
+    assert_eq!(pool.x_balance(&user) - x, quote.amount_x);
+    assert_eq!(pool.y_balance(&user) - y, quote.amount_y);
+    assert_eq!(lp - pool.lp_balance(&user), quote.lp_amount);
+}
@@ -0,0 +1,100 @@
+use anchor_amm::curve::{stable_swap, Curve};
+
+const RESERVE: u64 = 1_000_000_000_000;
+
+#[test]
+fn balanced_pool_swaps_close_to_one_to_one() {
+    let curve = Curve::StableSwap { amp: 100 };
+    let out = curve
+        .swap_amount_out(RESERVE, RESERVE, RESERVE, 0, true, 1_000_000_000)
+        .unwrap();
+
+    // 0.1% of the pool moves the price by well under a basis point
+    assert!(out < 1_000_000_000);
+    assert!(out > 999_990_000);
+}
+
+#[test]
+fn stable_swap_beats_constant_product_near_peg() {
+    let amount_in = 50_000_000_000;
+    let stable = Curve::StableSwap { amp: 100 }
+        .swap_amount_out(RESERVE, RESERVE, RESERVE, 30, true, amount_in)
+        .unwrap();
+    let constant_product = Curve::ConstantProduct
+        .swap_amount_out(RESERVE, RESERVE, RESERVE, 30, true, amount_in)
+        .unwrap();
+
+    assert!(stable > constant_product);
+}
+
+#[test]
+fn swaps_never_decrease_the_invariant() {
+    let amp = 200;
+    let (mut x, mut y) = (RESERVE, RESERVE / 2);
+
+    for (i, amount_in) in [1u64, 7_777, 123_456_789, 400_000_000_000, 3]
+        .into_iter()
+        .enumerate()
+    {
+        let is_x = i % 2 == 0;
+        let d_before = stable_swap::compute_d(amp, x, y).unwrap();
+
+        let out = Curve::StableSwap { amp }
+            .swap_amount_out(x, y, RESERVE, 0, is_x, amount_in)
+            .unwrap();
+        if is_x {
+            x += amount_in;
+            y -= out;
+        } else {
+            y += amount_in;
+            x -= out;
+        }
+
+        assert!(stable_swap::compute_d(amp, x, y).unwrap() >= d_before);
+    }
+}
+
+#[test]
+fn exact_out_quote_covers_the_requested_amount() {
+    let curve = Curve::StableSwap { amp: 50 };
+    let amount_out = 25_000_000_000;
+
+    let amount_in = curve
+        .swap_amount_in(RESERVE, RESERVE, 30, true, amount_out)
+        .unwrap();
+    let out = curve
+        .swap_amount_out(RESERVE, RESERVE, RESERVE, 30, true, amount_in)
+        .unwrap();
+
+    assert!(out >= amount_out);
+}
+
+#[test]
+fn spot_price_is_the_marginal_rate_of_a_tiny_swap() {
+    let amp = 100;
+    let q64 = 2f64.powi(64);
+    assert_eq!(
+        stable_swap::spot_price(amp, RESERVE, RESERVE),
+        Some(1 << 64)
+    );
+
+    for (x, y) in [
+        (RESERVE, RESERVE / 2),
+        (RESERVE / 3, RESERVE),
+        (RESERVE, RESERVE / 50),
+    ] {
+        let price = stable_swap::spot_price(amp, x, y).unwrap() as f64 / q64;
+
+        let amount_in = x / 1_000_000;
+        let out = stable_swap::swap_amount_out(amp, x, y, 0, amount_in).unwrap();
+        let rate = out as f64 / amount_in as f64;
+        assert!(
+            (price - rate).abs() < 1e-4 * price,
+            "{x}/{y}: {price} vs {rate}"
+        );
+
+        // Always between the peg and the constant-product price
+        let ratio = y as f64 / x as f64;
+        assert!(price >= ratio.min(1.0) && price <= ratio.max(1.0));
+    }
+}
@@ -0,0 +1,280 @@
+mod common;
+
+use anchor_amm::{error::AmmError, PoolType, MINIMUM_LIQUIDITY};
+use anchor_lang::prelude::Clock;
+use common::{assert_amm_error, seeded_pool, Pool, FEE, LIQUIDITY};
+
+fn k(pool: &Pool) -> u128 {
+    let (x, y) = pool.reserves();
+    x as u128 * y as u128
+}
+
+#[test]
+fn initialize_stores_the_pool() {
+    let pool = Pool::new();
+    let config = pool.config();
+
+    assert_eq!(config.mint_x, pool.mint_x);
+    assert_eq!(config.mint_y, pool.mint_y);
+    assert_eq!(config.fee, FEE);
+    assert!(!config.locked);
+    assert_eq!(pool.reserves(), (0, 0));
+    assert_eq!(pool.lp_supply(), 0);
+}
+
+#[test]
+fn first_deposit_locks_minimum_liquidity() {
+    let (pool, lp) = seeded_pool();
+
+    assert_eq!(pool.reserves(), (LIQUIDITY, LIQUIDITY));
+    assert_eq!(pool.lp_balance(&lp), LIQUIDITY - MINIMUM_LIQUIDITY);
+    assert_eq!(pool.balance(&pool.lp_lock), MINIMUM_LIQUIDITY);
+    assert_eq!(pool.lp_supply(), LIQUIDITY);
+}
+
+#[test]
+fn subsequent_deposit_is_proportional() {
+    let (mut pool, _) = seeded_pool();
+    let user = pool.user(LIQUIDITY, LIQUIDITY);
+
+    pool.deposit(&user, LIQUIDITY / 2, LIQUIDITY, LIQUIDITY)
+        .unwrap();
+
+    assert_eq!(pool.lp_balance(&user), LIQUIDITY / 2);
+    let (x, y) = pool.reserves();
+    assert_eq!(x, y);
+    assert!(x >= LIQUIDITY + LIQUIDITY / 2);
+}
+
+#[test]
+fn donations_cannot_dilute_the_next_depositor() {
+    let mut pool = Pool::new();
+    let attacker = pool.user(2 * LIQUIDITY, 2 * LIQUIDITY);
+    let victim = pool.user(LIQUIDITY, LIQUIDITY);
+
+    // Smallest first deposit the program accepts, then a donation to inflate
+    // what that one LP is worth
+    let seed = MINIMUM_LIQUIDITY + 1;
+    pool.deposit(&attacker, 1, seed, seed).unwrap();
+    assert_eq!(pool.lp_balance(&attacker), 1);
+    pool.donate(&attacker, LIQUIDITY, LIQUIDITY).unwrap();
+
+    // As much LP as the victim's tokens buy at the inflated price
+    let lp = LIQUIDITY as u128 * pool.lp_supply() as u128 / pool.reserves().0 as u128;
+    pool.deposit(&victim, lp as u64, LIQUIDITY, LIQUIDITY)
+        .unwrap();
+    let paid = LIQUIDITY - pool.x_balance(&victim);
+    assert!(paid > LIQUIDITY / 10 * 9);
+
+    pool.withdraw(&victim, pool.lp_balance(&victim), 0, 0)
+        .unwrap();
+    let redeemed = pool.x_balance(&victim) - (LIQUIDITY - paid);
+    assert!(
+        redeemed >= paid / 1000 * 999,
+        "paid {paid}, redeemed {redeemed}"
+    );
+
+    // The locked LP soaks up nearly all of the donation
+    pool.withdraw(&attacker, 1, 0, 0).unwrap();
+    assert!(pool.x_balance(&attacker) < 2 * LIQUIDITY - seed);
+}
+
+#[test]
+fn swaps_in_both_directions() {
+    let (mut pool, _) = seeded_pool();
+    let user = pool.user(10_000_000, 10_000_000);
+
+    pool.swap(&user, true, 1_000_000, 1).unwrap();
+    let bought_y = pool.y_balance(&user) - 10_000_000;
+    assert_eq!(pool.x_balance(&user), 9_000_000);
+    // Just under 1:1 after the fee and the price moving
+    assert!(bought_y < 1_000_000 && bought_y > 990_000);
+
+    pool.swap(&user, false, 1_000_000, 1).unwrap();
+    let bought_x = pool.x_balance(&user) - 9_000_000;
+    assert!(bought_x < 1_010_000 && bought_x > 990_000);
+
+    // A round trip never makes money
+    assert!(pool.x_balance(&user) < 10_000_000 || pool.y_balance(&user) < 10_000_000);
+}
+
+#[test]
+fn swaps_never_decrease_k() {
+    let (mut pool, _) = seeded_pool();
+    let user = pool.user(100_000_000, 100_000_000);
+
+    for (i, amount) in [1, 999, 1_000_000, 50_000_000, 7].into_iter().enumerate() {
+        let before = k(&pool);
+        pool.swap(&user, i % 2 == 0, amount, 0).ok();
+        assert!(k(&pool) >= before);
+    }
+}
+
+#[test]
+fn withdraw_everything_leaves_only_the_locked_liquidity() {
+    let (mut pool, lp) = seeded_pool();
+
+    let lp_amount = pool.lp_balance(&lp);
+    pool.withdraw(&lp, lp_amount, 0, 0).unwrap();
+
+    assert_eq!(pool.lp_balance(&lp), 0);
+    assert_eq!(pool.lp_supply(), MINIMUM_LIQUIDITY);
+    assert_eq!(pool.reserves(), (MINIMUM_LIQUIDITY, MINIMUM_LIQUIDITY));
+    assert_eq!(pool.x_balance(&lp), LIQUIDITY - MINIMUM_LIQUIDITY);
+}
+
+#[test]
+fn slippage_limits_are_enforced() {
+    let (mut pool, lp) = seeded_pool();
+    let user = pool.user(LIQUIDITY, LIQUIDITY);
+
+    // Asking for more out than the curve gives
+    let result = pool.swap(&user, true, 1_000_000, 1_000_000);
+    assert_amm_error(result, AmmError::SlippageExceeded);
+
+    // Capping the deposit below what the pool ratio needs
+    let result = pool.deposit(&user, LIQUIDITY / 2, LIQUIDITY / 4, LIQUIDITY);
+    assert_amm_error(result, AmmError::SlippageExceeded);
+
+    // Demanding more back than the share is worth
+    let lp_amount = pool.lp_balance(&lp);
+    let result = pool.withdraw(&lp, lp_amount, LIQUIDITY, 0);
+    assert_amm_error(result, AmmError::SlippageExceeded);
+
+    // Nothing moved
+    assert_eq!(pool.reserves(), (LIQUIDITY, LIQUIDITY));
+}
+
+#[test]
+fn first_deposit_checks_the_minimum_lp() {
+    let mut pool = Pool::new();
+    let lp = pool.user(LIQUIDITY, LIQUIDITY);
+
+    let result = pool.deposit(&lp, LIQUIDITY, LIQUIDITY, LIQUIDITY);
+    assert_amm_error(result, AmmError::SlippageExceeded);
+}
+
+#[test]
+fn single_sided_withdrawal_leaves_an_unsellable_side_in_the_pool() {
+    let mut pool = Pool::with_curve(PoolType::StableSwap, 100);
+    let lp = pool.user(LIQUIDITY, LIQUIDITY);
+    pool.deposit(&lp, 1, LIQUIDITY, LIQUIDITY).unwrap();
+
+    // Two LP are worth 2 of each side, and selling 2 y pays out 0 x after the fee
+    let result = pool.withdraw_single(&lp, true, 2, 3);
+    assert_amm_error(result, AmmError::SlippageExceeded);
+
+    let x = pool.x_balance(&lp);
+    pool.withdraw_single(&lp, true, 2, 2).unwrap();
+
+    assert_eq!(pool.x_balance(&lp), x + 2);
+    assert_eq!(pool.reserves(), (LIQUIDITY - 2, LIQUIDITY));
+    assert_eq!(pool.lp_supply(), LIQUIDITY - 2);
+}
+
+#[test]
+fn circuit_breaker_caps_trade_size_and_price_move() {
+    let (mut pool, _) = seeded_pool();
+    let user = pool.user(LIQUIDITY, LIQUIDITY);
+    // 1% of the reserve per trade, 2% of price per 10 slots
+    pool.update_circuit_breaker(100, 200, 10).unwrap();
+
+    let result = pool.swap(&user, true, LIQUIDITY / 50, 0);
+    assert_amm_error(result, AmmError::TradeTooLarge);
+
+    // Each of these moves the price about 1.6%, only one fits in a window
+    pool.swap(&user, true, LIQUIDITY / 125, 0).unwrap();
+    let result = pool.swap(&user, true, LIQUIDITY / 125, 0);
+    assert_amm_error(result, AmmError::PriceMoveTooLarge);
+
+    // Trading back towards where the window opened is fine
+    pool.swap(&user, false, LIQUIDITY / 125, 0).unwrap();
+
+    let slot = pool.svm.get_sysvar::<Clock>().slot;
+    pool.svm.warp_to_slot(slot + 10);
+    pool.swap(&user, false, LIQUIDITY / 125, 0).unwrap();
+
+    // Turning the limits off lets the large trade through
+    pool.update_circuit_breaker(0, 0, 0).unwrap();
+    pool.swap(&user, true, LIQUIDITY / 50, 0).unwrap();
+}
+
+#[test]
+fn circuit_breaker_covers_single_sided_deposits() {
+    let (mut pool, _) = seeded_pool();
+    let user = pool.user(LIQUIDITY, LIQUIDITY);
+    pool.update_circuit_breaker(100, 0, 0).unwrap();
+
+    // About half of a zap is swapped, so 4% of the reserve swaps about 2%
+    let result = pool.deposit_single(&user, true, LIQUIDITY / 25, 0);
+    assert_amm_error(result, AmmError::TradeTooLarge);
+
+    pool.deposit_single(&user, true, LIQUIDITY / 100, 0)
+        .unwrap();
+}
+
+#[test]
+fn circuit_breaker_measures_stable_pools_on_their_curve() {
+    let mut pool = Pool::with_curve(PoolType::StableSwap, 100);
+    let lp = pool.user(LIQUIDITY, LIQUIDITY);
+    pool.deposit(&lp, 1, LIQUIDITY, LIQUIDITY).unwrap();
+    let user = pool.user(LIQUIDITY, LIQUIDITY);
+    pool.update_circuit_breaker(0, 100, 10).unwrap();
+
+    // 10% of the reserve would move y / x about 20%, but barely moves the
+    // price on a stable curve
+    pool.swap(&user, true, LIQUIDITY / 10, 0).unwrap();
+
+    // Pushing the pool far enough off balance still trips it
+    let result = pool.swap(&user, true, LIQUIDITY / 2, 0);
+    assert_amm_error(result, AmmError::PriceMoveTooLarge);
+}
+
+#[test]
+fn stable_pool_twap_tracks_the_curve_not_the_reserve_ratio() {
+    let mut pool = Pool::with_curve(PoolType::StableSwap, 100);
+    let lp = pool.user(2 * LIQUIDITY, 2 * LIQUIDITY);
+    // Twice as much x as y, y / x would say 0.5
+    pool.deposit(&lp, 1, LIQUIDITY, LIQUIDITY / 2).unwrap();
+    let start = pool.config();
+
+    let mut clock = pool.svm.get_sysvar::<Clock>();
+    clock.unix_timestamp += 100;
+    pool.svm.set_sysvar(&clock);
+    // Any instruction that touches the oracle closes the interval
+    pool.deposit(&lp, 1_000, LIQUIDITY, LIQUIDITY).unwrap();
+    let end = pool.config();
+
+    let cumulative = end
+        .price_x_cumulative
+        .wrapping_sub(start.price_x_cumulative);
+    let twap = cumulative as f64 / 100.0 / 2f64.powi(64);
+    assert!(twap > 0.99 && twap < 1.0, "twap {twap}");
+}
+
+#[test]
+fn donations_do_not_move_the_twap() {
+    let (mut pool, _) = seeded_pool();
+    pool.observe().unwrap();
+    let start = pool.config();
+
+    let mut clock = pool.svm.get_sysvar::<Clock>();
+    clock.unix_timestamp += 100;
+    pool.svm.set_sysvar(&clock);
+
+    // Nine times the reserve sent straight to the vault would price x at a
+    // tenth of y if it counted for the interval before it
+    let donor = pool.user(9 * LIQUIDITY, 0);
+    pool.donate(&donor, 9 * LIQUIDITY, 0).unwrap();
+    pool.observe().unwrap();
+    let end = pool.config();
+
+    let cumulative = end
+        .price_x_cumulative
+        .wrapping_sub(start.price_x_cumulative);
+    assert_eq!(cumulative, 100u128 << 64);
+    assert_eq!(
+        (end.last_reserve_x, end.last_reserve_y),
+        (LIQUIDITY, LIQUIDITY)
+    );
+}
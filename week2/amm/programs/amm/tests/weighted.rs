@@ -0,0 +1,120 @@
+use anchor_amm::curve::{weighted, Curve};
+
+const RESERVE: u64 = 1_000_000_000_000;
+const ONE: f64 = 18_446_744_073_709_551_616.0;
+
+/// `w_x·ln(x) + w_y·ln(y)`, the log of the invariant
+fn invariant(weight_x: u16, weight_y: u16, x: u64, y: u64) -> f64 {
+    weight_x as f64 * (x as f64).ln() + weight_y as f64 * (y as f64).ln()
+}
+
+#[test]
+fn ln_and_exp_are_accurate() {
+    for x in [0.001, 0.5, 1.0, 1.5, 2.0, 1234.5678] {
+        let ln = weighted::ln((x * ONE) as u128).unwrap() as f64 / ONE;
+        assert!((ln - x.ln()).abs() < 1e-12, "ln({x})");
+    }
+
+    for y in [-20.0, -1.0, -0.3, 0.0, 0.7, 1.0, 30.0] {
+        let exp = weighted::exp((y * ONE) as i128).unwrap() as f64 / ONE;
+        assert!((exp - y.exp()).abs() < 1e-12 * y.exp().max(1.0), "exp({y})");
+    }
+
+    // e^44 doesn't fit in Q64.64
+    assert!(weighted::exp((44.0 * ONE) as i128).is_none());
+}
+
+#[test]
+fn equal_weights_match_the_constant_product() {
+    let amount_in = 50_000_000_000;
+    let weighted = Curve::Weighted {
+        weight_x: 5_000,
+        weight_y: 5_000,
+    }
+    .swap_amount_out(RESERVE, RESERVE, RESERVE, 30, true, amount_in)
+    .unwrap();
+    let constant_product = Curve::ConstantProduct
+        .swap_amount_out(RESERVE, RESERVE, RESERVE, 30, true, amount_in)
+        .unwrap();
+
+    // Rounding only ever goes the pool's way, and not by much
+    assert!(weighted <= constant_product);
+    assert!(constant_product - weighted < 100);
+}
+
+#[test]
+fn eighty_twenty_pool_follows_the_weighted_formula() {
+    let curve = Curve::Weighted {
+        weight_x: 8_000,
+        weight_y: 2_000,
+    };
+    let (x, y) = (RESERVE, RESERVE / 4);
+
+    for (is_x, amount_in) in [
+        (true, 1_000_000u64),
+        (true, 300_000_000_000),
+        (false, 9_999),
+    ] {
+        let out = curve.swap_amount_out(x, y, 0, 0, is_x, amount_in).unwrap();
+
+        let (reserve_in, reserve_out, ratio) = if is_x {
+            (x as f64, y as f64, 4.0)
+        } else {
+            (y as f64, x as f64, 0.25)
+        };
+        let expected =
+            reserve_out * (1.0 - (reserve_in / (reserve_in + amount_in as f64)).powf(ratio));
+
+        assert!(out as f64 <= expected);
+        assert!(out as f64 >= expected * (1.0 - 1e-9) - 1.0);
+    }
+}
+
+#[test]
+fn swaps_never_decrease_the_invariant() {
+    let (weight_x, weight_y) = (8_000, 2_000);
+    let curve = Curve::Weighted { weight_x, weight_y };
+    let (mut x, mut y) = (RESERVE, RESERVE / 3);
+
+    for (i, amount_in) in [1u64, 7_777, 123_456_789, 400_000_000_000, 3]
+        .into_iter()
+        .enumerate()
+    {
+        let is_x = i % 2 == 0;
+        let before = invariant(weight_x, weight_y, x, y);
+
+        let out = curve.swap_amount_out(x, y, 0, 0, is_x, amount_in).unwrap();
+        if is_x {
+            x += amount_in;
+            y -= out;
+        } else {
+            y += amount_in;
+            x -= out;
+        }
+
+        assert!(invariant(weight_x, weight_y, x, y) >= before);
+    }
+}
+
+#[test]
+fn exact_out_pays_at_least_the_exact_price() {
+    let (weight_x, weight_y) = (2_000, 8_000);
+    let curve = Curve::Weighted { weight_x, weight_y };
+    let (x, y) = (RESERVE / 4, RESERVE);
+
+    for (is_x, amount_out) in [(true, 25_000_000_000u64), (false, 1), (false, 1_000_000)] {
+        let (reserve_in, reserve_out) = if is_x { (x, y) } else { (y, x) };
+        let amount_in = curve
+            .swap_amount_in(reserve_in, reserve_out, 0, is_x, amount_out)
+            .unwrap();
+
+        let after = if is_x {
+            (x + amount_in, y - amount_out)
+        } else {
+            (x - amount_out, y + amount_in)
+        };
+        assert!(
+            invariant(weight_x, weight_y, after.0, after.1) >= invariant(weight_x, weight_y, x, y)
+        );
+    }
+}
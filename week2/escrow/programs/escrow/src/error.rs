@@ -1,7 +1,9 @@
 use anchor_lang::prelude::*;
 
 #[error_code]
-pub enum ErrorCode {
-    #[msg("Custom error message")]
-    CustomError,
+pub enum EscrowError {
+    #[msg("Amount must be above zero and at most what the escrow still wants")]
+    InvalidAmount,
+    #[msg("Amount is too small to receive any of mint_a")]
+    AmountTooSmall,
 }
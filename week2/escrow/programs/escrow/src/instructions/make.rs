@@ -2,9 +2,10 @@ use anchor_lang::prelude::*;
 
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{ transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked },
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
 };
 
+use crate::error::EscrowError;
 use crate::state::Escrow;
 
 #[derive(Accounts)]
@@ -60,20 +61,31 @@ pub struct Make<'info> {
 
 impl<'info> Make<'info> {
     // function to initialize the escrow account
-    pub fn init_escrow(&mut self, seed: u64, recieve: u64, bumps: &MakeBumps) -> Result<()> {
+    pub fn init_escrow(
+        &mut self,
+        seed: u64,
+        recieve: u64,
+        deposit: u64,
+        bumps: &MakeBumps,
+    ) -> Result<()> {
+        // both sides are needed to price partial takes
+        require!(recieve > 0 && deposit > 0, EscrowError::InvalidAmount);
+
         // set_inner is used to change the all vaules in single instance
         self.escrow.set_inner(Escrow {
             seed,
             maker: self.maker.key(),
             mint_a: self.mint_a.key(),
             mint_b: self.mint_b.key(),
-            recieve,
+            receive: recieve,
+            deposit,
             bump: bumps.escrow,
         });
         Ok(())
     }
 
-    // function to deposit the tokens into the escrow vault
+    // function to deposit the tokens into the escrow vault. takes are priced on
+    // what actually arrived, which is less than amount for a mint with a transfer fee
     pub fn deposit(&mut self, amount: u64) -> Result<()> {
         // it does the verification before the transaction
         let transfer_accounts = TransferChecked {
@@ -85,8 +97,14 @@ impl<'info> Make<'info> {
 
         let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
 
+        let before = self.vault.amount;
+
         // transfer_checked is used to transfer the tokens from maker to vault more securely
         transfer_checked(cpi_ctx, amount, self.mint_a.decimals)?;
+
+        self.vault.reload()?;
+        self.escrow.deposit = self.vault.amount - before;
+        require!(self.escrow.deposit > 0, EscrowError::InvalidAmount);
         Ok(())
     }
 }
@@ -2,6 +2,6 @@ pub mod make;
 pub mod refund;
 pub mod take;
 
+pub use make::*;
 pub use refund::*;
 pub use take::*;
-pub use make::*;
@@ -2,25 +2,21 @@ use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
     token_interface::{
-        Mint,
-        TokenAccount,
-        TokenInterface,
-        close_account,
-        transfer_checked,
-        CloseAccount,
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
         TransferChecked,
     },
 };
 
+use crate::instructions::harvest_withheld_fees;
 use crate::state::Escrow;
 
 #[derive(Accounts)]
 pub struct Refund<'info> {
-
     #[account(mut)]
     pub maker: Signer<'info>,
 
-    #[account(mint::token_program = token_program)]
+    // writable so fees withheld in the vault can be harvested before closing it
+    #[account(mut, mint::token_program = token_program)]
     pub mint_a: InterfaceAccount<'info, Mint>,
 
     #[account(
@@ -59,14 +55,12 @@ impl<'info> Refund<'info> {
     /// this function is used to refund the tokens to maker and close the escrow account.
     pub fn refund_and_close(&mut self) -> Result<()> {
         // defining the required seeds for the escrow account
-        let seeds: [&[&[u8]]; 1] = [
-            &[
-                b"escrow",
-                self.maker.to_account_info().key.as_ref(),
-                &self.escrow.seed.to_le_bytes()[..],
-                &[self.escrow.bump],
-            ],
-        ];
+        let seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.maker.to_account_info().key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump],
+        ]];
 
         // accounts that are involved in transfe
         let transfer_accounts = TransferChecked {
@@ -79,12 +73,18 @@ impl<'info> Refund<'info> {
         let cpi_ctx = CpiContext::new_with_signer(
             self.token_program.to_account_info(),
             transfer_accounts,
-            &seeds
+            &seeds,
         );
 
         // transfer check for better validation and verification
         transfer_checked(cpi_ctx, self.vault.amount, self.mint_a.decimals)?;
 
+        harvest_withheld_fees(
+            self.token_program.to_account_info(),
+            self.mint_a.to_account_info(),
+            self.vault.to_account_info(),
+        )?;
+
         // closing the escrow account and sending the remaining lamports to maker
         let close_accounts = CloseAccount {
             account: self.vault.to_account_info(), // the vault account to be closed
@@ -95,7 +95,7 @@ impl<'info> Refund<'info> {
         let close_cpi = CpiContext::new_with_signer(
             self.token_program.to_account_info(),
             close_accounts,
-            &seeds
+            &seeds,
         );
 
         close_account(close_cpi)?;
@@ -1,20 +1,29 @@
 use anchor_lang::prelude::*;
 
+use crate::error::EscrowError;
+use crate::state::Escrow;
 use anchor_spl::{
-    associated_token::AssociatedToken, token::{close_account, CloseAccount}, token_interface::{transfer_checked,Mint,TokenAccount,TokenInterface,TransferChecked}
+    associated_token::AssociatedToken,
+    token_2022::spl_token_2022::extension::transfer_fee::TransferFeeConfig,
+    token_interface::{
+        close_account, get_mint_extension_data, harvest_withheld_tokens_to_mint, transfer_checked,
+        CloseAccount, HarvestWithheldTokensToMint, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
 };
-use crate::state::Escrow;
 
 #[derive(Accounts)]
-pub struct Take<'info>{
+pub struct Take<'info> {
     #[account(mut)]
     pub taker: Signer<'info>,
 
     #[account(mut)]
     pub maker: SystemAccount<'info>,
 
-    pub mint_a : InterfaceAccount<'info,Mint>,
-    pub mint_b : InterfaceAccount<'info,Mint>,
+    // writable so fees withheld in the vault can be harvested before closing it
+    #[account(mut)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    pub mint_b: InterfaceAccount<'info, Mint>,
 
     #[account(
         init_if_needed,
@@ -23,35 +32,35 @@ pub struct Take<'info>{
         associated_token::authority = taker,
         associated_token::token_program = token_program,
     )]
-    pub taker_ata_a : Box<InterfaceAccount<'info,TokenAccount>>,
- 
+    pub taker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
     #[account(
         mut,
         associated_token::mint = mint_b,
         associated_token::authority = taker,
         associated_token::token_program = token_program,
     )]
-    pub taker_ata_b : Box<InterfaceAccount<'info,TokenAccount>>,
+    pub taker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         init_if_needed,
         payer = taker,
-        associated_token::mint = mint_a,
+        associated_token::mint = mint_b,
         associated_token::authority = maker,
         associated_token::token_program = token_program,
     )]
-    pub maker_ata_b : Box<InterfaceAccount<'info,TokenAccount>>,
+    pub maker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
 
+    // only closed once the last of it is taken, see withdraw
     #[account(
         mut,
-        close = maker,
         has_one = maker,
         has_one = mint_a,
         has_one = mint_b,
-        seeds = [b"escrow",maker.key().as_ref(),escrow.seed.to_be_bytes().as_ref()],
+        seeds = [b"escrow",maker.key().as_ref(),escrow.seed.to_le_bytes().as_ref()],
         bump = escrow.bump,
     )]
-    pub escrow : Account<'info,Escrow>,
+    pub escrow: Account<'info, Escrow>,
 
     #[account(
         mut,
@@ -59,56 +68,116 @@ pub struct Take<'info>{
         associated_token::authority = escrow,
         associated_token::token_program = token_program,
     )]
-    pub vault : InterfaceAccount<'info,TokenAccount>,
+    pub vault: InterfaceAccount<'info, TokenAccount>,
 
-    pub associated_token_program : Program<'info,AssociatedToken>,
-    pub token_program : Interface<'info,TokenInterface>,
-    pub system_program : Program<'info,System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
 }
 
+impl<'info> Take<'info> {
+    // pays amount of mint_b to the maker and returns the mint_a it buys, at the
+    // price the maker set. rounds down, the last take gets whatever is left
+    pub fn deposit(&mut self, amount: u64) -> Result<u64> {
+        require!(
+            amount > 0 && amount <= self.escrow.receive,
+            EscrowError::InvalidAmount
+        );
+
+        let amount_a = if amount == self.escrow.receive {
+            self.escrow.deposit
+        } else {
+            (amount as u128 * self.escrow.deposit as u128 / self.escrow.receive as u128) as u64
+        };
+        require!(amount_a > 0, EscrowError::AmountTooSmall);
+
+        self.escrow.receive -= amount;
+        self.escrow.deposit -= amount_a;
 
-impl <'info>Take<'info> {
-    pub fn deposit(&mut self) -> Result<()>{
-        let transfer_accounts = TransferChecked{
+        let transfer_accounts = TransferChecked {
             from: self.taker_ata_b.to_account_info(),
             mint: self.mint_b.to_account_info(),
-            to:self.maker_ata_b.to_account_info(),
-            authority:self.taker.to_account_info(),
+            to: self.maker_ata_b.to_account_info(),
+            authority: self.taker.to_account_info(),
         };
 
         let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
 
-    transfer_checked(cpi_ctx, self.escrow.receive, self.mint_b.decimals)
+        transfer_checked(cpi_ctx, amount, self.mint_b.decimals)?;
+
+        Ok(amount_a)
     }
 
-    pub fn withdraw_and_close_vault(&mut self) -> Result<()>{
+    // sends amount_a to the taker, and closes the vault and the escrow once fully filled
+    pub fn withdraw(&mut self, amount_a: u64) -> Result<()> {
+        let filled = self.escrow.receive == 0;
 
-       let signer_seeds: [&[&[u8]]; 1] = [&[
+        let signer_seeds: [&[&[u8]]; 1] = [&[
             b"escrow",
             self.maker.to_account_info().key.as_ref(),
             &self.escrow.seed.to_le_bytes()[..],
             &[self.escrow.bump],
         ]];
 
-        let transfer_accounts = TransferChecked{
+        let transfer_accounts = TransferChecked {
             from: self.vault.to_account_info(),
             mint: self.mint_a.to_account_info(),
             to: self.taker_ata_a.to_account_info(),
             authority: self.escrow.to_account_info(),
         };
 
-        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), transfer_accounts, &signer_seeds,);
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            transfer_accounts,
+            &signer_seeds,
+        );
 
-        transfer_checked(cpi_ctx, self.vault.amount, self.mint_a.decimals)?;
+        // the whole vault on the last take, so it is empty before closing
+        let amount = if filled { self.vault.amount } else { amount_a };
+        transfer_checked(cpi_ctx, amount, self.mint_a.decimals)?;
 
-        let accounts = CloseAccount{
+        if !filled {
+            return Ok(());
+        }
+
+        harvest_withheld_fees(
+            self.token_program.to_account_info(),
+            self.mint_a.to_account_info(),
+            self.vault.to_account_info(),
+        )?;
+
+        let accounts = CloseAccount {
             account: self.vault.to_account_info(),
             destination: self.taker.to_account_info(),
-            authority:self.escrow.to_account_info(),
+            authority: self.escrow.to_account_info(),
         };
 
-        let ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), accounts, &signer_seeds);
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds,
+        );
 
-        close_account(ctx)
+        close_account(ctx)?;
+
+        self.escrow.close(self.maker.to_account_info())
     }
-}
\ No newline at end of file
+}
+
+// token-2022 won't close an account that still holds withheld transfer fees, so
+// a vault of a mint with a transfer fee moves them to the mint first. anyone can
+pub fn harvest_withheld_fees<'info>(
+    token_program: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    vault: AccountInfo<'info>,
+) -> Result<()> {
+    if get_mint_extension_data::<TransferFeeConfig>(&mint).is_err() {
+        return Ok(());
+    }
+
+    let accounts = HarvestWithheldTokensToMint {
+        token_program_id: token_program.clone(),
+        mint,
+    };
+    harvest_withheld_tokens_to_mint(CpiContext::new(token_program, accounts), vec![vault])
+}
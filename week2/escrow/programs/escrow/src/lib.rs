@@ -1,53 +1,40 @@
 #![allow(unexpected_cfgs)]
 #![allow(deprecated)]
-use anchor_lang::prelude::*;
-
+pub mod constants;
+pub mod error;
 pub mod instructions;
 pub mod state;
 
+use anchor_lang::prelude::*;
+
+pub use constants::*;
 pub use instructions::*;
+pub use state::*;
 
-declare_id!("CwgCa5b6vwB5DLRdtAc8rQ9gFhqUvBEoT5mk1Uh3zLWd");
+declare_id!("AYD185bxNKvbhSgziSZRCXb19T5CVsf4NDvpavr26Mws");
 
 #[program]
 pub mod escrow {
     use super::*;
 
-    pub fn initialize(ctx: Context<Make>,seed:u64,recieve:u64,deposit_amt:u64) -> Result<()> {
-        ctx.accounts.init_escrow(seed, recieve, &ctx.bumps)?;
+    pub fn initialize(ctx: Context<Make>, seed: u64, recieve: u64, deposit_amt: u64) -> Result<()> {
+        ctx.accounts
+            .init_escrow(seed, recieve, deposit_amt, &ctx.bumps)?;
         ctx.accounts.deposit(deposit_amt)?;
 
         Ok(())
     }
 
-    pub fn refund(ctx: Context<Refund>)->Result<()>{
+    pub fn refund(ctx: Context<Refund>) -> Result<()> {
         msg!("RefundEscrow instruction called");
         ctx.accounts.refund_and_close()?;
         Ok(())
     }
 
-    pub fn take(ctx:Context<Take>,amount:u64)->Result<()>{
-        ctx.accounts.transfer_to_maker(amount)?;
-        ctx.accounts.take_and_close()?;
+    // amount is how much of mint_b the taker pays, they get the pro-rata share of mint_a
+    pub fn take(ctx: Context<Take>, amount: u64) -> Result<()> {
+        let amount_a = ctx.accounts.deposit(amount)?;
+        ctx.accounts.withdraw(amount_a)?;
         Ok(())
     }
-}pub mod constants;
-pub mod error;
-pub mod instructions;
-pub mod state;
-
-use anchor_lang::prelude::*;
-
-pub use constants::*;
-pub use instructions::*;
-pub use state::*;
- 
-declare_id!("AYD185bxNKvbhSgziSZRCXb19T5CVsf4NDvpavr26Mws");
-
-#[program]
-pub mod escrow {
-    use super::*;
-
-
 }
-    
@@ -3,13 +3,14 @@ use anchor_lang::prelude::*;
 #[account]
 #[derive(InitSpace)]
 
-pub struct Escrow{
-pub seed: u64,
-pub maker: Pubkey,
-pub mint_a:Pubkey,
-pub mint_b: Pubkey,
-pub receiver:Pubkey,
-pub receive: u64,
-pub bump: u8,
-
-}
\ No newline at end of file
+pub struct Escrow {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    // mint_b the maker still wants, goes down with every partial take
+    pub receive: u64,
+    // mint_a still left in the vault for takers
+    pub deposit: u64,
+    pub bump: u8,
+}
@@ -0,0 +1,430 @@
+//! Runs make, take and refund against the compiled program. Build it first
+//! with `anchor build`, the tests load `target/deploy/escrow.so`.
+#![allow(deprecated)]
+
+use anchor_lang::{
+    prelude::Pubkey,
+    solana_program::{
+        instruction::{Instruction, InstructionError},
+        program_pack::Pack,
+        system_instruction, system_program,
+    },
+    AccountDeserialize, InstructionData, ToAccountMetas,
+};
+use anchor_spl::{
+    associated_token::{
+        self, get_associated_token_address_with_program_id,
+        spl_associated_token_account::instruction::create_associated_token_account,
+    },
+    token::spl_token,
+    token_2022::spl_token_2022::{
+        self,
+        extension::{
+            transfer_fee::instruction::initialize_transfer_fee_config, ExtensionType,
+            StateWithExtensions,
+        },
+    },
+};
+use escrow::{accounts, error::EscrowError, instruction, Escrow};
+use litesvm::{types::TransactionResult, LiteSVM};
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+const PROGRAM_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../target/deploy/escrow.so");
+
+const SEED: u64 = 7;
+const BALANCE: u64 = 1_000_000;
+
+/// A maker and a taker holding `BALANCE` of mint_a and mint_b respectively
+struct Harness {
+    svm: LiteSVM,
+    maker: Keypair,
+    taker: Keypair,
+    token_program: Pubkey,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+    escrow: Pubkey,
+    vault: Pubkey,
+}
+
+impl Harness {
+    fn new() -> Self {
+        Self::with_mints(spl_token::ID, None)
+    }
+
+    /// Token-2022 mints, mint_a charging `fee_bps` on every transfer
+    fn with_transfer_fee(fee_bps: u16) -> Self {
+        Self::with_mints(spl_token_2022::ID, Some(fee_bps))
+    }
+
+    fn with_mints(token_program: Pubkey, fee_bps: Option<u16>) -> Self {
+        let mut svm = LiteSVM::new();
+        svm.add_program_from_file(escrow::ID, PROGRAM_PATH)
+            .expect("run `anchor build` before the Rust tests");
+
+        let maker = Keypair::new();
+        let taker = Keypair::new();
+        svm.airdrop(&maker.pubkey(), 10_000_000_000).unwrap();
+        svm.airdrop(&taker.pubkey(), 10_000_000_000).unwrap();
+
+        let escrow = Pubkey::find_program_address(
+            &[
+                b"escrow",
+                maker.pubkey().as_ref(),
+                SEED.to_le_bytes().as_ref(),
+            ],
+            &escrow::ID,
+        )
+        .0;
+
+        let mut harness = Harness {
+            svm,
+            maker,
+            taker,
+            token_program,
+            mint_a: Pubkey::default(),
+            mint_b: Pubkey::default(),
+            escrow,
+            vault: Pubkey::default(),
+        };
+        harness.mint_a = harness.create_mint(fee_bps);
+        harness.mint_b = harness.create_mint(None);
+        harness.vault = harness.ata(&escrow, &harness.mint_a);
+
+        let (maker, taker) = (harness.maker.pubkey(), harness.taker.pubkey());
+        harness.fund(&maker, harness.mint_a);
+        harness.fund(&taker, harness.mint_b);
+        harness
+    }
+
+    /// Escrows `deposit` of mint_a for `receive` of mint_b
+    fn make(&mut self, receive: u64, deposit: u64) -> TransactionResult {
+        let accounts = accounts::Make {
+            maker: self.maker.pubkey(),
+            mint_a: self.mint_a,
+            mint_b: self.mint_b,
+            maker_ata_a: self.ata(&self.maker.pubkey(), &self.mint_a),
+            escrow: self.escrow,
+            vault: self.vault,
+            associated_token_program: associated_token::ID,
+            token_program: self.token_program,
+            system_program: system_program::ID,
+        };
+        let data = instruction::Initialize {
+            seed: SEED,
+            recieve: receive,
+            deposit_amt: deposit,
+        };
+
+        let maker = self.maker.insecure_clone();
+        self.send(accounts.to_account_metas(None), data.data(), &maker)
+    }
+
+    /// Pays `amount` of mint_b for its share of the escrow
+    fn take(&mut self, amount: u64) -> TransactionResult {
+        let accounts = accounts::Take {
+            taker: self.taker.pubkey(),
+            maker: self.maker.pubkey(),
+            mint_a: self.mint_a,
+            mint_b: self.mint_b,
+            taker_ata_a: self.ata(&self.taker.pubkey(), &self.mint_a),
+            taker_ata_b: self.ata(&self.taker.pubkey(), &self.mint_b),
+            maker_ata_b: self.ata(&self.maker.pubkey(), &self.mint_b),
+            escrow: self.escrow,
+            vault: self.vault,
+            associated_token_program: associated_token::ID,
+            token_program: self.token_program,
+            system_program: system_program::ID,
+        };
+        let data = instruction::Take { amount };
+
+        let taker = self.taker.insecure_clone();
+        self.send(accounts.to_account_metas(None), data.data(), &taker)
+    }
+
+    fn refund(&mut self) -> TransactionResult {
+        let accounts = accounts::Refund {
+            maker: self.maker.pubkey(),
+            mint_a: self.mint_a,
+            maker_ata_a: self.ata(&self.maker.pubkey(), &self.mint_a),
+            escrow: self.escrow,
+            vault: self.vault,
+            associated_token_program: associated_token::ID,
+            token_program: self.token_program,
+            system_program: system_program::ID,
+        };
+        let data = instruction::Refund {};
+
+        let maker = self.maker.insecure_clone();
+        self.send(accounts.to_account_metas(None), data.data(), &maker)
+    }
+
+    /// The escrow's state, `None` once it is closed
+    fn escrow(&self) -> Option<Escrow> {
+        self.svm
+            .get_account(&self.escrow)
+            .filter(|account| !account.data.is_empty())
+            .map(|account| Escrow::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    fn vault_exists(&self) -> bool {
+        self.svm
+            .get_account(&self.vault)
+            .is_some_and(|account| !account.data.is_empty())
+    }
+
+    /// Token balance, zero for accounts that don't exist yet
+    fn balance(&self, owner: &Pubkey, mint: &Pubkey) -> u64 {
+        self.svm
+            .get_account(&self.ata(owner, mint))
+            .filter(|account| !account.data.is_empty())
+            .map(|account| {
+                StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data)
+                    .unwrap()
+                    .base
+                    .amount
+            })
+            .unwrap_or(0)
+    }
+
+    fn taker_a(&self) -> u64 {
+        self.balance(&self.taker.pubkey(), &self.mint_a)
+    }
+
+    fn maker_a(&self) -> u64 {
+        self.balance(&self.maker.pubkey(), &self.mint_a)
+    }
+
+    fn maker_b(&self) -> u64 {
+        self.balance(&self.maker.pubkey(), &self.mint_b)
+    }
+
+    /// A mint under the harness's token program, with a transfer fee of
+    /// `fee_bps` if one is given
+    fn create_mint(&mut self, fee_bps: Option<u16>) -> Pubkey {
+        let mint = Keypair::new();
+        let len = match fee_bps {
+            Some(_) => ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+                ExtensionType::TransferFeeConfig,
+            ])
+            .unwrap(),
+            None => spl_token::state::Mint::LEN,
+        };
+        let rent = self.svm.minimum_balance_for_rent_exemption(len);
+
+        let mut ixs = vec![system_instruction::create_account(
+            &self.maker.pubkey(),
+            &mint.pubkey(),
+            rent,
+            len as u64,
+            &self.token_program,
+        )];
+        if let Some(fee_bps) = fee_bps {
+            ixs.push(
+                initialize_transfer_fee_config(
+                    &self.token_program,
+                    &mint.pubkey(),
+                    None,
+                    None,
+                    fee_bps,
+                    u64::MAX,
+                )
+                .unwrap(),
+            );
+        }
+        ixs.push(
+            spl_token_2022::instruction::initialize_mint2(
+                &self.token_program,
+                &mint.pubkey(),
+                &self.maker.pubkey(),
+                None,
+                6,
+            )
+            .unwrap(),
+        );
+
+        let maker = self.maker.insecure_clone();
+        self.send_instructions(&ixs, &[&maker, &mint]).unwrap();
+
+        mint.pubkey()
+    }
+
+    /// Gives `owner` an associated account holding `BALANCE` of `mint`
+    fn fund(&mut self, owner: &Pubkey, mint: Pubkey) {
+        let create = create_associated_token_account(
+            &self.maker.pubkey(),
+            owner,
+            &mint,
+            &self.token_program,
+        );
+        let mint_to = spl_token_2022::instruction::mint_to(
+            &self.token_program,
+            &mint,
+            &self.ata(owner, &mint),
+            &self.maker.pubkey(),
+            &[],
+            BALANCE,
+        )
+        .unwrap();
+
+        let maker = self.maker.insecure_clone();
+        self.send_instructions(&[create, mint_to], &[&maker])
+            .unwrap();
+    }
+
+    fn ata(&self, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, mint, &self.token_program)
+    }
+
+    fn send(
+        &mut self,
+        accounts: Vec<anchor_lang::prelude::AccountMeta>,
+        data: Vec<u8>,
+        signer: &Keypair,
+    ) -> TransactionResult {
+        let ix = Instruction {
+            program_id: escrow::ID,
+            accounts,
+            data,
+        };
+
+        self.send_instructions(&[ix], &[signer])
+    }
+
+    fn send_instructions(
+        &mut self,
+        ixs: &[Instruction],
+        signers: &[&Keypair],
+    ) -> TransactionResult {
+        let mut all = vec![ComputeBudgetInstruction::set_compute_unit_limit(1_400_000)];
+        all.extend_from_slice(ixs);
+
+        let tx = Transaction::new_signed_with_payer(
+            &all,
+            Some(&signers[0].pubkey()),
+            signers,
+            self.svm.latest_blockhash(),
+        );
+        let result = self.svm.send_transaction(tx);
+
+        // Identical transactions are otherwise rejected as already processed
+        self.svm.expire_blockhash();
+        result
+    }
+}
+
+/// Fails unless the transaction was rejected by the program with `error`
+fn assert_escrow_error(result: TransactionResult, error: EscrowError) {
+    let failed = result.expect_err("transaction should have failed");
+    assert_eq!(
+        failed.err,
+        TransactionError::InstructionError(1, InstructionError::Custom(error.into())),
+        "{}",
+        failed.meta.pretty_logs()
+    );
+}
+
+#[test]
+fn partial_takes_pay_the_makers_price_and_the_last_gets_the_dust() {
+    let mut harness = Harness::new();
+    harness.make(30, 100).unwrap();
+
+    // 10 of 30 buys a third of 100, rounded down
+    harness.take(10).unwrap();
+    assert_eq!(harness.taker_a(), 33);
+
+    // The price is now 67 for 20, still rounded down
+    harness.take(10).unwrap();
+    assert_eq!(harness.taker_a(), 66);
+
+    // The last take empties the vault, rounding dust included
+    harness.take(10).unwrap();
+    assert_eq!(harness.taker_a(), 100);
+    assert_eq!(harness.maker_b(), 30);
+}
+
+#[test]
+fn escrow_stays_open_until_the_last_take() {
+    let mut harness = Harness::new();
+    harness.make(30, 100).unwrap();
+
+    harness.take(10).unwrap();
+    let escrow = harness
+        .escrow()
+        .expect("a partial take keeps the escrow open");
+    assert_eq!((escrow.receive, escrow.deposit), (20, 67));
+    assert!(harness.vault_exists());
+
+    harness.take(20).unwrap();
+    assert!(harness.escrow().is_none());
+    assert!(!harness.vault_exists());
+}
+
+#[test]
+fn takes_need_an_amount_the_escrow_can_pay() {
+    let mut harness = Harness::new();
+    harness.make(100, 10).unwrap();
+
+    // 5 of 100 is worth half a token of mint_a
+    assert_escrow_error(harness.take(5), EscrowError::AmountTooSmall);
+    assert_escrow_error(harness.take(0), EscrowError::InvalidAmount);
+    assert_escrow_error(harness.take(101), EscrowError::InvalidAmount);
+
+    // Nothing moved
+    assert_eq!(harness.taker_a(), 0);
+    assert_eq!(harness.maker_b(), 0);
+    let escrow = harness.escrow().unwrap();
+    assert_eq!((escrow.receive, escrow.deposit), (100, 10));
+}
+
+#[test]
+fn refund_after_a_partial_fill_returns_what_is_left() {
+    let mut harness = Harness::new();
+    harness.make(30, 100).unwrap();
+    assert_eq!(harness.maker_a(), BALANCE - 100);
+
+    harness.take(10).unwrap();
+    harness.refund().unwrap();
+
+    assert_eq!(harness.maker_a(), BALANCE - 33);
+    assert_eq!(harness.maker_b(), 10);
+    assert!(harness.escrow().is_none());
+    assert!(!harness.vault_exists());
+}
+
+#[test]
+fn takes_are_priced_on_what_reached_the_vault() {
+    // 1% of every mint_a transfer goes to the fee
+    let mut harness = Harness::with_transfer_fee(100);
+    harness.make(30, 1_000).unwrap();
+
+    let escrow = harness.escrow().unwrap();
+    assert_eq!(escrow.deposit, 990);
+
+    // A third of what is there, not of what the maker sent
+    harness.take(10).unwrap();
+    let escrow = harness.escrow().unwrap();
+    assert_eq!((escrow.receive, escrow.deposit), (20, 660));
+    assert_eq!(harness.taker_a(), 330 - 4);
+
+    // The last take still empties the vault
+    harness.take(20).unwrap();
+    assert!(harness.escrow().is_none());
+    assert_eq!(harness.taker_a(), 330 - 4 + 660 - 7);
+}
+
+#[test]
+fn refund_closes_a_vault_holding_withheld_fees() {
+    let mut harness = Harness::with_transfer_fee(100);
+    harness.make(30, 1_000).unwrap();
+    harness.take(10).unwrap();
+
+    // 660 left, less the 1% fee on the way out
+    harness.refund().unwrap();
+    assert_eq!(harness.maker_a(), BALANCE - 1_000 + 660 - 7);
+    assert!(harness.escrow().is_none());
+    assert!(!harness.vault_exists());
+}
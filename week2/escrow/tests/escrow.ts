@@ -1,16 +1,183 @@
 import * as anchor from "@coral-xyz/anchor";
-import { Program } from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import {
+  createMint,
+  getAccount,
+  getAssociatedTokenAddressSync,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+  TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
+import { Keypair, LAMPORTS_PER_SOL, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
 import { Escrow } from "../target/types/escrow";
 
+const BALANCE = 1_000_000;
+
 describe("escrow", () => {
-  // Configure the client to use the local cluster.
-  anchor.setProvider(anchor.AnchorProvider.env());
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const connection = provider.connection;
 
   const program = anchor.workspace.escrow as Program<Escrow>;
 
-  it("Is initialized!", async () => {
-    // Add your test here.
-    const tx = await program.methods.initialize().rpc();
-    console.log("Your transaction signature", tx);
+  const maker = Keypair.generate();
+  const taker = Keypair.generate();
+  let mintA: PublicKey;
+  let mintB: PublicKey;
+  let seed = 0;
+
+  const balance = async (owner: PublicKey, mint: PublicKey) => {
+    try {
+      const account = await getAccount(
+        connection,
+        getAssociatedTokenAddressSync(mint, owner)
+      );
+      return Number(account.amount);
+    } catch {
+      return 0;
+    }
+  };
+
+  const escrowFor = (seed: number) =>
+    PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("escrow"),
+        maker.publicKey.toBuffer(),
+        new BN(seed).toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    )[0];
+
+  // A fresh escrow of `deposit` mint_a for `receive` mint_b
+  const make = async (receive: number, deposit: number) => {
+    seed += 1;
+    const escrow = escrowFor(seed);
+    await program.methods
+      .initialize(new BN(seed), new BN(receive), new BN(deposit))
+      .accounts({
+        maker: maker.publicKey,
+        mintA,
+        mintB,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([maker])
+      .rpc();
+    return {
+      escrow,
+      vault: getAssociatedTokenAddressSync(mintA, escrow, true),
+    };
+  };
+
+  const take = (escrow: PublicKey, amount: number) =>
+    program.methods
+      .take(new BN(amount))
+      .accountsPartial({
+        taker: taker.publicKey,
+        maker: maker.publicKey,
+        mintA,
+        mintB,
+        escrow,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([taker])
+      .rpc();
+
+  const expectError = async (promise: Promise<unknown>, code: string) => {
+    try {
+      await promise;
+      expect.fail(`expected ${code}`);
+    } catch (err) {
+      expect(err).to.be.instanceOf(anchor.AnchorError);
+      expect((err as anchor.AnchorError).error.errorCode.code).to.equal(code);
+    }
+  };
+
+  before(async () => {
+    for (const wallet of [maker, taker]) {
+      const sig = await connection.requestAirdrop(
+        wallet.publicKey,
+        10 * LAMPORTS_PER_SOL
+      );
+      await connection.confirmTransaction(sig);
+    }
+
+    mintA = await createMint(connection, maker, maker.publicKey, null, 6);
+    mintB = await createMint(connection, maker, maker.publicKey, null, 6);
+
+    const makerA = await getOrCreateAssociatedTokenAccount(
+      connection,
+      maker,
+      mintA,
+      maker.publicKey
+    );
+    await mintTo(connection, maker, mintA, makerA.address, maker, BALANCE);
+    const takerB = await getOrCreateAssociatedTokenAccount(
+      connection,
+      taker,
+      mintB,
+      taker.publicKey
+    );
+    await mintTo(connection, maker, mintB, takerB.address, maker, BALANCE);
+  });
+
+  it("pays the maker's price on partial takes and the last take gets the dust", async () => {
+    const { escrow, vault } = await make(30, 100);
+    const takerA = await balance(taker.publicKey, mintA);
+    const makerB = await balance(maker.publicKey, mintB);
+
+    // 10 of 30 buys a third of 100, rounded down
+    await take(escrow, 10);
+    expect((await balance(taker.publicKey, mintA)) - takerA).to.equal(33);
+
+    // The escrow stays open with what is left
+    const state = await program.account.escrow.fetch(escrow);
+    expect(state.receive.toNumber()).to.equal(20);
+    expect(state.deposit.toNumber()).to.equal(67);
+
+    // The price is now 67 for 20, still rounded down
+    await take(escrow, 10);
+    expect((await balance(taker.publicKey, mintA)) - takerA).to.equal(66);
+
+    // The last take empties the vault and closes both accounts
+    await take(escrow, 10);
+    expect((await balance(taker.publicKey, mintA)) - takerA).to.equal(100);
+    expect((await balance(maker.publicKey, mintB)) - makerB).to.equal(30);
+    expect(await connection.getAccountInfo(escrow)).to.be.null;
+    expect(await connection.getAccountInfo(vault)).to.be.null;
+  });
+
+  it("rejects takes the escrow can't pay for", async () => {
+    const { escrow } = await make(100, 10);
+
+    // 5 of 100 is worth half a token of mint_a
+    await expectError(take(escrow, 5), "AmountTooSmall");
+    await expectError(take(escrow, 0), "InvalidAmount");
+    await expectError(take(escrow, 101), "InvalidAmount");
+
+    const state = await program.account.escrow.fetch(escrow);
+    expect(state.receive.toNumber()).to.equal(100);
+    expect(state.deposit.toNumber()).to.equal(10);
+  });
+
+  it("refunds what is left after a partial fill", async () => {
+    const { escrow, vault } = await make(30, 100);
+    const makerA = await balance(maker.publicKey, mintA);
+
+    await take(escrow, 10);
+    await program.methods
+      .refund()
+      .accountsPartial({
+        maker: maker.publicKey,
+        mintA,
+        escrow,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([maker])
+      .rpc();
+
+    expect((await balance(maker.publicKey, mintA)) - makerA).to.equal(67);
+    expect(await connection.getAccountInfo(escrow)).to.be.null;
+    expect(await connection.getAccountInfo(vault)).to.be.null;
   });
 });